@@ -15,25 +15,32 @@ use gpui::*;
 use crate::{
     assets::Assets,
     commands::RootCommands,
+    components::list::frecency::FrecencyModel,
     hotkey::HotkeyManager,
     ipc::server::start_server,
     theme::Theme,
-    window::{Window, WindowStyle},
+    window::{Window, WindowPlacement, WindowSettings, WindowStyle},
     workspace::Workspace,
 };
 
 pub fn run_app(listener: UnixListener, app: gpui::App) {
     app.with_assets(Assets).run(move |cx: &mut AppContext| {
         Theme::init(cx);
-        // TODO: This still only works for a single display
-        let bounds = cx.displays().first().map(|d| d.bounds()).unwrap_or(Bounds {
+        FrecencyModel::init(cx);
+        let display = WindowSettings::display(cx).or_else(|| cx.displays().first().cloned());
+        let bounds = display.as_ref().map(|d| d.bounds()).unwrap_or(Bounds {
             origin: Point::new(Pixels::from(0.0), Pixels::from(0.0)),
             size: Size {
                 width: Pixels::from(1920.0),
                 height: Pixels::from(1080.0),
             },
         });
-        let _ = cx.open_window(WindowStyle::Main.options(bounds), |cx| {
+        let mut options = WindowStyle::Main.options(bounds);
+        options.display_id = display.map(|d| d.id());
+        WindowPlacement::restore(&mut options, cx);
+        let _ = cx.open_window(options, |cx| {
+            // Lets the platform layer find the window to move it between displays
+            cx.set_window_title("Loungy");
             let theme = cx.global::<Theme>();
             cx.set_background_appearance(WindowBackgroundAppearance::from(
                 theme.window_background.clone().unwrap_or_default(),
@@ -13,6 +13,7 @@ use std::{fs, time::Duration};
 
 use async_std::channel::Sender;
 use bonsaidb::core::schema::SerializedCollection;
+use futures::FutureExt;
 use gpui::*;
 use log::error;
 
@@ -24,15 +25,16 @@ use crate::{
         list::{Accessory, Item, ItemBuilder, ListBuilder, ListItem},
         shared::{Icon, Img},
     },
+    platform::keychain_delete,
     state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
 };
 
-use super::list::{db, BitwardenAccount};
+use super::list::{db, BitwardenAccount, KEYCHAIN_SERVICE};
 
 #[derive(Clone)]
 pub(super) struct BitwardenPasswordPromptBuilder {
     pub(super) account: BitwardenAccount,
-    pub(super) password: Sender<(String, bool)>,
+    pub(super) password: Sender<(String, bool, bool)>,
 }
 
 command!(BitwardenPasswordPromptBuilder);
@@ -56,30 +58,23 @@ impl StateViewBuilder for BitwardenPasswordPromptBuilder {
                 Input::new(
                     "remember_password",
                     "Remember Password?",
-                    InputKind::TextField {
-                        placeholder: "(y)es|(n)o".to_string(),
-                        value: "no".to_string(),
-                        validate: Some(|v| match v.to_lowercase().as_str() {
-                            "y" | "yes" | "n" | "no" => None,
-                            _ => Some("Invalid response"),
-                        }),
-                        password: false,
-                    },
+                    InputKind::Toggle { value: false },
+                    cx,
+                ),
+                Input::new(
+                    "biometric",
+                    "Unlock With Touch ID?",
+                    InputKind::Toggle { value: false },
                     cx,
                 ),
             ],
             move |values, _, cx| {
                 let password = password.clone();
                 cx.spawn(|mut cx| async move {
-                    let remember = matches!(
-                        values["remember_password"]
-                            .value::<String>()
-                            .to_lowercase()
-                            .as_str(),
-                        "y" | "yes"
-                    );
+                    let remember = values["remember_password"].value::<bool>();
+                    let biometric = values["biometric"].value::<bool>();
                     if password
-                        .send((values["password"].value::<String>(), remember))
+                        .send((values["password"].value::<String>(), remember, biometric))
                         .await
                         .is_err()
                     {
@@ -104,66 +99,129 @@ impl StateViewBuilder for BitwardenPasswordPromptBuilder {
 impl EventEmitter<Self> for BitwardenPasswordPromptBuilder {}
 
 #[derive(Clone)]
-pub struct BitwardenAccountFormBuilder;
+pub struct BitwardenAccountFormBuilder {
+    pub(super) account: Option<BitwardenAccount>,
+}
 command!(BitwardenAccountFormBuilder);
 impl StateViewBuilder for BitwardenAccountFormBuilder {
     fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let existing = self.account.clone();
+        let mut inputs = vec![Input::new(
+            "instance",
+            "Instance URL",
+            InputKind::TextField {
+                placeholder: "Enter the bitwarden instance URL...".to_string(),
+                value: existing
+                    .as_ref()
+                    .map(|account| account.instance.clone())
+                    .unwrap_or("https://bitwarden.com".to_string()),
+                validate: Some(|v| {
+                    if v.is_empty() {
+                        return Some("Instance URL is required");
+                    };
+                    if url::Url::parse(v).is_err() {
+                        return Some("Invalid URL");
+                    }
+                    None
+                }),
+                password: false,
+            },
+            cx,
+        )
+        .async_validate(|v| {
+            async move {
+                let reachable = reqwest::get(&v).await.is_ok();
+                (!reachable).then(|| "Instance is not reachable".to_string())
+            }
+            .boxed_local()
+        })];
+        // The identifier is the natural id, so it can't be changed once created
+        if existing.is_none() {
+            inputs.push(Input::new(
+                "id",
+                "Identifier",
+                InputKind::TextField {
+                    placeholder: "Enter an account identifier...".to_string(),
+                    value: "".to_string(),
+                    validate: Some(|v| v.is_empty().then_some("Identifier is required")),
+                    password: false,
+                },
+                cx,
+            ));
+        }
+        inputs.append(&mut vec![
+            Input::new(
+                "client_id",
+                "Client ID",
+                InputKind::TextField {
+                    placeholder: "Enter client_id...".to_string(),
+                    value: existing
+                        .as_ref()
+                        .map(|account| account.client_id.clone())
+                        .unwrap_or_default(),
+                    validate: Some(|v| v.is_empty().then_some("Client ID is required")),
+                    password: false,
+                },
+                cx,
+            ),
+            Input::new(
+                "client_secret",
+                "Client Secret",
+                InputKind::TextField {
+                    placeholder: "Enter client_secret...".to_string(),
+                    value: existing
+                        .as_ref()
+                        .map(|account| account.client_secret.clone())
+                        .unwrap_or_default(),
+                    validate: Some(|v| v.is_empty().then_some("Client Secret is required")),
+                    password: true,
+                },
+                cx,
+            ),
+        ]);
         Form::new(
-            vec![
-                Input::new(
-                    "instance",
-                    "Instance URL",
-                    InputKind::TextField {
-                        placeholder: "Enter the bitwarden instance URL...".to_string(),
-                        value: "https://bitwarden.com".to_string(),
-                        validate: Some(|v| {
-                            if v.is_empty() {
-                                return Some("Instance URL is required");
-                            };
-                            if url::Url::parse(v).is_err() {
-                                return Some("Invalid URL");
-                            }
-                            None
-                        }),
-                        password: false,
-                    },
-                    cx,
-                ),
-                Input::new(
-                    "id",
-                    "Identifier",
-                    InputKind::TextField {
-                        placeholder: "Enter an account identifier...".to_string(),
-                        value: "".to_string(),
-                        validate: Some(|v| v.is_empty().then_some("Identifier is required")),
-                        password: false,
-                    },
-                    cx,
-                ),
-                Input::new(
-                    "client_id",
-                    "Client ID",
-                    InputKind::TextField {
-                        placeholder: "Enter client_id...".to_string(),
-                        value: "".to_string(),
-                        validate: Some(|v| v.is_empty().then_some("Client ID is required")),
-                        password: false,
-                    },
-                    cx,
-                ),
-                Input::new(
-                    "client_secret",
-                    "Client Secret",
-                    InputKind::TextField {
-                        placeholder: "Enter client_secret...".to_string(),
-                        value: "".to_string(),
-                        validate: Some(|v| v.is_empty().then_some("Client Secret is required")),
-                        password: true,
-                    },
-                    cx,
-                ),
-            ],
-            |values, actions, cx| {
+            inputs,
+            move |values, actions, cx| {
+                let instance = values["instance"].value::<String>();
+                let client_id = values["client_id"].value::<String>();
+                let client_secret = values["client_secret"].value::<String>();
+
+                if let Some(existing) = &existing {
+                    let mut account = BitwardenAccount::get(&existing.id, db())
+                        .ok()
+                        .flatten()
+                        .map(|account| account.contents)
+                        .unwrap_or(existing.clone());
+                    let changed = account.instance != instance
+                        || account.client_id != client_id
+                        || account.client_secret != client_secret;
+                    account.instance = instance;
+                    account.client_id = client_id;
+                    account.client_secret = client_secret;
+                    let mut actions = actions.clone();
+                    cx.spawn(|mut cx| async move {
+                        if changed {
+                            // Drop the cached login so the next unlock re-authenticates
+                            actions.toast.loading("Updating account...", &mut cx);
+                            let _ = account.command(vec!["logout"]).await;
+                            account.session = None;
+                        }
+                        if let Err(err) = account.clone().overwrite_into(&account.id, db()) {
+                            error!("Failed to save account: {:?}", err.error);
+                            actions.toast.error("Failed to save account", &mut cx);
+                            return;
+                        }
+                        if changed && account.unlock(&mut cx).await.is_err() {
+                            actions.toast.error("Failed to unlock account", &mut cx);
+                            return;
+                        }
+                        actions.toast.success("Account updated", &mut cx);
+                        StateModel::update_async(|this, cx| this.pop(cx), &mut cx);
+                    })
+                    .detach();
+                    return;
+                }
+
                 if BitwardenAccount::get(&values["id"].value::<String>(), db())
                     .unwrap()
                     .is_some()
@@ -178,10 +236,10 @@ impl StateViewBuilder for BitwardenAccountFormBuilder {
                     actions.toast.loading("Saving account...", &mut cx);
 
                     let mut account = BitwardenAccount {
-                        instance: values["instance"].value::<String>(),
+                        instance,
                         id: values["id"].value::<String>(),
-                        client_id: values["client_id"].value::<String>(),
-                        client_secret: values["client_secret"].value::<String>(),
+                        client_id,
+                        client_secret,
                         password: None,
                         session: None,
                     };
@@ -227,7 +285,10 @@ impl StateViewBuilder for BitwardenAccountListBuilder {
                 "Add Account",
                 Some(Shortcut::new("n").cmd()),
                 |_, cx| {
-                    StateModel::update(|this, cx| this.push(BitwardenAccountFormBuilder, cx), cx);
+                    StateModel::update(
+                        |this, cx| this.push(BitwardenAccountFormBuilder { account: None }, cx),
+                        cx,
+                    );
                 },
                 false,
             )],
@@ -260,9 +321,18 @@ impl StateViewBuilder for BitwardenAccountListBuilder {
                                     "Edit",
                                     None,
                                     {
-                                        // TODO:
-                                        move |actions, cx| {
-                                            actions.toast.error("Not implemented", cx);
+                                        let account = account.clone();
+                                        move |_, cx| {
+                                            let account = Some(account.clone());
+                                            StateModel::update(
+                                                |this, cx| {
+                                                    this.push(
+                                                        BitwardenAccountFormBuilder { account },
+                                                        cx,
+                                                    )
+                                                },
+                                                cx,
+                                            );
                                         }
                                     },
                                     false,
@@ -276,6 +346,9 @@ impl StateViewBuilder for BitwardenAccountListBuilder {
                                         let path = account.path();
                                         let id = account.id.clone();
                                         move |actions, cx| {
+                                            if !keychain_delete(KEYCHAIN_SERVICE, &id) {
+                                                error!("Failed to delete keychain entry");
+                                            }
                                             if let Err(err) = fs::remove_dir_all(path.clone()) {
                                                 error!("Failed to delete account: {}", err);
                                                 actions.toast.error("Failed to delete account", cx);
@@ -294,7 +367,8 @@ impl StateViewBuilder for BitwardenAccountListBuilder {
                                         }
                                     },
                                     false,
-                                ),
+                                )
+                                .confirm(),
                             ])
                             .build()
                         })
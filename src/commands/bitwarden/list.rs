@@ -15,30 +15,38 @@ use async_std::{
     channel,
     process::{Command, Output},
 };
+use base64::{engine::general_purpose::STANDARD, Engine};
 use bonsaidb::{
     core::schema::{Collection, SerializedCollection},
     local::Database,
 };
 use gpui::*;
+use jiff::Timestamp;
 use log::*;
 use serde::{Deserialize, Serialize};
-use serde_json::Value;
+use serde_json::{json, Value};
 use url::Url;
 
 use crate::{
     command,
     commands::{RootCommand, RootCommandBuilder},
     components::{
-        list::{Accessory, AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
+        form::{Form, Input, InputKind},
+        list::{Accessory, AsyncListItems, Item, ItemBuilder, List, ListBuilder, ListItem},
         shared::{Icon, Img, ImgMask},
     },
-    db::Db,
+    db::{Db, Migrate},
     paths::paths,
-    platform::{autofill, close_and_paste},
+    platform::{
+        autofill, close_and_paste, copy_text, keychain_get, keychain_set, require_accessibility,
+    },
     state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
     window::Window,
 };
 
+#[cfg(feature = "clipboard")]
+use crate::commands::clipboard::settings::ClipboardSettings;
+
 use super::accounts::{
     BitwardenAccountFormBuilder, BitwardenAccountListBuilder, BitwardenPasswordPromptBuilder,
 };
@@ -46,56 +54,268 @@ use super::accounts::{
 #[derive(Clone)]
 pub struct BitwardenListBuilder {
     view: View<AsyncListItems>,
+    folders: Model<HashMap<String, Vec<BitwardenFolder>>>,
 }
 command!(BitwardenListBuilder);
 impl StateViewBuilder for BitwardenListBuilder {
     fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
         context.query.set_placeholder("Search your vault...", cx);
         if let Ok(accounts) = BitwardenAccount::all(db()).query() {
-            if accounts.len() > 1 {
-                let mut options = vec![("".to_string(), "Show All".to_string())];
-                for account in accounts {
-                    let id = account.contents.id.clone();
-                    options.push((id.clone(), id));
+            let folders = self.folders.read(cx);
+            let multiple = accounts.len() > 1;
+            let mut options = vec![("".to_string(), "Show All".to_string())];
+            for account in accounts {
+                let id = account.contents.id.clone();
+                if multiple {
+                    options.push((id.clone(), id.clone()));
+                }
+                // Folder values are scoped to their account as "<account>/<folder>"
+                for folder in folders.get(&id).into_iter().flatten() {
+                    let Some(folder_id) = &folder.id else {
+                        continue;
+                    };
+                    let label = if multiple {
+                        format!("{} / {}", id, folder.name)
+                    } else {
+                        folder.name.clone()
+                    };
+                    options.push((format!("{}/{}", id, folder_id), label));
                 }
+            }
+            if options.len() > 1 {
                 context.actions.set_dropdown("", options, cx);
             }
         }
 
+        let view = self.view.clone();
+        let dropdown = context.actions.clone();
         context.actions.update_global(
-            vec![Action::new(
-                Img::default().icon(Icon::UserSearch),
-                "List Accounts",
-                Some(Shortcut::new(",").cmd()),
-                |_, cx| {
-                    StateModel::update(
-                        |this, cx| {
-                            this.push(BitwardenAccountListBuilder, cx);
-                        },
-                        cx,
-                    );
-                },
-                false,
-            )],
+            vec![
+                Action::new(
+                    Img::default().icon(Icon::PlusSquare),
+                    "Create Login",
+                    Some(Shortcut::new("n").cmd()),
+                    move |_, cx| {
+                        let selected = dropdown.get_dropdown_value(cx);
+                        let Ok(accounts) = BitwardenAccount::all(db()).query() else {
+                            return;
+                        };
+                        let Some(account) = accounts
+                            .iter()
+                            .find(|account| account.contents.id.eq(&selected))
+                            .or(accounts.first())
+                        else {
+                            return;
+                        };
+                        let account = account.contents.clone();
+                        let view = view.clone();
+                        StateModel::update(
+                            |this, cx| {
+                                this.push(BitwardenCreateLoginBuilder { view, account }, cx);
+                            },
+                            cx,
+                        );
+                    },
+                    false,
+                ),
+                Action::new(
+                    Img::default().icon(Icon::UserSearch),
+                    "List Accounts",
+                    Some(Shortcut::new(",").cmd()),
+                    |_, cx| {
+                        StateModel::update(
+                            |this, cx| {
+                                this.push(BitwardenAccountListBuilder, cx);
+                            },
+                            cx,
+                        );
+                    },
+                    false,
+                ),
+            ],
             cx,
         );
         AsyncListItems::loader(&self.view, &context.actions, cx);
         let view = self.view.clone();
-        ListBuilder::new()
-            .build(
-                move |list, _, cx| {
-                    let account = list.actions.get_dropdown_value(cx);
-                    let items = view.read(cx).items.clone();
-                    if account.is_empty() {
-                        Ok(Some(items.values().flatten().cloned().collect()))
-                    } else {
-                        Ok(Some(items.get(&account).cloned().unwrap_or_default()))
-                    }
-                },
-                context,
-                cx,
-            )
-            .into()
+        let list = ListBuilder::new().build(
+            move |list, _, cx| {
+                let selected = list.actions.get_dropdown_value(cx);
+                let items = view.read(cx).items.clone();
+                if selected.is_empty() {
+                    return Ok(Some(items.values().flatten().cloned().collect()));
+                }
+                if let Some(items) = items.get(&selected) {
+                    return Ok(Some(items.clone()));
+                }
+                let Some((account, folder)) = selected.rsplit_once('/') else {
+                    return Ok(Some(vec![]));
+                };
+                Ok(Some(
+                    items
+                        .get(account)
+                        .into_iter()
+                        .flatten()
+                        .filter(|item| {
+                            item.get_meta::<EntryMeta>(cx)
+                                .and_then(|meta| meta.1.get("folder_id").cloned())
+                                .is_some_and(|id| id.eq(folder))
+                        })
+                        .cloned()
+                        .collect(),
+                ))
+            },
+            context,
+            cx,
+        );
+        TotpTicker::init(list.downgrade(), cx);
+        list.into()
+    }
+}
+
+#[derive(Clone)]
+struct BitwardenCreateLoginBuilder {
+    view: View<AsyncListItems>,
+    account: BitwardenAccount,
+}
+command!(BitwardenCreateLoginBuilder);
+impl StateViewBuilder for BitwardenCreateLoginBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let view = self.view.clone();
+        let account = self.account.clone();
+        Form::new(
+            vec![
+                Input::new(
+                    "name",
+                    "Name",
+                    InputKind::TextField {
+                        placeholder: "Enter a name...".to_string(),
+                        value: "".to_string(),
+                        validate: Some(|v| v.is_empty().then_some("Name is required")),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "username",
+                    "Username",
+                    InputKind::TextField {
+                        placeholder: "Enter the username...".to_string(),
+                        value: "".to_string(),
+                        validate: None,
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "password",
+                    "Password",
+                    InputKind::TextField {
+                        placeholder: "Enter the password...".to_string(),
+                        value: "".to_string(),
+                        validate: None,
+                        password: true,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "uri",
+                    "URI",
+                    InputKind::TextField {
+                        placeholder: "Enter the website URI...".to_string(),
+                        value: "".to_string(),
+                        validate: None,
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "totp",
+                    "TOTP Secret",
+                    InputKind::TextField {
+                        placeholder: "Enter the TOTP secret (optional)...".to_string(),
+                        value: "".to_string(),
+                        validate: None,
+                        password: true,
+                    },
+                    cx,
+                ),
+            ],
+            move |values, actions, cx| {
+                let username = values["username"].value::<String>();
+                let password = values["password"].value::<String>();
+                if username.is_empty() && password.is_empty() {
+                    actions
+                        .clone()
+                        .toast
+                        .error("Username or password is required", cx);
+                    return;
+                }
+                let uri = values["uri"].value::<String>();
+                let totp = values["totp"].value::<String>();
+                let uris = if uri.is_empty() {
+                    vec![]
+                } else {
+                    vec![json!({ "uri": uri, "match": null })]
+                };
+                let template = json!({
+                    "type": 1,
+                    "name": values["name"].value::<String>(),
+                    "notes": null,
+                    "favorite": false,
+                    "fields": [],
+                    "reprompt": 0,
+                    "organizationId": null,
+                    "folderId": null,
+                    "login": {
+                        "username": username,
+                        "password": password,
+                        "totp": (!totp.is_empty()).then_some(totp),
+                        "uris": uris,
+                    },
+                });
+                let encoded = STANDARD.encode(template.to_string());
+
+                let mut actions = actions.clone();
+                let mut account = account.clone();
+                let view = view.clone();
+                cx.spawn(|mut cx| async move {
+                    actions.toast.loading("Creating login...", &mut cx);
+                    let output = account
+                        .auth_command(vec!["create", "item", &encoded], &mut cx)
+                        .await;
+                    let item = match output {
+                        Ok(output) if output.status.success() => {
+                            serde_json::from_slice::<BitwardenItem>(&output.stdout).ok()
+                        }
+                        Ok(output) => {
+                            error!(
+                                "Failed to create item: {}",
+                                String::from_utf8_lossy(&output.stderr)
+                            );
+                            None
+                        }
+                        Err(err) => {
+                            error!("Failed to create item: {}", err);
+                            None
+                        }
+                    };
+                    let Some(item) = item.and_then(|item| login_item(&account, item, &mut cx))
+                    else {
+                        actions.toast.error("Failed to create login", &mut cx);
+                        return;
+                    };
+                    let _ = view.update(&mut cx, |list, cx| {
+                        list.push(account.id.clone(), item, cx);
+                    });
+                    actions.toast.success("Login created", &mut cx);
+                    StateModel::update_async(|this, cx| this.pop(cx), &mut cx);
+                })
+                .detach();
+            },
+            context,
+            cx,
+        )
+        .into()
     }
 }
 
@@ -127,10 +347,13 @@ impl BitwardenLoginItem {
             _ => panic!("Unknown field {}", field),
         }
     }
-    pub fn get_action(&self, field: &str) -> Action {
+    pub fn get_action(&self, field: &str, meta: &Model<EntryMeta>) -> Action {
         let (label, img, shortcut) = self.get_label(field);
+        let is_totp = field.eq("totp");
+        #[cfg(feature = "clipboard")]
+        let sensitive = matches!(field, "password" | "totp");
         let field = field.to_string();
-        Action::new(
+        let action = Action::new(
             img,
             label,
             Some(shortcut),
@@ -139,16 +362,66 @@ impl BitwardenLoginItem {
                     return;
                 };
                 let value = meta.1.get(&field).cloned().unwrap_or("".to_string());
+                if !require_accessibility("paste", &mut this.toast, cx) {
+                    return;
+                }
                 close_and_paste(value.as_str(), true, cx);
+                #[cfg(feature = "clipboard")]
+                if sensitive {
+                    ClipboardSettings::clear_sensitive(value, cx);
+                }
+            },
+            false,
+        );
+        if !is_totp {
+            return action;
+        }
+        let meta = meta.clone();
+        action.badge(move |cx| {
+            if !meta.read(cx).1.contains_key("totp") {
+                return None;
+            }
+            TotpTicker::keep_alive(cx);
+            Some(format!("{}s", totp_remaining()))
+        })
+    }
+    pub fn get_copy_action(&self, field: &str) -> Action {
+        let (label, _, shortcut) = self.get_label(field);
+        #[cfg(feature = "clipboard")]
+        let sensitive = matches!(field, "password" | "totp");
+        let field = field.to_string();
+        Action::new(
+            Img::default().icon(Icon::ClipboardCopy),
+            format!("Copy {}", label),
+            Some(shortcut.alt()),
+            move |this, cx| {
+                let Some(meta) = this.get_meta::<(Vec<String>, HashMap<String, String>)>(cx) else {
+                    return;
+                };
+                let value = meta.1.get(&field).cloned().unwrap_or("".to_string());
+                if let Err(err) = copy_text(&value, cx) {
+                    error!("Failed to copy {}: {:?}", field, err);
+                    this.toast.error("Failed to copy to clipboard", cx);
+                    return;
+                }
+                #[cfg(feature = "clipboard")]
+                if sensitive {
+                    ClipboardSettings::clear_sensitive(value, cx);
+                }
+                this.toast
+                    .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
             },
             false,
         )
     }
-    pub fn get_actions(&self) -> Vec<Action> {
-        self.fields()
+    pub fn get_actions(&self, meta: &Model<EntryMeta>) -> Vec<Action> {
+        let fields = self.fields();
+        let mut actions: Vec<Action> = fields
             .iter()
-            .map(|field| self.get_action(field))
-            .collect()
+            .map(|field| self.get_action(field, meta))
+            .collect();
+        actions.extend(fields.iter().map(|field| self.get_copy_action(field)));
+        actions
     }
 }
 
@@ -172,11 +445,19 @@ pub(super) enum BitwardenItem {
         id: String,
         name: String,
         notes: Option<String>,
+        #[serde(rename = "folderId")]
+        folder_id: Option<String>,
         login: BitwardenLoginItem,
     },
     Other(Value),
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub(super) struct BitwardenFolder {
+    id: Option<String>,
+    name: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Collection)]
 #[collection(name = "bitwarden-accounts")]
 pub(super) struct BitwardenAccount {
@@ -189,6 +470,8 @@ pub(super) struct BitwardenAccount {
     pub session: Option<String>,
 }
 
+impl Migrate for BitwardenAccount {}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub(super) enum BitwardenVaultStatus {
@@ -266,10 +549,27 @@ impl BitwardenAccount {
             _ => {}
         }
 
+        // Falls back to the password prompt if biometrics are unavailable or denied
+        let biometric = if self.password.is_none() {
+            let id = self.id.clone();
+            cx.background_executor()
+                .spawn(async move {
+                    keychain_get(
+                        KEYCHAIN_SERVICE,
+                        &id,
+                        &format!("unlock the Bitwarden account {}", id),
+                    )
+                })
+                .await
+        } else {
+            None
+        };
         let password = if let Some(password) = &self.password {
             password.clone()
+        } else if let Some(password) = biometric {
+            password
         } else {
-            let (s, r) = channel::unbounded::<(String, bool)>();
+            let (s, r) = channel::unbounded::<(String, bool, bool)>();
             StateModel::update_async(
                 |this, cx| {
                     this.push(
@@ -282,10 +582,13 @@ impl BitwardenAccount {
                 },
                 cx,
             );
-            let (password, remember) = r.recv().await?;
+            let (password, remember, biometric) = r.recv().await?;
             if remember {
                 self.password = Some(password.clone());
             }
+            if biometric && !keychain_set(KEYCHAIN_SERVICE, &self.id, &password) {
+                error!("Failed to store password in keychain");
+            }
             password
         };
         let output = self
@@ -308,13 +611,58 @@ impl BitwardenAccount {
 
 pub struct BitwardenCommandBuilder;
 
+// Keychain service under which master passwords for Touch ID unlock are stored
+pub(super) static KEYCHAIN_SERVICE: &str = "app.loungy.bitwarden";
+
 pub(super) fn db() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(Db::init_collection::<BitwardenAccount>)
 }
 
+type EntryMeta = (Vec<String>, HashMap<String, String>);
+
+// TOTP codes rotate every 30 seconds
+static TOTP_PERIOD: i64 = 30;
+
+fn totp_remaining() -> i64 {
+    TOTP_PERIOD - Timestamp::now().as_second().rem_euclid(TOTP_PERIOD)
+}
+
+// Redraws the list once a second for the TOTP countdown. Rendering a countdown badge keeps it
+// going for another second, so it stops by itself once no TOTP item is shown
+struct TotpTicker {
+    wake: channel::Sender<()>,
+}
+
+impl Global for TotpTicker {}
+
+impl TotpTicker {
+    // Replacing the global drops the previous sender, which ends that list's ticker
+    fn init(list: WeakView<List>, cx: &mut WindowContext) {
+        let (wake, woken) = channel::bounded::<()>(1);
+        cx.set_global(Self { wake });
+        cx.spawn(|mut cx| async move {
+            while woken.recv().await.is_ok() {
+                let elapsed = Timestamp::now().subsec_millisecond() as u64;
+                cx.background_executor()
+                    .timer(Duration::from_millis(1000 - elapsed))
+                    .await;
+                if list.update(&mut cx, |_, cx| cx.notify()).is_err() {
+                    return;
+                }
+            }
+        })
+        .detach();
+    }
+    fn keep_alive(cx: &WindowContext) {
+        if let Some(ticker) = cx.try_global::<Self>() {
+            let _ = ticker.wake.try_send(());
+        }
+    }
+}
+
 struct EntryModel {
-    inner: Model<(Vec<String>, HashMap<String, String>)>,
+    inner: Model<EntryMeta>,
 }
 
 impl EntryModel {
@@ -327,10 +675,18 @@ impl EntryModel {
         Self {
             inner: cx
                 .new_model(|cx| match item {
-                    BitwardenItem::Login { id, login, .. } => {
+                    BitwardenItem::Login {
+                        id,
+                        login,
+                        folder_id,
+                        ..
+                    } => {
                         let mut map = HashMap::new();
                         map.insert("username".to_string(), login.username.clone());
                         map.insert("password".to_string(), login.password.clone());
+                        if let Some(folder_id) = folder_id {
+                            map.insert("folder_id".to_string(), folder_id.clone());
+                        }
 
                         if login.totp.is_some() {
                             let account = account.clone();
@@ -367,9 +723,11 @@ impl EntryModel {
                                             cx.notify();
                                         },
                                     );
+                                    // Fetch the new code once the current TOTP step is over
+                                    drop(model);
                                     cx.background_executor()
-                                        .timer(Duration::from_secs(10))
-                                        .await
+                                        .timer(Duration::from_secs(totp_remaining() as u64))
+                                        .await;
                                 }
                             })
                             .detach();
@@ -382,13 +740,165 @@ impl EntryModel {
         }
     }
 }
+
+fn login_item(
+    account: &BitwardenAccount,
+    item: BitwardenItem,
+    cx: &mut AsyncWindowContext,
+) -> Option<Item> {
+    let item_clone = item.clone();
+
+    let BitwardenItem::Login {
+        id,
+        name,
+        notes: _,
+        folder_id: _,
+        login,
+    } = item
+    else {
+        return None;
+    };
+    let mut img = login
+        .uris
+        .first()
+        .and_then(|uri| {
+            Url::parse(&if !uri.uri.starts_with("http") {
+                format!("https://{}", uri.uri)
+            } else {
+                uri.uri.clone()
+            })
+            .ok()
+            .and_then(|url| {
+                cx.update_window(cx.window_handle(), |_, cx| {
+                    Img::default()
+                        .mask(ImgMask::Rounded)
+                        .favicon(url, Icon::Globe, cx)
+                })
+                .ok()
+            })
+        })
+        .unwrap_or(Img::default().icon(Icon::Globe));
+
+    img.mask = ImgMask::Rounded;
+
+    let mut keywords = vec![name.clone()];
+    keywords.append(&mut login.uris.iter().map(|uri| uri.uri.clone()).collect());
+    let meta = EntryModel::new(account, &item_clone, cx);
+    let mut actions = vec![Action::new(
+        Img::default().icon(Icon::PaintBucket),
+        "Autofill",
+        None,
+        {
+            |this, cx| {
+                if !require_accessibility("autofill", &mut this.toast, cx) {
+                    return;
+                }
+                Window::close(cx);
+                let Some(meta) = this.get_meta_model::<(Vec<String>, HashMap<String, String>)>()
+                else {
+                    return;
+                };
+                let mut toast = this.toast.clone();
+                cx.spawn(move |mut cx| async move {
+                    Window::wait_for_close(&mut cx).await;
+                    let mut prev = "".to_string();
+                    let max_tries = 900;
+                    let mut tries = 0;
+                    let Ok(keys) = cx.read_model(&meta, |(keys, _), _| keys.clone()) else {
+                        return;
+                    };
+                    let total = keys.len();
+                    for (i, field) in keys.into_iter().enumerate() {
+                        toast.progress("Autofilling...", i as f32 / total as f32, &mut cx);
+                        loop {
+                            let value = cx
+                                .read_model(&meta, |(_, map), _| map.clone())
+                                .map(|map| map.get(&field).map(|s| s.to_string()))
+                                .ok()
+                                .flatten()
+                                .unwrap_or("".to_string());
+                            match autofill(value.as_str(), field.eq("password"), &prev) {
+                                Some(p) => {
+                                    prev = p;
+                                    break;
+                                }
+                                None => {
+                                    tries += 1;
+                                    if tries > max_tries {
+                                        error!("Autofill timed out");
+                                        toast.error("Autofill timed out", &mut cx);
+                                        return;
+                                    }
+                                    cx.background_executor()
+                                        .timer(Duration::from_millis(100))
+                                        .await;
+                                    //cx.background_executor().timer(Duration::from_millis(100)).await;
+                                }
+                            }
+                        }
+                    }
+                    toast.success("Autofilled", &mut cx);
+                })
+                .detach();
+            }
+        },
+        false,
+    )];
+
+    let url = login.uris.first().and_then(|uri| {
+        Url::parse(&if !uri.uri.starts_with("http") {
+            format!("https://{}", uri.uri)
+        } else {
+            uri.uri.clone()
+        })
+        .ok()
+    });
+
+    if let Some(url) = url {
+        actions.push(Action::new(
+            Img::default().icon(Icon::Globe),
+            "Open",
+            Some(Shortcut::new("o").cmd()),
+            {
+                move |_, cx| {
+                    Window::close(cx);
+                    cx.open_url(url.as_str());
+                }
+            },
+            false,
+        ));
+    }
+
+    // let preview = cx.update_window::<StateItem, _>(cx.window_handle(), |_, cx| {
+    //     StateItem::init(BitwardenAccountListBuilder, false, cx)
+    // }).ok();
+    actions.append(&mut login.get_actions(&meta.inner));
+    Some(
+        ItemBuilder::new(
+            id.clone(),
+            ListItem::new(
+                Some(img),
+                name.clone(),
+                None,
+                vec![Accessory::new(login.username.clone(), None)],
+            ),
+        )
+        .keywords(keywords)
+        .actions(actions)
+        .meta(meta.inner.into_any())
+        .build(),
+    )
+}
+
 command!(BitwardenCommandBuilder);
 impl RootCommandBuilder for BitwardenCommandBuilder {
     fn build(&self, cx: &mut WindowContext) -> RootCommand {
+        let folders = cx.new_model(|_| HashMap::<String, Vec<BitwardenFolder>>::new());
         let view = cx.new_view(|cx| {
             let accounts = BitwardenAccount::all(db()).query().unwrap_or_default();
             for account in accounts {
                 let mut account = account.contents;
+                let folders = folders.clone();
                 cx.spawn(move |view, mut cx| async move {
                     let mut first = true;
                     loop {
@@ -418,160 +928,27 @@ impl RootCommandBuilder for BitwardenCommandBuilder {
                             .unwrap_or_default();
 
                         for item in parsed {
-                            let item_clone = item.clone();
-
-                            if let BitwardenItem::Login {
-                                id,
-                                name,
-                                notes: _,
-                                login,
-                            } = item
-                            {
-                                let mut img = login
-                                    .uris
-                                    .first()
-                                    .and_then(|uri| {
-                                        Url::parse(&if !uri.uri.starts_with("http") {
-                                            format!("https://{}", uri.uri)
-                                        } else {
-                                            uri.uri.clone()
-                                        })
-                                        .ok()
-                                        .and_then(|url| {
-                                            cx.update_window(cx.window_handle(), |_, cx| {
-                                                Img::default().mask(ImgMask::Rounded).favicon(
-                                                    url,
-                                                    Icon::Globe,
-                                                    cx,
-                                                )
-                                            })
-                                            .ok()
-                                        })
-                                    })
-                                    .unwrap_or(Img::default().icon(Icon::Globe));
-
-                                img.mask = ImgMask::Rounded;
-
-                                let mut keywords = vec![name.clone()];
-                                keywords.append(
-                                    &mut login.uris.iter().map(|uri| uri.uri.clone()).collect(),
-                                );
-                                let meta = EntryModel::new(&account, &item_clone, &mut cx);
-                                let mut actions = vec![Action::new(
-                                    Img::default().icon(Icon::PaintBucket),
-                                    "Autofill",
-                                    None,
-                                    {
-                                        |this, cx| {
-                                            Window::close(cx);
-                                            let Some(meta) = this.get_meta_model::<(
-                                                Vec<String>,
-                                                HashMap<String, String>,
-                                            )>(
-                                            ) else {
-                                                return;
-                                            };
-                                            cx.spawn(move |mut cx| async move {
-                                                Window::wait_for_close(&mut cx).await;
-                                                let mut prev = "".to_string();
-                                                let max_tries = 900;
-                                                let mut tries = 0;
-                                                let Ok(keys) = cx
-                                                    .read_model(&meta, |(keys, _), _| keys.clone())
-                                                else {
-                                                    return;
-                                                };
-                                                for field in keys {
-                                                    loop {
-                                                        let value = cx
-                                                            .read_model(&meta, |(_, map), _| {
-                                                                map.clone()
-                                                            })
-                                                            .map(|map| {
-                                                                map.get(&field)
-                                                                    .map(|s| s.to_string())
-                                                            })
-                                                            .ok()
-                                                            .flatten()
-                                                            .unwrap_or("".to_string());
-                                                        match autofill(
-                                                            value.as_str(),
-                                                            field.eq("password"),
-                                                            &prev,
-                                                        ) {
-                                                            Some(p) => {
-                                                                prev = p;
-                                                                break;
-                                                            }
-                                                            None => {
-                                                                tries += 1;
-                                                                if tries > max_tries {
-                                                                    error!("Autofill timed out");
-                                                                    return;
-                                                                }
-                                                                cx.background_executor()
-                                                                    .timer(Duration::from_millis(
-                                                                        100,
-                                                                    ))
-                                                                    .await;
-                                                                //cx.background_executor().timer(Duration::from_millis(100)).await;
-                                                            }
-                                                        }
-                                                    }
-                                                }
-                                            })
-                                            .detach();
-                                        }
-                                    },
-                                    false,
-                                )];
-
-                                let url = login.uris.first().and_then(|uri| {
-                                    Url::parse(&if !uri.uri.starts_with("http") {
-                                        format!("https://{}", uri.uri)
-                                    } else {
-                                        uri.uri.clone()
-                                    })
-                                    .ok()
-                                });
-
-                                if let Some(url) = url {
-                                    actions.push(Action::new(
-                                        Img::default().icon(Icon::Globe),
-                                        "Open",
-                                        Some(Shortcut::new("o").cmd()),
-                                        {
-                                            move |_, cx| {
-                                                Window::close(cx);
-                                                cx.open_url(url.as_str());
-                                            }
-                                        },
-                                        false,
-                                    ));
-                                }
+                            items.extend(login_item(&account, item, &mut cx));
+                        }
+                        let id = account.id.clone();
 
-                                // let preview = cx.update_window::<StateItem, _>(cx.window_handle(), |_, cx| {
-                                //     StateItem::init(BitwardenAccountListBuilder, false, cx)
-                                // }).ok();
-                                actions.append(&mut login.get_actions());
-                                items.push(
-                                    ItemBuilder::new(
-                                        id.clone(),
-                                        ListItem::new(
-                                            Some(img),
-                                            name.clone(),
-                                            None,
-                                            vec![Accessory::new(login.username.clone(), None)],
-                                        ),
-                                    )
-                                    .keywords(keywords)
-                                    .actions(actions)
-                                    .meta(meta.inner.into_any())
-                                    .build(),
-                                );
+                        match account
+                            .auth_command(vec!["list", "folders", "--nointeraction"], &mut cx)
+                            .await
+                            .map_err(|e| e.to_string())
+                            .and_then(|output| {
+                                serde_json::from_slice::<Vec<BitwardenFolder>>(&output.stdout)
+                                    .map_err(|e| e.to_string())
+                            }) {
+                            Ok(parsed) => {
+                                let id = id.clone();
+                                let _ = folders.update(&mut cx, |folders, cx| {
+                                    folders.insert(id, parsed);
+                                    cx.notify();
+                                });
                             }
+                            Err(err) => error!("Failed to list folders: {}", err),
                         }
-                        let id = account.id.clone();
                         if let Some(view) = view.upgrade() {
                             let _ = view.update(&mut cx, move |list: &mut AsyncListItems, cx| {
                                 list.update(id.clone(), items, cx);
@@ -598,16 +975,22 @@ impl RootCommandBuilder for BitwardenCommandBuilder {
             None,
             move |_, cx| {
                 let view = view.clone();
+                let folders = folders.clone();
                 let accounts = BitwardenAccount::all(db());
                 if accounts.count().unwrap_or_default() == 0 {
                     StateModel::update(
-                        |this, cx| this.push(BitwardenAccountFormBuilder {}, cx),
+                        |this, cx| this.push(BitwardenAccountFormBuilder { account: None }, cx),
                         cx,
                     );
                 } else {
-                    StateModel::update(|this, cx| this.push(BitwardenListBuilder { view }, cx), cx);
+                    StateModel::update(
+                        |this, cx| this.push(BitwardenListBuilder { view, folders }, cx),
+                        cx,
+                    );
                 };
             },
         )
+        // The vault may have to be unlocked again
+        .no_restore()
     }
 }
@@ -0,0 +1,53 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::*;
+
+use crate::{
+    command,
+    commands::{root::numbat::Numbat, RootCommand, RootCommandBuilder},
+    components::{list::ListBuilder, shared::Icon},
+    state::{CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+#[derive(Clone)]
+struct CalculatorListBuilder;
+command!(CalculatorListBuilder);
+impl StateViewBuilder for CalculatorListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Type an expression or a conversion...", cx);
+        let numbat = Numbat::init(&context.query, cx);
+        ListBuilder::new()
+            .filter(move |_, cx| Numbat::item(&numbat, cx).into_iter().collect())
+            .build(|_, _, _| Ok(Some(vec![])), context, cx)
+            .into()
+    }
+}
+
+pub struct CalculatorCommandBuilder;
+command!(CalculatorCommandBuilder);
+impl RootCommandBuilder for CalculatorCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "calculator",
+            "Calculator",
+            "Calculate",
+            Icon::Calculator,
+            vec!["Math", "Arithmetic", "Convert", "Units", "Currency"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(CalculatorListBuilder, cx), cx);
+            },
+        )
+    }
+}
@@ -0,0 +1,92 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::{rgb, Hsla};
+
+// Parses a whole string as a single hex, rgb() or hsl() color
+pub fn parse_color(value: &str) -> Option<(u8, u8, u8)> {
+    let value = value.trim().to_lowercase();
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    if let Some(args) = function_args(&value, "rgb") {
+        let [r, g, b] = args;
+        return Some((r.parse().ok()?, g.parse().ok()?, b.parse().ok()?));
+    }
+    if let Some(args) = function_args(&value, "hsl") {
+        let [h, s, l] = args;
+        let h: f32 = h.trim_end_matches("deg").parse().ok()?;
+        let s: f32 = s.strip_suffix('%')?.parse().ok()?;
+        let l: f32 = l.strip_suffix('%')?.parse().ok()?;
+        if !(0.0..=100.0).contains(&s) || !(0.0..=100.0).contains(&l) {
+            return None;
+        }
+        return Some(hsl_to_rgb(h, s / 100.0, l / 100.0));
+    }
+    None
+}
+
+fn parse_hex(hex: &str) -> Option<(u8, u8, u8)> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 => {
+            let mut channels = hex.chars().map(|c| c.to_digit(16).unwrap() as u8 * 17);
+            Some((channels.next()?, channels.next()?, channels.next()?))
+        }
+        6 => Some((
+            u8::from_str_radix(&hex[0..2], 16).ok()?,
+            u8::from_str_radix(&hex[2..4], 16).ok()?,
+            u8::from_str_radix(&hex[4..6], 16).ok()?,
+        )),
+        _ => None,
+    }
+}
+
+fn function_args<'a>(value: &'a str, name: &str) -> Option<[&'a str; 3]> {
+    let args = value
+        .strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('(')?
+        .strip_suffix(')')?;
+    let args: Vec<&str> = args.split(',').map(|arg| arg.trim()).collect();
+    args.try_into().ok()
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32) -> (u8, u8, u8) {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = h.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u8 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let channel = |v: f32| ((v + m) * 255.0).round() as u8;
+    (channel(r), channel(g), channel(b))
+}
+
+pub fn to_hex((r, g, b): (u8, u8, u8)) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub fn to_rgb((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({}, {}, {})", r, g, b)
+}
+
+pub fn to_hsla((r, g, b): (u8, u8, u8)) -> Hsla {
+    rgb((r as u32) << 16 | (g as u32) << 8 | b as u32).into()
+}
@@ -13,21 +13,25 @@ use std::{
     cmp::Reverse,
     collections::hash_map::DefaultHasher,
     hash::{Hash, Hasher},
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, OnceLock},
     thread,
     time::{Duration, Instant},
 };
 
 use arboard::Clipboard;
+#[cfg(target_os = "linux")]
+use arboard::{GetExtLinux, LinuxClipboardKind};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use bonsaidb::{
     core::schema::{Collection, SerializedCollection},
     local::Database,
 };
 use gpui::*;
-use image::{DynamicImage, ImageBuffer};
+use image::{imageops::FilterType, DynamicImage, ImageBuffer};
 use jiff::{Span, Timestamp, ToSpan};
 use log::error;
+use qrcode::QrCode;
 use serde::{Deserialize, Serialize};
 
 use url::Url;
@@ -39,17 +43,25 @@ use crate::{
         list::{AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
         shared::{Icon, Img, ImgMask, ImgSize, ObjectFit},
     },
-    date::format_date,
-    db::Db,
+    date::{format_date, format_day_section},
+    db::{Db, Migrate},
     paths::paths,
     platform::{
-        clipboard, close_and_paste, close_and_paste_file, get_frontmost_application_data, ocr,
-        AppData, ClipboardWatcher,
+        clipboard, close_and_paste, close_and_paste_file, close_and_paste_html, copy_html,
+        copy_image, copy_text, get_frontmost_application_data, ocr, open_path,
+        require_accessibility, reveal_path, AppData, ClipboardWatcher, REVEAL_LABEL,
     },
     state::{
-        Action, CommandTrait, Shortcut, StateItem, StateModel, StateViewBuilder, StateViewContext,
+        Action, Actions, CommandTrait, Shortcut, StateItem, StateModel, StateViewBuilder,
+        StateViewContext,
     },
     theme::Theme,
+    window::Window,
+};
+
+use super::{
+    color::{parse_color, to_hex, to_hsla, to_rgb},
+    settings::{ClipboardSettings, ClipboardSettingsBuilder},
 };
 
 #[derive(Clone)]
@@ -64,29 +76,104 @@ impl StateViewBuilder for ClipboardListBuilder {
             .set_placeholder("Search your clipboard history...", cx);
 
         context.actions.update_global(
-            vec![Action::new(
-                Img::default().icon(Icon::Trash),
-                "Delete All",
-                None,
-                {
-                    let view = self.view.clone();
-                    move |actions, cx| {
-                        if let Err(err) =
-                            ClipboardListItem::prune(ToSpan::seconds(0), view.downgrade(), cx)
-                        {
-                            error!("Failed to prune clipboard: {:?}", err);
-                            actions
-                                .toast
-                                .error("Failed to delete clipboard entries", cx);
-                        } else {
-                            actions
-                                .toast
-                                .success("Successfully deleted clipboard entries", cx);
+            vec![
+                Action::new(
+                    Img::default().icon(Icon::Trash),
+                    "Delete All",
+                    None,
+                    {
+                        let view = self.view.clone();
+                        move |actions, cx| {
+                            if let Err(err) =
+                                ClipboardListItem::prune(ToSpan::seconds(0), view.downgrade(), cx)
+                            {
+                                error!("Failed to prune clipboard: {:?}", err);
+                                actions
+                                    .toast
+                                    .error("Failed to delete clipboard entries", cx);
+                            } else {
+                                actions
+                                    .toast
+                                    .success("Successfully deleted clipboard entries", cx);
+                            }
                         }
-                    }
-                },
-                false,
-            )],
+                    },
+                    false,
+                )
+                .confirm(),
+                Action::new(
+                    Img::default().icon(Icon::Download),
+                    "Export History",
+                    None,
+                    |actions, cx| {
+                        let path = cx.prompt_for_new_path(&paths().home);
+                        let mut toast = actions.toast.clone();
+                        cx.spawn(|mut cx| async move {
+                            let Ok(Some(path)) = path.await else {
+                                return;
+                            };
+                            match ClipboardListItem::export(&path) {
+                                Ok(count) => toast.success(
+                                    format!("Exported {} clipboard entries", count),
+                                    &mut cx,
+                                ),
+                                Err(err) => {
+                                    error!("Failed to export clipboard: {:?}", err);
+                                    toast.error("Failed to export clipboard history", &mut cx);
+                                }
+                            }
+                        })
+                        .detach();
+                    },
+                    false,
+                ),
+                Action::new(
+                    Img::default().icon(Icon::Upload),
+                    "Import History",
+                    None,
+                    {
+                        let view = self.view.clone();
+                        move |actions, cx| {
+                            let paths = cx.prompt_for_paths(PathPromptOptions {
+                                files: true,
+                                directories: false,
+                                multiple: false,
+                            });
+                            let mut toast = actions.toast.clone();
+                            let view = view.downgrade();
+                            cx.spawn(|mut cx| async move {
+                                let Ok(Some(paths)) = paths.await else {
+                                    return;
+                                };
+                                let Some(path) = paths.into_iter().next() else {
+                                    return;
+                                };
+                                match cx.update(|cx| ClipboardListItem::import(&path, view, cx)) {
+                                    Ok(Ok(count)) => toast.success(
+                                        format!("Imported {} clipboard entries", count),
+                                        &mut cx,
+                                    ),
+                                    Ok(Err(err)) | Err(err) => {
+                                        error!("Failed to import clipboard: {:?}", err);
+                                        toast.error("Failed to import clipboard history", &mut cx);
+                                    }
+                                }
+                            })
+                            .detach();
+                        }
+                    },
+                    false,
+                ),
+                Action::new(
+                    Img::default().icon(Icon::Settings),
+                    "Clipboard Settings",
+                    Some(Shortcut::new(",").cmd()),
+                    |_, cx| {
+                        StateModel::update(|this, cx| this.push(ClipboardSettingsBuilder, cx), cx);
+                    },
+                    false,
+                ),
+            ],
             cx,
         );
 
@@ -97,6 +184,8 @@ impl StateViewBuilder for ClipboardListBuilder {
                 ("Text", "Text Only"),
                 ("Link", "Links Only"),
                 ("Image", "Images Only"),
+                ("Color", "Colors Only"),
+                ("File", "Files Only"),
             ],
             cx,
         );
@@ -104,6 +193,7 @@ impl StateViewBuilder for ClipboardListBuilder {
         AsyncListItems::loader(&self.view, &context.actions, cx);
         let view = self.view.clone();
         ListBuilder::new()
+            .multi_select()
             .build(
                 move |list, _, cx| {
                     let t = list.actions.get_dropdown_value(cx);
@@ -114,7 +204,9 @@ impl StateViewBuilder for ClipboardListBuilder {
                         items.get(&t).cloned().unwrap_or_default()
                     };
 
-                    items.sort_by_key(|item| Reverse(item.get_meta::<Timestamp>(cx).unwrap()));
+                    items.sort_by_key(|item| {
+                        Reverse(item.get_meta::<ClipboardListItem>(cx).unwrap().copied_last)
+                    });
                     Ok(Some(items))
                 },
                 context,
@@ -130,6 +222,17 @@ enum ClipboardKind {
         characters: u64,
         words: u64,
         text: String,
+        // Full text of entries above the size cap, `text` then only holds a preview
+        #[serde(default)]
+        file: Option<PathBuf>,
+    },
+    RichText {
+        html: String,
+        text: String,
+    },
+    Color {
+        text: String,
+        rgb: (u8, u8, u8),
     },
     Url {
         url: String,
@@ -142,6 +245,129 @@ enum ClipboardKind {
         thumbnail: PathBuf,
         path: PathBuf,
     },
+    Files {
+        paths: Vec<PathBuf>,
+    },
+}
+
+fn text_file(id: u64) -> PathBuf {
+    paths().cache.join("clipboard").join(format!("{}.txt", id))
+}
+
+fn qr_file(id: u64) -> PathBuf {
+    paths()
+        .cache
+        .join("clipboard")
+        .join(format!("{}.qr.png", id))
+}
+
+// Byte mode capacity of the largest QR code at the default error correction level
+static QR_MAX_BYTES: usize = 2331;
+
+// Black on white, including the quiet zone scanners need around the code
+fn render_qr(text: &str, path: &Path) -> anyhow::Result<()> {
+    let code = QrCode::new(text.as_bytes())?;
+    let width = code.width() as u32;
+    let colors = code.to_colors();
+    let (scale, margin) = (8, 4);
+    let size = (width + margin * 2) * scale;
+    let image = ImageBuffer::from_fn(size, size, |x, y| {
+        let (x, y) = (x / scale, y / scale);
+        let inside = (margin..width + margin).contains(&x) && (margin..width + margin).contains(&y);
+        let dark =
+            inside && colors[((y - margin) * width + x - margin) as usize] == qrcode::Color::Dark;
+        image::Luma([if dark { 0u8 } else { 255 }])
+    });
+    image.save(path)?;
+    Ok(())
+}
+
+// Swaps the preview of a text or url entry for its QR code and back
+fn toggle_qr(id: u64, actions: &mut Actions, cx: &mut WindowContext) {
+    let Some(preview) = actions
+        .active
+        .as_ref()
+        .and_then(|item| item.view.clone().downcast::<ClipboardPreview>().ok())
+    else {
+        return;
+    };
+    if preview.read(cx).qr.is_some() {
+        preview.update(cx, |this, cx| {
+            this.qr = None;
+            cx.notify();
+        });
+        return;
+    }
+    let text = match preview.read(cx).detail.kind.clone() {
+        ClipboardKind::Text { text, file, .. } => ClipboardKind::full_text(&text, &file),
+        ClipboardKind::Url { url, .. } => url,
+        _ => return,
+    };
+    if text.len() > QR_MAX_BYTES {
+        actions.toast.error("Too long to fit in a QR code", cx);
+        return;
+    }
+    let path = qr_file(id);
+    let mut toast = actions.toast.clone();
+    let preview = preview.downgrade();
+    cx.spawn(|mut cx| async move {
+        let result = cx
+            .background_executor()
+            .spawn({
+                let path = path.clone();
+                async move { render_qr(&text, &path) }
+            })
+            .await;
+        match result {
+            Ok(()) => {
+                let _ = preview.update(&mut cx, |this, cx| {
+                    this.qr = Some(path);
+                    cx.notify();
+                });
+            }
+            Err(err) => {
+                error!("Failed to render QR code: {:?}", err);
+                toast.error("Failed to render QR code", &mut cx);
+            }
+        }
+    })
+    .detach();
+}
+
+impl ClipboardKind {
+    // Huge texts only keep a preview in the database, the rest is written next to the images
+    fn text(id: u64, text: &str) -> Self {
+        let characters = text.chars().count() as u64;
+        let words = text.split_whitespace().count() as u64;
+        let max = ClipboardSettings::get().max_text_size;
+        if text.len() > max {
+            let file = text_file(id);
+            if std::fs::write(&file, text).is_ok() {
+                let mut end = max;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                return Self::Text {
+                    characters,
+                    words,
+                    text: format!("{}…", &text[..end]),
+                    file: Some(file),
+                };
+            }
+        }
+        Self::Text {
+            characters,
+            words,
+            text: text.to_string(),
+            file: None,
+        }
+    }
+    // Reads capped texts back from disk
+    fn full_text(text: &str, file: &Option<PathBuf>) -> String {
+        file.as_ref()
+            .and_then(|file| std::fs::read_to_string(file).ok())
+            .unwrap_or_else(|| text.to_string())
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, Collection)]
@@ -152,21 +378,169 @@ struct ClipboardDetail {
     application: String,
     application_icon: Option<PathBuf>,
     kind: ClipboardKind,
+    // Perceptual hash of images, filled in once the thumbnail has been generated
+    #[serde(default)]
+    phash: Option<u64>,
+}
+
+impl Migrate for ClipboardDetail {}
+
+// Differing bits up to which two image hashes count as the same picture
+static SIMILAR_IMAGE_DISTANCE: u32 = 4;
+// Image hashes are split into this many slices, similar ones always share at least one of them
+static PHASH_BANDS: u64 = SIMILAR_IMAGE_DISTANCE as u64 + 1;
+
+// Ids of the images whose hash has a given value in a given slice, so finding similar ones
+// doesn't mean loading every clipboard entry
+#[derive(Clone, Serialize, Deserialize, Collection)]
+#[collection(name = "clipboard.phash")]
+struct ClipboardPhashBucket {
+    #[natural_id]
+    key: u64,
+    ids: Vec<u64>,
+}
+
+impl Migrate for ClipboardPhashBucket {}
+
+// One bucket key per slice, the slice index in the top byte and its bits below
+fn phash_buckets(phash: u64) -> Vec<u64> {
+    let width = 64 / PHASH_BANDS;
+    (0..PHASH_BANDS)
+        .map(|band| {
+            let shift = band * width;
+            // The last slice takes the remaining bits
+            let bits = if band == PHASH_BANDS - 1 {
+                64 - shift
+            } else {
+                width
+            };
+            (band << 56) | ((phash >> shift) & ((1 << bits) - 1))
+        })
+        .collect()
+}
+
+// Difference hash, one bit per horizontally adjacent pixel pair of a 9x8 grayscale version
+fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+    let mut hash = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            hash <<= 1;
+            if small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0] {
+                hash |= 1;
+            }
+        }
+    }
+    hash
+}
+
+impl ClipboardDetail {
+    // Stores the hash and returns an earlier image that looks the same, if any
+    fn set_phash(id: u64, phash: u64, find_similar: bool) -> anyhow::Result<Option<u64>> {
+        if let Some(mut detail) = Self::get(&id, db_detail())? {
+            detail.contents.phash = Some(phash);
+            detail.update(db_detail())?;
+        }
+        let mut similar = None;
+        for key in phash_buckets(phash) {
+            let bucket = ClipboardPhashBucket::get(&key, db_phash())?
+                .map(|bucket| bucket.contents.ids)
+                .unwrap_or_default();
+            let mut ids = Vec::with_capacity(bucket.len() + 1);
+            for other in bucket {
+                if other == id {
+                    continue;
+                }
+                // Deleted entries are dropped from the bucket on the way
+                let Some(detail) = Self::get(&other, db_detail())? else {
+                    continue;
+                };
+                if find_similar
+                    && similar.is_none()
+                    && detail
+                        .contents
+                        .phash
+                        .is_some_and(|other| (other ^ phash).count_ones() <= SIMILAR_IMAGE_DISTANCE)
+                {
+                    similar = Some(other);
+                }
+                ids.push(other);
+            }
+            ids.push(id);
+            ClipboardPhashBucket { key, ids }
+                .overwrite_into(&key, db_phash())
+                .map_err(|err| err.error)?;
+        }
+        Ok(similar)
+    }
+}
+
+struct ClipboardBackup {
+    item: ClipboardListItem,
+    detail: Option<ClipboardDetail>,
+    files: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl ClipboardBackup {
+    fn restore(
+        &self,
+        view: WeakView<AsyncListItems>,
+        cx: &mut WindowContext,
+    ) -> anyhow::Result<()> {
+        for (path, bytes) in &self.files {
+            std::fs::write(path, bytes)?;
+        }
+        self.item
+            .clone()
+            .overwrite_into(&self.item.id, db_items())
+            .map_err(|err| err.error)?;
+        if let Some(detail) = &self.detail {
+            detail
+                .clone()
+                .overwrite_into(&detail.id, db_detail())
+                .map_err(|err| err.error)?;
+        }
+        let _ = view.update(cx, |view, cx| {
+            let item = self.item.get_item(cx);
+            view.push(self.item.kind.clone().into(), item, cx);
+        });
+        Ok(())
+    }
+}
+
+// Portable copy of the clipboard history, images are embedded together with their thumbnails
+#[derive(Serialize, Deserialize)]
+struct ClipboardArchive {
+    entries: Vec<ClipboardArchiveEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClipboardArchiveEntry {
+    item: ClipboardListItem,
+    detail: Option<ClipboardDetail>,
+    // Base64 encoded image and thumbnail
+    image: Option<(String, String)>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 enum ClipboardListItemKind {
     Text,
+    RichText,
+    Color { rgb: (u8, u8, u8) },
     Url { url: String },
     Image { thumbnail: PathBuf },
+    Files { paths: Vec<PathBuf> },
 }
 
 impl From<ClipboardKind> for ClipboardListItemKind {
     fn from(val: ClipboardKind) -> Self {
         match val {
             ClipboardKind::Text { .. } => ClipboardListItemKind::Text,
+            ClipboardKind::RichText { .. } => ClipboardListItemKind::RichText,
+            ClipboardKind::Color { rgb, .. } => ClipboardListItemKind::Color { rgb },
             ClipboardKind::Url { url, .. } => ClipboardListItemKind::Url { url },
             ClipboardKind::Image { thumbnail, .. } => ClipboardListItemKind::Image { thumbnail },
+            ClipboardKind::Files { paths } => ClipboardListItemKind::Files { paths },
         }
     }
 }
@@ -174,9 +548,11 @@ impl From<ClipboardKind> for ClipboardListItemKind {
 impl From<ClipboardListItemKind> for String {
     fn from(val: ClipboardListItemKind) -> Self {
         match val {
-            ClipboardListItemKind::Text => "Text".to_string(),
+            ClipboardListItemKind::Text | ClipboardListItemKind::RichText => "Text".to_string(),
+            ClipboardListItemKind::Color { .. } => "Color".to_string(),
             ClipboardListItemKind::Url { .. } => "Link".to_string(),
             ClipboardListItemKind::Image { .. } => "Image".to_string(),
+            ClipboardListItemKind::Files { .. } => "File".to_string(),
         }
     }
 }
@@ -193,6 +569,8 @@ struct ClipboardListItem {
     copy_count: u32,
 }
 
+impl Migrate for ClipboardListItem {}
+
 impl ClipboardListItem {
     fn new(id: u64, title: impl ToString, kind: ClipboardKind, app: &Option<AppData>) -> Self {
         let (application, application_icon) = app
@@ -214,11 +592,47 @@ impl ClipboardListItem {
             application,
             application_icon,
             kind,
+            phash: None,
         };
         let _ = detail.push_into(db_detail());
 
         item
     }
+    // Folds a freshly recorded image into the earlier entry it looks the same as
+    fn merge(
+        duplicate: u64,
+        original: u64,
+        view: WeakView<AsyncListItems>,
+        cx: &mut WindowContext,
+    ) -> anyhow::Result<()> {
+        let (Some(duplicate), Some(mut item)) = (
+            Self::get(&duplicate, db_items())?,
+            Self::get(&original, db_items())?,
+        ) else {
+            return Ok(());
+        };
+        item.contents.copied_last = duplicate.contents.copied_last;
+        item.contents.copy_count += duplicate.contents.copy_count;
+        item.update(db_items())?;
+        duplicate.contents.delete(view.clone(), cx)?;
+        let entry = item.contents;
+        let _ = view.update(cx, |view, cx| {
+            let item = entry.get_item(cx);
+            view.push(entry.kind.clone().into(), item, cx);
+        });
+        Ok(())
+    }
+    // Bumps an entry that has been copied before, or creates a new one
+    fn record(id: u64, create: impl FnOnce() -> Self) -> Self {
+        if let Ok(Some(mut item)) = Self::get(&id, db_items()) {
+            item.contents.copied_last = Timestamp::now();
+            item.contents.copy_count += 1;
+            let _ = item.update(db_items());
+            item.contents.clone()
+        } else {
+            create()
+        }
+    }
     fn get_item(&self, cx: &mut ViewContext<AsyncListItems>) -> Item {
         ItemBuilder::new(
             self.id,
@@ -229,11 +643,17 @@ impl ClipboardListItem {
                             .file(thumbnail)
                             .object_fit(ObjectFit::Contain),
                     ),
+                    ClipboardListItemKind::Color { rgb } => Some(Img::default().dot(to_hsla(rgb))),
                     ClipboardListItemKind::Url { url } => Some(
                         Img::default()
                             .mask(ImgMask::Rounded)
                             .favicon(url, Icon::Link, cx),
                     ),
+                    ClipboardListItemKind::Files { paths }
+                        if paths.len() == 1 && paths[0].is_dir() =>
+                    {
+                        Some(Img::default().icon(Icon::Folder))
+                    }
                     _ => Some(Img::default().icon(Icon::File)),
                 },
                 self.title.clone(),
@@ -242,6 +662,7 @@ impl ClipboardListItem {
             ),
         )
         .keywords(vec![self.title.clone()])
+        .section(Some(format_day_section(self.copied_last)))
         .preview(0.66, {
             let id = self.id;
             move |cx| StateItem::init(ClipboardPreview::init(id, cx), false, cx)
@@ -250,27 +671,92 @@ impl ClipboardListItem {
             let mut actions = vec![
                 Action::new(
                     Img::default().icon(Icon::ClipboardPaste),
-                    "Paste",
+                    match self.kind {
+                        ClipboardListItemKind::RichText => "Paste as Rich Text",
+                        _ => "Paste",
+                    },
                     None,
                     {
                         let id = self.id;
-                        move |_, cx| {
+                        move |actions, cx| {
+                            if !require_accessibility("paste", &mut actions.toast, cx) {
+                                return;
+                            }
                             let detail = ClipboardDetail::get(&id, db_detail()).unwrap().unwrap();
                             let _ = cx.update_window(cx.window_handle(), |_, cx| {
                                 match detail.contents.kind.clone() {
-                                    ClipboardKind::Text { text, .. }
+                                    ClipboardKind::Text { text, file, .. } => {
+                                        let text = ClipboardKind::full_text(&text, &file);
+                                        close_and_paste(text.as_str(), false, cx);
+                                    }
+                                    ClipboardKind::Color { text, .. }
                                     | ClipboardKind::Url { url: text, .. } => {
                                         close_and_paste(text.as_str(), false, cx);
                                     }
+                                    ClipboardKind::RichText { html, text } => {
+                                        close_and_paste_html(&html, &text, cx);
+                                    }
                                     ClipboardKind::Image { path, .. } => {
                                         close_and_paste_file(&path, cx);
                                     }
+                                    ClipboardKind::Files { paths } => {
+                                        if let [path] = paths.as_slice() {
+                                            close_and_paste_file(path, cx);
+                                        } else {
+                                            let paths: Vec<String> = paths
+                                                .iter()
+                                                .map(|path| path.to_string_lossy().to_string())
+                                                .collect();
+                                            close_and_paste(&paths.join("\n"), false, cx);
+                                        }
+                                    }
                                 }
                             });
                         }
                     },
                     false,
                 ),
+                Action::new(
+                    Img::default().icon(Icon::ClipboardCopy),
+                    "Copy to Clipboard",
+                    Some(Shortcut::new("c").cmd().shift()),
+                    {
+                        let id = self.id;
+                        move |actions, cx| {
+                            let detail = ClipboardDetail::get(&id, db_detail()).unwrap().unwrap();
+                            let result = match detail.contents.kind {
+                                ClipboardKind::Text { text, file, .. } => {
+                                    copy_text(&ClipboardKind::full_text(&text, &file), cx)
+                                }
+                                ClipboardKind::Color { text, .. }
+                                | ClipboardKind::Url { url: text, .. } => copy_text(&text, cx),
+                                ClipboardKind::RichText { html, text } => {
+                                    copy_html(&html, &text, cx)
+                                }
+                                ClipboardKind::Image { path, .. } => copy_image(&path, cx),
+                                // arboard can't write file references, so the paths are copied instead
+                                ClipboardKind::Files { paths } => {
+                                    let paths: Vec<String> = paths
+                                        .iter()
+                                        .map(|path| path.to_string_lossy().to_string())
+                                        .collect();
+                                    copy_text(&paths.join("\n"), cx)
+                                }
+                            };
+                            if let Err(err) = result {
+                                error!("Failed to copy clipboard entry: {:?}", err);
+                                actions.toast.error("Failed to copy to clipboard", cx);
+                                return;
+                            }
+                            actions.toast.floating(
+                                "Copied to clipboard",
+                                Some(Icon::Clipboard),
+                                cx,
+                            );
+                        }
+                    },
+                    false,
+                ),
                 Action::new(
                     Img::default().icon(Icon::Trash),
                     "Delete",
@@ -279,38 +765,149 @@ impl ClipboardListItem {
                         let self_clone = self.clone();
                         let view = cx.view().clone();
                         move |actions, cx| {
+                            let backup = self_clone.backup();
                             if let Err(err) = self_clone.delete(view.downgrade(), cx) {
                                 error!("Failed to delete clipboard entry: {:?}", err);
                                 actions.toast.error("Failed to delete clipboard entry", cx);
                             } else {
-                                actions
-                                    .toast
-                                    .success("Successfully deleted clipboard entry", cx);
+                                let view = view.downgrade();
+                                actions.toast.success_with_action(
+                                    "Successfully deleted clipboard entry",
+                                    "Undo",
+                                    move |cx| {
+                                        if let Err(err) = backup.restore(view.clone(), cx) {
+                                            error!("Failed to restore clipboard entry: {:?}", err);
+                                        }
+                                    },
+                                    cx,
+                                );
                             }
                         }
                     },
                     false,
-                ),
+                )
+                .bulk({
+                    let view = cx.view().clone();
+                    move |items, actions, cx| {
+                        let mut failed = false;
+                        for item in items {
+                            let Some(entry) = item.get_meta::<ClipboardListItem>(cx) else {
+                                continue;
+                            };
+                            if let Err(err) = entry.delete(view.downgrade(), cx) {
+                                error!("Failed to delete clipboard entry: {:?}", err);
+                                failed = true;
+                            }
+                        }
+                        if failed {
+                            actions
+                                .toast
+                                .error("Failed to delete clipboard entries", cx);
+                        } else {
+                            actions
+                                .toast
+                                .success("Successfully deleted clipboard entries", cx);
+                        }
+                    }
+                }),
             ];
             match self.kind.clone() {
-                ClipboardListItemKind::Image { thumbnail } => actions.insert(
+                ClipboardListItemKind::RichText => actions.insert(
                     1,
                     Action::new(
-                        Img::default().icon(Icon::ScanEye),
-                        "Copy Text to Clipboard",
+                        Img::default().icon(Icon::ClipboardPaste),
+                        "Paste as Plain Text",
                         Some(Shortcut::new("enter").shift()),
                         {
-                            let mut path = thumbnail.clone();
-                            path.pop();
-                            path = path.join(format!("{}.png", self.id));
+                            let id = self.id;
                             move |actions, cx| {
-                                ocr(&path);
-                                actions.toast.success("Copied Text to Clipboard", cx);
+                                if !require_accessibility("paste", &mut actions.toast, cx) {
+                                    return;
+                                }
+                                let detail =
+                                    ClipboardDetail::get(&id, db_detail()).unwrap().unwrap();
+                                if let ClipboardKind::RichText { text, .. } = detail.contents.kind {
+                                    close_and_paste(text.as_str(), false, cx);
+                                }
                             }
                         },
                         false,
                     ),
                 ),
+                ClipboardListItemKind::Color { rgb } => {
+                    for (i, (label, value)) in
+                        [("Copy as Hex", to_hex(rgb)), ("Copy as RGB", to_rgb(rgb))]
+                            .into_iter()
+                            .enumerate()
+                    {
+                        actions.insert(
+                            i + 1,
+                            Action::new(
+                                Img::default().icon(Icon::Palette),
+                                label,
+                                (i == 0).then(|| Shortcut::new("enter").shift()),
+                                move |actions, cx| {
+                                    if let Err(err) = copy_text(&value, cx) {
+                                        error!("Failed to copy color: {:?}", err);
+                                        actions.toast.error("Failed to copy to clipboard", cx);
+                                        return;
+                                    }
+                                    actions.toast.floating(
+                                        "Copied to clipboard",
+                                        Some(Icon::Clipboard),
+                                        cx,
+                                    );
+                                },
+                                false,
+                            ),
+                        );
+                    }
+                }
+                ClipboardListItemKind::Image { thumbnail } => {
+                    actions.insert(
+                        1,
+                        Action::new(
+                            Img::default().icon(Icon::ScanEye),
+                            "Copy Text to Clipboard",
+                            Some(Shortcut::new("enter").shift()),
+                            {
+                                let mut path = thumbnail.clone();
+                                path.pop();
+                                path = path.join(format!("{}.png", self.id));
+                                move |actions, cx| {
+                                    ocr(&path);
+                                    actions.toast.success("Copied Text to Clipboard", cx);
+                                }
+                            },
+                            false,
+                        ),
+                    );
+                    actions.insert(
+                        2,
+                        Action::new(
+                            Img::default().icon(Icon::FolderOpen),
+                            REVEAL_LABEL,
+                            Some(Shortcut::new("enter").cmd().shift()),
+                            {
+                                let id = self.id;
+                                move |actions, cx| {
+                                    let Some(ClipboardKind::Image { path, .. }) =
+                                        ClipboardDetail::get(&id, db_detail())
+                                            .ok()
+                                            .flatten()
+                                            .map(|detail| detail.contents.kind)
+                                    else {
+                                        actions.toast.error("Image file not found", cx);
+                                        return;
+                                    };
+                                    Window::close(cx);
+                                    reveal_path(&path);
+                                }
+                            },
+                            false,
+                        ),
+                    );
+                }
                 ClipboardListItemKind::Url { url } => actions.insert(
                     1,
                     Action::new(
@@ -331,11 +928,60 @@ impl ClipboardListItem {
                         false,
                     ),
                 ),
+                ClipboardListItemKind::Files { paths } => {
+                    actions.insert(
+                        1,
+                        Action::new(
+                            Img::default().icon(Icon::ArrowUpRightFromSquare),
+                            "Open",
+                            Some(Shortcut::new("enter").shift()),
+                            {
+                                let paths = paths.clone();
+                                move |_, cx| {
+                                    Window::close(cx);
+                                    for path in paths.iter() {
+                                        open_path(path);
+                                    }
+                                }
+                            },
+                            false,
+                        ),
+                    );
+                    actions.insert(
+                        2,
+                        Action::new(
+                            Img::default().icon(Icon::FolderOpen),
+                            REVEAL_LABEL,
+                            Some(Shortcut::new("enter").cmd().shift()),
+                            {
+                                let path = paths[0].clone();
+                                move |_, cx| {
+                                    Window::close(cx);
+                                    reveal_path(&path);
+                                }
+                            },
+                            false,
+                        ),
+                    );
+                }
                 _ => {}
             }
+            if matches!(
+                self.kind,
+                ClipboardListItemKind::Text | ClipboardListItemKind::Url { .. }
+            ) {
+                let id = self.id;
+                actions.push(Action::new(
+                    Img::default().icon(Icon::QrCode),
+                    "Toggle QR Code",
+                    Some(Shortcut::new("q").cmd().shift()),
+                    move |actions, cx| toggle_qr(id, actions, cx),
+                    false,
+                ));
+            }
             actions
         })
-        .meta(cx.new_model(|_| self.copied_last).into_any())
+        .meta(cx.new_model(|_| self.clone()).into_any())
         .build()
     }
     fn delete(&self, view: WeakView<AsyncListItems>, cx: &mut WindowContext) -> anyhow::Result<()> {
@@ -355,8 +1001,140 @@ impl ClipboardListItem {
             let _ = std::fs::remove_file(thumbnail);
             let _ = std::fs::remove_file(path.join(format!("{}.png", self.id)));
         }
+        if let ClipboardListItemKind::Text = self.kind {
+            let _ = std::fs::remove_file(text_file(self.id));
+        }
+        let _ = std::fs::remove_file(qr_file(self.id));
         Ok(())
     }
+    // Snapshot taken before deleting, so the entry can be restored
+    fn backup(&self) -> ClipboardBackup {
+        let mut files = vec![];
+        if let ClipboardListItemKind::Image { thumbnail } = self.kind.clone() {
+            let mut path = thumbnail.clone();
+            path.pop();
+            for path in [thumbnail, path.join(format!("{}.png", self.id))] {
+                if let Ok(bytes) = std::fs::read(&path) {
+                    files.push((path, bytes));
+                }
+            }
+        }
+        if let Ok(bytes) = std::fs::read(text_file(self.id)) {
+            files.push((text_file(self.id), bytes));
+        }
+        ClipboardBackup {
+            item: self.clone(),
+            detail: ClipboardDetail::get(&self.id, db_detail())
+                .ok()
+                .flatten()
+                .map(|detail| detail.contents),
+            files,
+        }
+    }
+    fn export(path: &Path) -> anyhow::Result<usize> {
+        let mut entries = vec![];
+        for item in Self::all(db_items()).query()? {
+            let item = item.contents;
+            let image = match item.kind.clone() {
+                ClipboardListItemKind::Image { thumbnail } => {
+                    let mut path = thumbnail.clone();
+                    path.pop();
+                    // Entries whose image got cleaned up can't be restored anyway
+                    let (Ok(image), Ok(thumbnail)) = (
+                        std::fs::read(path.join(format!("{}.png", item.id))),
+                        std::fs::read(thumbnail),
+                    ) else {
+                        continue;
+                    };
+                    Some((STANDARD.encode(image), STANDARD.encode(thumbnail)))
+                }
+                _ => None,
+            };
+            let mut detail =
+                ClipboardDetail::get(&item.id, db_detail())?.map(|detail| detail.contents);
+            // The archive is self contained, so capped texts are inlined again
+            if let Some(ClipboardKind::Text { text, file, .. }) =
+                detail.as_mut().map(|detail| &mut detail.kind)
+            {
+                *text = ClipboardKind::full_text(text, file);
+                *file = None;
+            }
+            entries.push(ClipboardArchiveEntry {
+                detail,
+                item,
+                image,
+            });
+        }
+        let count = entries.len();
+        std::fs::write(path, serde_json::to_vec(&ClipboardArchive { entries })?)?;
+        Ok(count)
+    }
+    // Merges an exported history, colliding entries keep the higher count and latest timestamps
+    fn import(
+        path: &Path,
+        view: WeakView<AsyncListItems>,
+        cx: &mut WindowContext,
+    ) -> anyhow::Result<usize> {
+        let archive: ClipboardArchive = serde_json::from_slice(&std::fs::read(path)?)?;
+        let cache = paths().cache.join("clipboard");
+        std::fs::create_dir_all(&cache)?;
+        let count = archive.entries.len();
+        for ClipboardArchiveEntry {
+            mut item,
+            mut detail,
+            image,
+        } in archive.entries
+        {
+            // Images are stored by id hash, so paths from the other machine are rewritten
+            if let Some((image, thumbnail)) = image {
+                let path = cache.join(format!("{}.png", item.id));
+                let thumbnail_path = cache.join(format!("{}.thumb.png", item.id));
+                std::fs::write(&path, STANDARD.decode(image)?)?;
+                std::fs::write(&thumbnail_path, STANDARD.decode(thumbnail)?)?;
+                item.kind = ClipboardListItemKind::Image {
+                    thumbnail: thumbnail_path.clone(),
+                };
+                if let Some(ClipboardKind::Image {
+                    path: image_path,
+                    thumbnail,
+                    ..
+                }) = detail.as_mut().map(|detail| &mut detail.kind)
+                {
+                    *image_path = path;
+                    *thumbnail = thumbnail_path;
+                }
+            }
+            if let Some(detail) = detail.as_mut() {
+                if let ClipboardKind::Text { text, .. } = &detail.kind {
+                    detail.kind = ClipboardKind::text(item.id, text);
+                }
+            }
+            let item = if let Some(mut existing) = Self::get(&item.id, db_items())? {
+                let contents = &mut existing.contents;
+                contents.copy_count = contents.copy_count.max(item.copy_count);
+                contents.copied_first = contents.copied_first.min(item.copied_first);
+                contents.copied_last = contents.copied_last.max(item.copied_last);
+                existing.update(db_items())?;
+                existing.contents
+            } else {
+                item.clone()
+                    .overwrite_into(&item.id, db_items())
+                    .map_err(|err| err.error)?;
+                if let Some(detail) = detail {
+                    detail
+                        .clone()
+                        .overwrite_into(&detail.id, db_detail())
+                        .map_err(|err| err.error)?;
+                }
+                item
+            };
+            let _ = view.update(cx, |view, cx| {
+                let list_item = item.get_item(cx);
+                view.push(item.kind.clone().into(), list_item, cx);
+            });
+        }
+        Ok(count)
+    }
     fn prune(
         age: Span,
         view: WeakView<AsyncListItems>,
@@ -380,6 +1158,8 @@ struct ClipboardPreview {
     detail: ClipboardDetail,
     bounds: Model<Bounds<Pixels>>,
     state: ListState,
+    // Rendered QR code shown in place of the contents
+    qr: Option<PathBuf>,
 }
 
 impl ClipboardPreview {
@@ -400,14 +1180,32 @@ impl ClipboardPreview {
             item,
             detail: detail.clone(),
             bounds: bounds.clone(),
+            qr: None,
             state: ListState::new(
                 1,
                 ListAlignment::Top,
                 Pixels(100.0),
                 move |_, cx| match detail.kind.clone() {
-                    ClipboardKind::Text { text, .. } | ClipboardKind::Url { url: text, .. } => {
+                    ClipboardKind::Text { text, .. }
+                    | ClipboardKind::RichText { text, .. }
+                    | ClipboardKind::Url { url: text, .. } => {
                         div().p_2().w_full().child(text.clone()).into_any_element()
                     }
+                    ClipboardKind::Files { paths } => div()
+                        .p_2()
+                        .w_full()
+                        .children(
+                            paths
+                                .iter()
+                                .map(|path| div().child(path.to_string_lossy().to_string())),
+                        )
+                        .into_any_element(),
+                    ClipboardKind::Color { text, rgb } => div()
+                        .p_2()
+                        .w_full()
+                        .child(div().h_24().w_full().mb_2().rounded_xl().bg(to_hsla(rgb)))
+                        .child(text.clone())
+                        .into_any_element(),
                     ClipboardKind::Image {
                         width,
                         height,
@@ -505,6 +1303,24 @@ impl Render for ClipboardPreview {
                 ));
                 table.push(("Words".to_string(), words.to_string().into_any_element()));
             }
+            ClipboardKind::RichText { text, .. } => {
+                table.push((
+                    "Characters".to_string(),
+                    text.chars().count().to_string().into_any_element(),
+                ));
+                table.push((
+                    "Words".to_string(),
+                    text.split_whitespace()
+                        .count()
+                        .to_string()
+                        .into_any_element(),
+                ));
+                table.push(("Format".to_string(), "HTML".into_any_element()));
+            }
+            ClipboardKind::Color { rgb, .. } => {
+                table.push(("Hex".to_string(), to_hex(*rgb).into_any_element()));
+                table.push(("RGB".to_string(), to_rgb(*rgb).into_any_element()));
+            }
             ClipboardKind::Url {
                 characters, title, ..
             } => {
@@ -522,6 +1338,12 @@ impl Render for ClipboardPreview {
                     format!("{}x{}", width, height).into_any_element(),
                 ));
             }
+            ClipboardKind::Files { paths } => {
+                table.push((
+                    "Files".to_string(),
+                    paths.len().to_string().into_any_element(),
+                ));
+            }
         }
         div()
             .ml_2()
@@ -533,7 +1355,16 @@ impl Render for ClipboardPreview {
             .flex_col()
             .justify_between()
             .text_xs()
-            .child(
+            .child(if let Some(qr) = self.qr.clone() {
+                let bounds = self.bounds.read(cx);
+                let size = Pixels(bounds.size.width.0.min(bounds.size.height.0));
+                div()
+                    .flex_1()
+                    .flex()
+                    .items_center()
+                    .justify_center()
+                    .child(img(ImageSource::File(Arc::new(qr))).w(size).h(size))
+            } else {
                 div().flex_1().font_family(theme.font_mono.clone()).child(
                     canvas(
                         {
@@ -555,8 +1386,8 @@ impl Render for ClipboardPreview {
                         |_bounds, mut list, cx| list.paint(cx),
                     )
                     .size_full(),
-                ),
-            )
+                )
+            })
             .child(
                 div()
                     .border_t_1()
@@ -591,6 +1422,36 @@ impl StateViewBuilder for ClipboardPreview {
     }
 }
 
+// Polling slows down while the window is hidden, since nobody is looking at the history
+static HIDDEN_POLL_BACKOFF: u32 = 4;
+static TITLE_LENGTH: usize = 25;
+
+// Single line title for copied text, cut by chars since a byte offset may split a character
+fn text_title(text: &str) -> String {
+    let text = text.trim().replace('\n', " ");
+    if text.chars().count() <= TITLE_LENGTH {
+        return text;
+    }
+    let mut title: String = text.chars().take(TITLE_LENGTH).collect();
+    title.push_str("...");
+    title
+}
+
+// Texts above this aren't recorded at all, not even as a file
+static TEXT_HARD_CAP: usize = 32 * 1024 * 1024;
+
+// Shows the error in whatever view is active, since the watcher has no toast of its own
+fn toast_error(message: &str, cx: &mut WindowContext) {
+    let mut toast = {
+        let state = cx.global::<StateModel>().inner.read(cx);
+        let Some(item) = state.stack.last() else {
+            return;
+        };
+        item.actions.read(cx).toast.clone()
+    };
+    toast.error(message, cx);
+}
+
 pub(super) fn db_items() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(Db::init_collection::<ClipboardListItem>)
@@ -601,6 +1462,11 @@ pub(super) fn db_detail() -> &'static Database {
     DB.get_or_init(Db::init_collection::<ClipboardDetail>)
 }
 
+fn db_phash() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(Db::init_collection::<ClipboardPhashBucket>)
+}
+
 pub struct ClipboardCommandBuilder;
 command!(ClipboardCommandBuilder);
 impl RootCommandBuilder for ClipboardCommandBuilder {
@@ -619,135 +1485,209 @@ impl RootCommandBuilder for ClipboardCommandBuilder {
             cx.spawn(|view, cx| async move {
                 let mut cp = Clipboard::new().unwrap();
                 let mut hash: u64 = 0;
+                #[cfg(target_os = "linux")]
+                let mut primary_hash: u64 = 0;
                 let cache = paths().cache.join("clipboard");
                 if !cache.exists() {
                     let _ = std::fs::create_dir_all(&cache);
                 }
                 let mut now = Instant::now();
+                // Near duplicate images found while generating thumbnails, as (duplicate, original)
+                let (similar_sender, similar_receiver) =
+                    crossbeam_channel::unbounded::<(u64, u64)>();
+                let mut settings = ClipboardSettings::get();
+                let mut settings_at = Instant::now();
                 clipboard(
                     |cx| {
-                        if Instant::now() - now > Duration::from_secs(3600) {
-                            now = Instant::now();
-                            // Prune clipboard history every hour, keeping entries for a week
+                        // The settings live in the db, so only re-read them every now and then
+                        if settings_at.elapsed() > Duration::from_secs(10) {
+                            settings = ClipboardSettings::get();
+                            settings_at = Instant::now();
+                        }
+                        while let Ok((duplicate, original)) = similar_receiver.try_recv() {
                             let _ = cx.update_window(cx.window_handle(), |_, cx| {
-                                let _ = ClipboardListItem::prune(
-                                    ToSpan::seconds(60 * 60 * 24 * 7),
-                                    view.clone(),
-                                    cx,
-                                );
+                                if let Err(err) =
+                                    ClipboardListItem::merge(duplicate, original, view.clone(), cx)
+                                {
+                                    error!("Failed to merge similar images: {:?}", err);
+                                }
                             });
                         }
+                        if Instant::now() - now > Duration::from_secs(3600) {
+                            now = Instant::now();
+                            // Prune clipboard history every hour, keeping entries as long as configured
+                            if let Some(age) = settings.retention.span() {
+                                let _ = cx.update_window(cx.window_handle(), |_, cx| {
+                                    let _ = ClipboardListItem::prune(age, view.clone(), cx);
+                                });
+                            }
+                        }
 
-                        let app = get_frontmost_application_data();
-                        let condition = |app: &Option<AppData>, cx: &mut AsyncAppContext| {
+                        let watching = |cx: &mut AsyncAppContext| {
+                            // Our own copies switch the watcher off for a single change
                             if !ClipboardWatcher::is_enabled(cx) {
                                 ClipboardWatcher::enabled(cx);
                                 return false;
                             }
-
-                            // TODO: make this configurable and platform independent
-                            if let Some(app) = app {
-                                if matches!(
-                                    app.id.as_str(),
-                                    "com.apple.systempreferences" | "com.apple.keychainaccess"
-                                ) {
-                                    return false;
-                                }
-                            }
-
                             true
                         };
-                        if let Ok(text) = cp.get_text() {
-                            let mut hasher = DefaultHasher::new();
-                            text.hash(&mut hasher);
-                            let new_hash = hasher.finish();
-                            if new_hash != hash {
-                                hash = new_hash;
-                                if !condition(&app, cx) {
-                                    return;
-                                }
-                                let entry = if let Ok(Some(mut item)) =
-                                    ClipboardListItem::get(&hash, db_items())
-                                {
-                                    item.contents.copied_last = jiff::Timestamp::now();
-                                    item.contents.copy_count += 1;
-                                    let _ = item.update(db_items());
-                                    item.contents.clone()
-                                } else {
-                                    let url = Url::parse(&text);
-                                    if url.is_ok() && {
-                                        let url = url.unwrap();
-                                        !url.cannot_be_a_base() && url.scheme().starts_with("http")
-                                    } {
-                                        ClipboardListItem::new(
-                                            hash,
-                                            {
-                                                let mut text = text.trim().replace('\n', " ");
-                                                if text.len() > 25 {
-                                                    text.truncate(25);
-                                                    text.push_str("...");
-                                                }
-                                                text
-                                            },
-                                            ClipboardKind::Url {
-                                                characters: text.chars().count() as u64,
-                                                url: text,
-                                                title: "".to_string(),
-                                            },
-                                            &app,
-                                        )
-                                    } else {
-                                        ClipboardListItem::new(
-                                            hash,
-                                            {
-                                                let mut text = text.trim().replace('\n', " ");
-                                                if text.len() > 25 {
-                                                    text.truncate(25);
-                                                    text.push_str("...");
-                                                }
-                                                text
-                                            },
-                                            ClipboardKind::Text {
-                                                characters: text.chars().count() as u64,
-                                                words: text.split_whitespace().count() as u64,
-                                                text: text.clone(),
-                                            },
-                                            &app,
-                                        )
+                        // Looking up the source app can be slow, so entries are only created
+                        // once it has been looked up off the main thread
+                        let mut pending: Vec<
+                            Box<dyn FnOnce(&Option<AppData>) -> ClipboardListItem>,
+                        > = Vec::new();
+                        // Bailing out of the clipboard checks must not skip the PRIMARY selection
+                        'clipboard: {
+                            #[cfg(target_os = "linux")]
+                            if let Some(paths) = crate::platform::clipboard_files() {
+                                let mut hasher = DefaultHasher::new();
+                                paths.hash(&mut hasher);
+                                let new_hash = hasher.finish();
+                                if new_hash != hash {
+                                    hash = new_hash;
+                                    if !watching(cx) {
+                                        break 'clipboard;
                                     }
-                                };
-                                let _ = cx.update(|cx| {
-                                    let _ = view.update(cx, |view: &mut AsyncListItems, cx| {
-                                        let item = entry.get_item(cx);
-                                        view.push(entry.kind.into(), item, cx);
-                                    });
-                                });
+                                    let id = hash;
+                                    pending.push(Box::new(move |app| {
+                                        ClipboardListItem::record(id, || {
+                                            let title = match paths.as_slice() {
+                                                [path] => path
+                                                    .file_name()
+                                                    .map(|name| name.to_string_lossy().to_string())
+                                                    .unwrap_or_else(|| {
+                                                        path.to_string_lossy().to_string()
+                                                    }),
+                                                _ => format!("{} Files", paths.len()),
+                                            };
+                                            ClipboardListItem::new(
+                                                id,
+                                                title,
+                                                ClipboardKind::Files { paths },
+                                                app,
+                                            )
+                                        })
+                                    }));
+                                }
+                                break 'clipboard;
                             }
-                        } else if let Ok(image) = cp.get_image() {
-                            let mut hasher = DefaultHasher::new();
-                            image.bytes.hash(&mut hasher);
-                            let new_hash = hasher.finish();
-                            if new_hash != hash {
-                                hash = new_hash;
-                                if !condition(&app, cx) {
-                                    return;
+                            if let Ok(text) = cp.get_text() {
+                                let mut hasher = DefaultHasher::new();
+                                text.hash(&mut hasher);
+                                let new_hash = hasher.finish();
+                                if new_hash != hash {
+                                    hash = new_hash;
+                                    if !watching(cx) {
+                                        break 'clipboard;
+                                    }
+                                    if text.len() > TEXT_HARD_CAP {
+                                        let _ = cx.update(|cx| {
+                                            toast_error(
+                                                "Copied text is too large for the history",
+                                                cx,
+                                            )
+                                        });
+                                        break 'clipboard;
+                                    }
+                                    // The clipboard may have moved on once the entry is created
+                                    let html = cp.get().html().ok().filter(|html| !html.is_empty());
+                                    let id = hash;
+                                    pending.push(Box::new(move |app| {
+                                        if let Ok(Some(mut item)) =
+                                            ClipboardListItem::get(&id, db_items())
+                                        {
+                                            item.contents.copied_last = jiff::Timestamp::now();
+                                            item.contents.copy_count += 1;
+                                            let _ = item.update(db_items());
+                                            item.contents.clone()
+                                        } else if let Some(rgb) = parse_color(&text) {
+                                            ClipboardListItem::new(
+                                                id,
+                                                text.trim(),
+                                                ClipboardKind::Color {
+                                                    text: text.trim().to_string(),
+                                                    rgb,
+                                                },
+                                                app,
+                                            )
+                                        } else {
+                                            let url = Url::parse(&text);
+                                            if url.is_ok() && {
+                                                let url = url.unwrap();
+                                                !url.cannot_be_a_base()
+                                                    && url.scheme().starts_with("http")
+                                            } {
+                                                ClipboardListItem::new(
+                                                    id,
+                                                    text_title(&text),
+                                                    ClipboardKind::Url {
+                                                        characters: text.chars().count() as u64,
+                                                        url: text,
+                                                        title: "".to_string(),
+                                                    },
+                                                    app,
+                                                )
+                                            } else if let Some(html) = html {
+                                                // Keep the formatted flavor around so it can be pasted back
+                                                ClipboardListItem::new(
+                                                    id,
+                                                    text_title(&text),
+                                                    ClipboardKind::RichText {
+                                                        html,
+                                                        text: text.clone(),
+                                                    },
+                                                    app,
+                                                )
+                                            } else {
+                                                ClipboardListItem::new(
+                                                    id,
+                                                    text_title(&text),
+                                                    ClipboardKind::text(id, &text),
+                                                    app,
+                                                )
+                                            }
+                                        }
+                                    }));
                                 }
-                                let entry = if let Ok(Some(mut item)) =
-                                    ClipboardListItem::get(&hash, db_items())
-                                {
-                                    item.contents.copied_last = Timestamp::now();
-                                    item.contents.copy_count += 1;
-                                    let _ = item.update(db_items());
-                                    item.contents.clone()
-                                } else {
-                                    let width = image.width.try_into().unwrap();
-                                    let height = image.height.try_into().unwrap();
-                                    let path = cache.join(format!("{}.png", hash));
-                                    let thumbnail = cache.join(format!("{}.thumb.png", hash));
-                                    // Spawn a thread to generate thumbnail and saving to filesystem.
-                                    {
-                                        let path = path.clone();
-                                        let thumbnail = thumbnail.clone();
+                            } else if let Ok(image) = cp.get_image() {
+                                let mut hasher = DefaultHasher::new();
+                                image.bytes.hash(&mut hasher);
+                                let new_hash = hasher.finish();
+                                if new_hash != hash {
+                                    hash = new_hash;
+                                    if !watching(cx) {
+                                        break 'clipboard;
+                                    }
+                                    let id = hash;
+                                    let cache = cache.clone();
+                                    let find_similar = settings.merge_similar_images;
+                                    let similar_sender = similar_sender.clone();
+                                    pending.push(Box::new(move |app| {
+                                        if let Ok(Some(mut item)) =
+                                            ClipboardListItem::get(&id, db_items())
+                                        {
+                                            item.contents.copied_last = Timestamp::now();
+                                            item.contents.copy_count += 1;
+                                            let _ = item.update(db_items());
+                                            return item.contents.clone();
+                                        }
+                                        let width = image.width.try_into().unwrap();
+                                        let height = image.height.try_into().unwrap();
+                                        let path = cache.join(format!("{}.png", id));
+                                        let thumbnail = cache.join(format!("{}.thumb.png", id));
+                                        // Inserted before the thread starts, so the hash has an entry to land in
+                                        let item = ClipboardListItem::new(
+                                            id,
+                                            format!("Image ({}x{})", width, height),
+                                            ClipboardKind::Image {
+                                                width,
+                                                height,
+                                                path: path.clone(),
+                                                thumbnail: thumbnail.clone(),
+                                            },
+                                            app,
+                                        );
+                                        // Spawn a thread to generate thumbnail and saving to filesystem.
                                         thread::spawn(move || {
                                             let image = DynamicImage::ImageRgba8(
                                                 ImageBuffer::from_vec(
@@ -760,27 +1700,96 @@ impl RootCommandBuilder for ClipboardCommandBuilder {
                                             let _ = image.save(&path);
                                             let t = image.thumbnail(64, 64);
                                             let _ = t.save(&thumbnail);
+                                            match ClipboardDetail::set_phash(
+                                                id,
+                                                dhash(&t),
+                                                find_similar,
+                                            ) {
+                                                Ok(Some(original)) => {
+                                                    let _ = similar_sender.send((id, original));
+                                                }
+                                                Ok(None) => {}
+                                                Err(err) => {
+                                                    error!("Failed to hash image: {:?}", err)
+                                                }
+                                            }
                                         });
+                                        item
+                                    }));
+                                }
+                            }
+                        }
+                        #[cfg(target_os = "linux")]
+                        if settings.watch_primary {
+                            if let Ok(text) = cp.get().clipboard(LinuxClipboardKind::Primary).text()
+                            {
+                                let mut hasher = DefaultHasher::new();
+                                text.hash(&mut hasher);
+                                let new_hash = hasher.finish();
+                                // Copying usually selects the text too, which was just recorded above
+                                if new_hash != primary_hash
+                                    && new_hash != hash
+                                    && !text.trim().is_empty()
+                                {
+                                    primary_hash = new_hash;
+                                    if watching(cx) {
+                                        let id = primary_hash;
+                                        pending.push(Box::new(move |app| {
+                                            ClipboardListItem::record(id, || {
+                                                ClipboardListItem::new(
+                                                    id,
+                                                    text_title(&text),
+                                                    ClipboardKind::text(id, &text),
+                                                    app,
+                                                )
+                                            })
+                                        }));
                                     }
-                                    ClipboardListItem::new(
-                                        hash,
-                                        format!("Image ({}x{})", width, height),
-                                        ClipboardKind::Image {
-                                            width,
-                                            height,
-                                            path,
-                                            thumbnail,
-                                        },
-                                        &app,
-                                    )
-                                };
-                                let _ = cx.update_window(cx.window_handle(), |_, cx| {
+                                }
+                            }
+                        }
+                        if pending.is_empty() {
+                            return;
+                        }
+                        let settings = settings.clone();
+                        let view = view.clone();
+                        cx.spawn(|mut cx| async move {
+                            let app = cx
+                                .background_executor()
+                                .spawn(async { get_frontmost_application_data() })
+                                .await;
+                            if let Some(app) = &app {
+                                if settings.is_excluded(&app.id) {
+                                    return;
+                                }
+                            }
+                            for record in pending {
+                                let entry = record(&app);
+                                let _ = cx.update(|cx| {
                                     let _ = view.update(cx, |view: &mut AsyncListItems, cx| {
                                         let item = entry.get_item(cx);
                                         view.push(entry.kind.into(), item, cx);
                                     });
                                 });
                             }
+                        })
+                        .detach();
+                    },
+                    {
+                        let mut settings = ClipboardSettings::get();
+                        let mut settings_at = Instant::now();
+                        move |cx| {
+                            // Re-read the settings every now and then instead of on every poll
+                            if settings_at.elapsed() > Duration::from_secs(10) {
+                                settings = ClipboardSettings::get();
+                                settings_at = Instant::now();
+                            }
+                            let interval = settings.poll_interval();
+                            if Window::is_open(cx) {
+                                interval
+                            } else {
+                                interval * HIDDEN_POLL_BACKOFF
+                            }
                         }
                     },
                     cx,
@@ -9,4 +9,6 @@
  *
  */
 
+mod color;
 pub mod list;
+pub mod settings;
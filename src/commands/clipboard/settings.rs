@@ -0,0 +1,283 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::time::Duration;
+
+use arboard::Clipboard;
+use gpui::*;
+use jiff::{Span, ToSpan};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command,
+    components::form::{Form, Input, InputKind},
+    db::db,
+    state::{CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+static MIN_POLL_INTERVAL: u64 = 50;
+static MAX_POLL_INTERVAL: u64 = 2000;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub enum ClipboardRetention {
+    Day,
+    #[default]
+    Week,
+    Month,
+    Forever,
+}
+
+impl ClipboardRetention {
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::Day => Some(ToSpan::seconds(60 * 60 * 24)),
+            Self::Week => Some(ToSpan::seconds(60 * 60 * 24 * 7)),
+            Self::Month => Some(ToSpan::seconds(60 * 60 * 24 * 30)),
+            Self::Forever => None,
+        }
+    }
+    fn parse(value: &str) -> Option<Self> {
+        match value.to_lowercase().as_str() {
+            "d" | "day" => Some(Self::Day),
+            "w" | "week" => Some(Self::Week),
+            "m" | "month" => Some(Self::Month),
+            "f" | "forever" => Some(Self::Forever),
+            _ => None,
+        }
+    }
+}
+
+impl From<ClipboardRetention> for String {
+    fn from(val: ClipboardRetention) -> Self {
+        match val {
+            ClipboardRetention::Day => "day",
+            ClipboardRetention::Week => "week",
+            ClipboardRetention::Month => "month",
+            ClipboardRetention::Forever => "forever",
+        }
+        .to_string()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClipboardSettings {
+    pub retention: ClipboardRetention,
+    // Bundle ids of apps whose copies never end up in the history
+    pub excluded_apps: Vec<String>,
+    // Seconds after which pasted passwords are cleared from the clipboard
+    pub clear_sensitive_after: Option<u64>,
+    // Also record the X11/Wayland PRIMARY selection, which changes on every text selection
+    pub watch_primary: bool,
+    // Milliseconds between clipboard checks while the window is open
+    pub poll_interval: u64,
+    // Bytes of text stored in the database, longer texts are kept in a file
+    pub max_text_size: usize,
+    // Images that look the same count as copying the earlier entry again
+    pub merge_similar_images: bool,
+}
+
+impl Default for ClipboardSettings {
+    fn default() -> Self {
+        Self {
+            retention: ClipboardRetention::default(),
+            excluded_apps: vec![
+                "com.apple.systempreferences".to_string(),
+                "com.apple.keychainaccess".to_string(),
+            ],
+            clear_sensitive_after: Some(30),
+            watch_primary: false,
+            poll_interval: 250,
+            max_text_size: 100 * 1024,
+            merge_similar_images: false,
+        }
+    }
+}
+
+impl ClipboardSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("clipboard").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("clipboard", self)?;
+        Ok(())
+    }
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_millis(
+            self.poll_interval
+                .clamp(MIN_POLL_INTERVAL, MAX_POLL_INTERVAL),
+        )
+    }
+    pub fn is_excluded(&self, id: &str) -> bool {
+        self.excluded_apps.iter().any(|app| app.eq(id))
+    }
+    pub fn clear_sensitive(value: impl ToString, cx: &mut WindowContext) {
+        let Some(delay) = Self::get().clear_sensitive_after else {
+            return;
+        };
+        let value = value.to_string();
+        cx.spawn(move |cx| async move {
+            cx.background_executor()
+                .timer(Duration::from_secs(delay))
+                .await;
+            let Ok(mut cp) = Clipboard::new() else {
+                return;
+            };
+            // Leave the clipboard alone if something else was copied in the meantime
+            if cp.get_text().is_ok_and(|text| text.eq(&value)) {
+                let _ = cp.clear();
+            }
+        })
+        .detach();
+    }
+}
+
+#[derive(Clone)]
+pub struct ClipboardSettingsBuilder;
+command!(ClipboardSettingsBuilder);
+impl StateViewBuilder for ClipboardSettingsBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let settings = ClipboardSettings::get();
+        Form::new(
+            vec![
+                Input::new(
+                    "retention",
+                    "Keep History For",
+                    InputKind::TextField {
+                        placeholder: "(d)ay|(w)eek|(m)onth|(f)orever".to_string(),
+                        value: settings.retention.into(),
+                        validate: Some(|v| {
+                            ClipboardRetention::parse(v)
+                                .is_none()
+                                .then_some("Invalid response")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "excluded_apps",
+                    "Excluded Apps",
+                    InputKind::TextField {
+                        placeholder: "Comma separated bundle ids...".to_string(),
+                        value: settings.excluded_apps.join(", "),
+                        validate: None,
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "clear_sensitive_after",
+                    "Clear Passwords After",
+                    InputKind::TextField {
+                        placeholder: "Seconds, leave empty to never clear".to_string(),
+                        value: settings
+                            .clear_sensitive_after
+                            .map(|delay| delay.to_string())
+                            .unwrap_or_default(),
+                        validate: Some(|v| {
+                            (!v.is_empty() && v.parse::<u64>().is_err())
+                                .then_some("Invalid number of seconds")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "poll_interval",
+                    "Poll Interval",
+                    InputKind::TextField {
+                        placeholder: "Milliseconds between clipboard checks".to_string(),
+                        value: settings.poll_interval.to_string(),
+                        validate: Some(|v| {
+                            v.parse::<u64>()
+                                .map_or(true, |ms| {
+                                    !(MIN_POLL_INTERVAL..=MAX_POLL_INTERVAL).contains(&ms)
+                                })
+                                .then_some("Expected 50 to 2000 milliseconds")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "max_text_size",
+                    "Max Text Size",
+                    InputKind::TextField {
+                        placeholder: "Kilobytes stored per text entry".to_string(),
+                        value: (settings.max_text_size / 1024).to_string(),
+                        validate: Some(|v| {
+                            v.parse::<usize>()
+                                .map_or(true, |kb| kb == 0)
+                                .then_some("Invalid number of kilobytes")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "merge_similar_images",
+                    "Merge Similar Images",
+                    InputKind::Toggle {
+                        value: settings.merge_similar_images,
+                    },
+                    cx,
+                ),
+                #[cfg(target_os = "linux")]
+                Input::new(
+                    "watch_primary",
+                    "Watch Primary Selection",
+                    InputKind::Toggle {
+                        value: settings.watch_primary,
+                    },
+                    cx,
+                ),
+            ],
+            |values, actions, cx| {
+                let mut settings = ClipboardSettings::get();
+                settings.retention =
+                    ClipboardRetention::parse(&values["retention"].value::<String>())
+                        .unwrap_or_default();
+                settings.excluded_apps = values["excluded_apps"]
+                    .value::<String>()
+                    .split(',')
+                    .map(|id| id.trim().to_string())
+                    .filter(|id| !id.is_empty())
+                    .collect();
+                settings.clear_sensitive_after = values["clear_sensitive_after"]
+                    .value::<String>()
+                    .parse()
+                    .ok();
+                settings.poll_interval = values["poll_interval"]
+                    .value::<String>()
+                    .parse()
+                    .unwrap_or(settings.poll_interval);
+                if let Ok(kb) = values["max_text_size"].value::<String>().parse::<usize>() {
+                    settings.max_text_size = kb * 1024;
+                }
+                settings.merge_similar_images = values["merge_similar_images"].value::<bool>();
+                #[cfg(target_os = "linux")]
+                {
+                    settings.watch_primary = values["watch_primary"].value::<bool>();
+                }
+                if settings.set().is_err() {
+                    actions.toast.error("Failed to save clipboard settings", cx);
+                    return;
+                }
+                actions.toast.success("Saved clipboard settings", cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
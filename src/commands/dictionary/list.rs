@@ -0,0 +1,248 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use gpui::*;
+use log::debug;
+use reqwest::StatusCode;
+use serde::Deserialize;
+use url::Url;
+
+#[cfg(target_os = "macos")]
+use crate::platform::define_word;
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    query::TextEvent,
+    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+const API_URL: &str = "https://api.dictionaryapi.dev/api/v2/entries/en";
+const LOOKUP_DEBOUNCE: Duration = Duration::from_millis(500);
+
+#[derive(Deserialize)]
+struct Entry {
+    meanings: Vec<Meaning>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Meaning {
+    part_of_speech: String,
+    definitions: Vec<Definition>,
+}
+
+#[derive(Deserialize)]
+struct Definition {
+    definition: String,
+    example: Option<String>,
+}
+
+struct Sense {
+    part_of_speech: String,
+    definition: String,
+    example: Option<String>,
+}
+
+impl Sense {
+    fn get_item(self, index: usize) -> Item {
+        let definition = self.definition.clone();
+        ItemBuilder::new(
+            index,
+            ListItem::new(
+                Some(Img::default().icon(Icon::BookOpen)),
+                self.definition,
+                self.example,
+                vec![],
+            ),
+        )
+        .section(Some(self.part_of_speech))
+        .actions(vec![Action::new(
+            Img::default().icon(Icon::Copy),
+            "Copy Definition",
+            None,
+            move |this, cx| {
+                cx.write_to_clipboard(ClipboardItem::new_string(definition.clone()));
+                this.toast
+                    .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+            },
+            false,
+        )])
+        .build()
+    }
+}
+
+async fn lookup_online(word: &str) -> anyhow::Result<Vec<Sense>> {
+    let mut url = Url::parse(API_URL)?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Invalid dictionary url"))?
+        .push(word);
+    let response = reqwest::get(url).await?;
+    // Words the dictionary doesn't know are a 404
+    if response.status() == StatusCode::NOT_FOUND {
+        return Ok(vec![]);
+    }
+    let entries: Vec<Entry> = serde_json::from_str(&response.error_for_status()?.text().await?)?;
+    Ok(entries
+        .into_iter()
+        .flat_map(|entry| entry.meanings)
+        .flat_map(|meaning| {
+            let part_of_speech = meaning.part_of_speech;
+            meaning.definitions.into_iter().map(move |d| Sense {
+                part_of_speech: part_of_speech.clone(),
+                definition: d.definition,
+                example: d.example,
+            })
+        })
+        .collect())
+}
+
+// The system dictionary works offline, but only hands out the entry as one block of text
+async fn lookup(word: String) -> anyhow::Result<Vec<Sense>> {
+    let online = async_std::task::spawn({
+        let word = word.clone();
+        async move { lookup_online(&word).await }
+    })
+    .await;
+    #[cfg(target_os = "macos")]
+    if !matches!(&online, Ok(senses) if !senses.is_empty()) {
+        if let Some(definition) = define_word(&word) {
+            return Ok(vec![Sense {
+                part_of_speech: "Dictionary".to_string(),
+                definition,
+                example: None,
+            }]);
+        }
+    }
+    online
+}
+
+#[derive(Clone)]
+struct DictionaryListBuilder;
+command!(DictionaryListBuilder);
+impl StateViewBuilder for DictionaryListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Type a word to define...", cx);
+
+        let view = cx.new_view(|_| AsyncListItems::new());
+        AsyncListItems::loader(&view, &context.actions, cx);
+        view.update(cx, |view, cx| {
+            view.update("definitions".to_string(), vec![], cx)
+        });
+
+        if let Some(query) = context.query.view.upgrade() {
+            let view = view.downgrade();
+            let toast = context
+                .actions
+                .inner
+                .upgrade()
+                .map(|actions| actions.read(cx).toast.clone());
+            // Only the most recent input gets to look up its word
+            let pending = Rc::new(Cell::new(0u64));
+            cx.subscribe(&query, move |_, event: &TextEvent, cx| {
+                let TextEvent::Input { text } = event else {
+                    return;
+                };
+                let word = text.trim().to_lowercase();
+                pending.set(pending.get() + 1);
+                let current = pending.get();
+                let pending = pending.clone();
+                let view = view.clone();
+                let mut toast = toast.clone();
+                cx.spawn(|mut cx| async move {
+                    cx.background_executor().timer(LOOKUP_DEBOUNCE).await;
+                    if pending.get() != current {
+                        return;
+                    }
+                    if word.is_empty() {
+                        let _ = view.update(&mut cx, |view, cx| {
+                            view.update("definitions".to_string(), vec![], cx)
+                        });
+                        return;
+                    }
+                    if let Some(toast) = toast.as_mut() {
+                        toast.loading(format!("Looking up \"{}\"...", word), &mut cx);
+                    }
+                    let result = lookup(word.clone()).await;
+                    if pending.get() != current {
+                        return;
+                    }
+                    let senses = match result {
+                        Ok(senses) => senses,
+                        Err(err) => {
+                            debug!("Failed to look up {}: {:?}", word, err);
+                            if let Some(toast) = toast.as_mut() {
+                                toast.error("Failed to reach the dictionary", &mut cx);
+                            }
+                            return;
+                        }
+                    };
+                    if let Some(toast) = toast.as_mut() {
+                        if senses.is_empty() {
+                            toast.error(format!("No definitions found for \"{}\"", word), &mut cx);
+                        } else {
+                            toast.success(format!("Found {} definitions", senses.len()), &mut cx);
+                        }
+                    }
+                    let _ = view.update(&mut cx, |view, cx| {
+                        let items = senses
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, sense)| sense.get_item(i))
+                            .collect();
+                        view.update("definitions".to_string(), items, cx);
+                    });
+                })
+                .detach();
+            })
+            .detach();
+        }
+
+        ListBuilder::new()
+            // The definitions belong to the typed word, so there is nothing left to match
+            .filter(|this, _| this.items_all.clone())
+            .build(
+                move |_, _, cx| {
+                    Ok(Some(
+                        view.read(cx).items.values().flatten().cloned().collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct DictionaryCommandBuilder;
+command!(DictionaryCommandBuilder);
+impl RootCommandBuilder for DictionaryCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "dictionary",
+            "Define Word",
+            "Dictionary",
+            Icon::BookA,
+            vec!["Dictionary", "Definition", "Meaning"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(DictionaryListBuilder, cx), cx);
+            },
+        )
+    }
+}
@@ -0,0 +1,111 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::*;
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{Accessory, Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+    window::WindowSettings,
+};
+
+fn item(id: Option<u32>, title: impl ToString, subtitle: Option<String>, icon: Icon) -> Item {
+    let selected = WindowSettings::get().display.eq(&id);
+    ItemBuilder::new(
+        id,
+        ListItem::new(
+            Some(Img::default().icon(icon.clone())),
+            title.to_string(),
+            subtitle,
+            if selected {
+                vec![Accessory::new(
+                    "Active",
+                    Some(Img::default().icon(Icon::Check)),
+                )]
+            } else {
+                vec![]
+            },
+        ),
+    )
+    .actions(vec![Action::new(
+        Img::default().icon(icon),
+        "Open Loungy Here",
+        None,
+        move |this, cx| {
+            let settings = WindowSettings { display: id };
+            if settings.set().is_err() {
+                this.toast.error("Failed to change display", cx);
+            } else {
+                this.toast.success("Changed display", cx);
+            }
+        },
+        false,
+    )])
+    .build()
+}
+
+#[derive(Clone)]
+pub struct DisplayListBuilder;
+command!(DisplayListBuilder);
+
+impl StateViewBuilder for DisplayListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder("Search displays...", cx);
+        ListBuilder::new()
+            .build(
+                |_, _, cx| {
+                    let mut items =
+                        vec![item(None, "Display with Cursor", None, Icon::MousePointer)];
+                    items.extend(cx.displays().into_iter().enumerate().map(|(i, display)| {
+                        let size = display.bounds().size;
+                        item(
+                            Some(display.id().into()),
+                            format!("Display {}", i + 1),
+                            Some(format!(
+                                "{}x{}",
+                                f32::from(size.width),
+                                f32::from(size.height)
+                            )),
+                            Icon::Monitor,
+                        )
+                    }));
+                    Ok(Some(items))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct DisplayCommandBuilder;
+command!(DisplayCommandBuilder);
+
+impl RootCommandBuilder for DisplayCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "displays",
+            "Choose Display",
+            "Customization",
+            Icon::Monitor,
+            vec!["Monitor", "Screen"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(DisplayListBuilder, cx), cx);
+            },
+        )
+    }
+}
@@ -0,0 +1,189 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use emojis::{Emoji, SkinTone};
+use gpui::*;
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    db::db,
+    platform::{close_and_paste, copy_text, require_accessibility},
+    state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
+};
+
+static RECENT_KEY: &str = "emoji.recent";
+static RECENT_LIMIT: usize = 16;
+// Fuzzy score multiplier for recently used emoji
+static RECENT_WEIGHT: u16 = 2;
+
+pub fn recent() -> Vec<String> {
+    db().get::<Vec<String>>(RECENT_KEY).unwrap_or_default()
+}
+
+pub fn remember(emoji: &str) {
+    let mut recent = recent();
+    recent.retain(|e| e.ne(emoji));
+    recent.insert(0, emoji.to_string());
+    recent.truncate(RECENT_LIMIT);
+    if let Err(err) = db().set::<Vec<String>>(RECENT_KEY, &recent) {
+        log::error!("Failed to save recent emoji: {}", err);
+    }
+}
+
+pub fn skin_tone(value: &str) -> Option<SkinTone> {
+    match value {
+        "light" => Some(SkinTone::Light),
+        "medium-light" => Some(SkinTone::MediumLight),
+        "medium" => Some(SkinTone::Medium),
+        "medium-dark" => Some(SkinTone::MediumDark),
+        "dark" => Some(SkinTone::Dark),
+        _ => None,
+    }
+}
+
+fn emoji_item(emoji: &'static Emoji, recent: bool) -> Item {
+    let value = emoji.as_str();
+    let mut keywords = vec![emoji.name().to_string()];
+    keywords.extend(emoji.shortcodes().map(|s| s.to_string()));
+    let mut actions = vec![
+        Action::new(
+            Img::default().icon(Icon::ClipboardPaste),
+            "Paste Emoji",
+            None,
+            move |this, cx| {
+                if !require_accessibility("paste", &mut this.toast, cx) {
+                    return;
+                }
+                remember(value);
+                close_and_paste(value, false, cx);
+            },
+            false,
+        ),
+        Action::new(
+            Img::default().icon(Icon::ClipboardCopy),
+            "Copy Emoji",
+            Some(Shortcut::new("c").cmd().shift()),
+            move |this, cx| {
+                if copy_text(value, cx).is_err() {
+                    this.toast.error("Failed to copy to clipboard", cx);
+                    return;
+                }
+                remember(value);
+                this.toast
+                    .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+            },
+            false,
+        ),
+    ];
+    if emoji.skin_tones().is_some() {
+        actions.push(Action::new(
+            Img::default().icon(Icon::Palette),
+            "Choose Skin Tone",
+            Some(Shortcut::new("t").cmd()),
+            move |_, cx| {
+                StateModel::update(|this, cx| this.push(SkinToneListBuilder { emoji }, cx), cx);
+            },
+            false,
+        ));
+    }
+    let item = ItemBuilder::new(
+        value,
+        ListItem::new(None, format!("{}  {}", value, emoji.name()), None, vec![]),
+    )
+    .keywords(keywords)
+    .actions(actions);
+    if recent {
+        item.weight(RECENT_WEIGHT).build()
+    } else {
+        item.build()
+    }
+}
+
+#[derive(Clone)]
+struct SkinToneListBuilder {
+    emoji: &'static Emoji,
+}
+command!(SkinToneListBuilder);
+impl StateViewBuilder for SkinToneListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder("Search skin tones...", cx);
+        let emoji = self.emoji;
+        ListBuilder::new()
+            .build(
+                move |_, _, _| {
+                    Ok(Some(
+                        emoji
+                            .skin_tones()
+                            .into_iter()
+                            .flatten()
+                            .map(|emoji| emoji_item(emoji, false))
+                            .collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+#[derive(Clone)]
+struct EmojiListBuilder;
+command!(EmojiListBuilder);
+impl StateViewBuilder for EmojiListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder("Search emoji...", cx);
+        ListBuilder::new()
+            .build(
+                |_, _, _| {
+                    let recent: Vec<&'static Emoji> =
+                        recent().iter().filter_map(|e| emojis::get(e)).collect();
+                    // Recent emoji come first so they also lead the list while the query is empty
+                    let mut items: Vec<Item> = recent
+                        .iter()
+                        .map(|&emoji| emoji_item(emoji, true))
+                        .collect();
+                    items.extend(
+                        emojis::iter()
+                            .filter(|emoji| !recent.contains(emoji))
+                            .map(|emoji| emoji_item(emoji, false)),
+                    );
+                    Ok(Some(items))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct EmojiCommandBuilder;
+command!(EmojiCommandBuilder);
+impl RootCommandBuilder for EmojiCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "emoji",
+            "Search Emoji",
+            "Emoji",
+            Icon::Smile,
+            vec!["Emoticons", "Symbols"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(EmojiListBuilder, cx), cx);
+            },
+        )
+    }
+}
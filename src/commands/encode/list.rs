@@ -0,0 +1,159 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use base64::{
+    engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD},
+    Engine,
+};
+use gpui::*;
+use md5::Md5;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+// Unreserved characters as per RFC 3986 stay as they are
+const COMPONENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+fn digest<D: Digest>(text: &str) -> String {
+    D::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// Only offers decoding when the input is valid base64 of printable text
+fn base64_decode(text: &str) -> Option<String> {
+    let text = text.trim();
+    if text.len() < 4
+        || !text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "+/-_=".contains(c))
+    {
+        return None;
+    }
+    let bytes = [STANDARD, URL_SAFE, STANDARD_NO_PAD, URL_SAFE_NO_PAD]
+        .iter()
+        .find_map(|engine| engine.decode(text).ok())?;
+    let decoded = String::from_utf8(bytes).ok()?;
+    (!decoded.is_empty()
+        && !decoded
+            .chars()
+            .any(|c| c.is_control() && !c.is_whitespace()))
+    .then_some(decoded)
+}
+
+fn url_decode(text: &str) -> Option<String> {
+    if !text.contains('%') && !text.contains('+') {
+        return None;
+    }
+    let decoded = percent_decode_str(&text.replace('+', " "))
+        .decode_utf8()
+        .ok()?
+        .to_string();
+    (decoded != text).then_some(decoded)
+}
+
+fn transform_item(label: &str, icon: Icon, value: String) -> Item {
+    ItemBuilder::new(
+        label,
+        ListItem::new(
+            Some(Img::default().icon(icon)),
+            value.clone(),
+            Some(label.to_string()),
+            vec![],
+        ),
+    )
+    .actions(vec![Action::new(
+        Img::default().icon(Icon::Copy),
+        format!("Copy {}", label),
+        None,
+        move |this, cx| {
+            cx.write_to_clipboard(ClipboardItem::new_string(value.clone()));
+            this.toast
+                .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+        },
+        false,
+    )])
+    .build()
+}
+
+// One item per transform of the query, decoders first since they only show up when they apply
+fn transform_items(text: &str) -> Vec<Item> {
+    if text.is_empty() {
+        return vec![];
+    }
+    let mut items = vec![];
+    if let Some(decoded) = base64_decode(text) {
+        items.push(transform_item("Base64 Decode", Icon::Binary, decoded));
+    }
+    if let Some(decoded) = url_decode(text) {
+        items.push(transform_item("URL Decode", Icon::Link, decoded));
+    }
+    items.extend([
+        transform_item("Base64 Encode", Icon::Binary, STANDARD.encode(text)),
+        transform_item(
+            "URL Encode",
+            Icon::Link,
+            utf8_percent_encode(text, COMPONENT).to_string(),
+        ),
+        transform_item("SHA-256", Icon::Hash, digest::<Sha256>(text)),
+        transform_item("SHA-1", Icon::Hash, digest::<Sha1>(text)),
+        transform_item("MD5", Icon::Hash, digest::<Md5>(text)),
+    ]);
+    items
+}
+
+#[derive(Clone)]
+struct EncodeListBuilder;
+command!(EncodeListBuilder);
+impl StateViewBuilder for EncodeListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Type or paste text to encode or hash...", cx);
+        ListBuilder::new()
+            .filter(|this, cx| transform_items(&this.query.get_text(cx)))
+            .build(|_, _, _| Ok(Some(vec![])), context, cx)
+            .into()
+    }
+}
+
+pub struct EncodeCommandBuilder;
+command!(EncodeCommandBuilder);
+impl RootCommandBuilder for EncodeCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "encode",
+            "Encode and Hash",
+            "Developer",
+            Icon::Binary,
+            vec!["Base64", "URL", "MD5", "SHA", "Hash", "Decode"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(EncodeListBuilder, cx), cx);
+            },
+        )
+    }
+}
@@ -0,0 +1,298 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use gpui::*;
+use nucleo::{pattern::Atom, Matcher, Utf32Str};
+use walkdir::WalkDir;
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{
+            nucleo::{fuzzy_match, normalize, Score},
+            AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem,
+        },
+        shared::{Icon, Img},
+    },
+    paths::paths,
+    platform::{open_path, reveal_path, REVEAL_LABEL},
+    state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
+    window::Window,
+};
+
+// Directories that are never worth descending into, hidden ones are skipped as well
+static IGNORED: [&str; 5] = ["node_modules", "target", "Library", "__pycache__", "venv"];
+static IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "gif", "webp"];
+static RESULT_LIMIT: usize = 50;
+// Keeps memory and matching time bounded for very large home folders
+static MAX_ENTRIES: usize = 200_000;
+static REINDEX_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Clone)]
+struct FileEntry {
+    name: String,
+    // Normalized once while indexing, rather than on every keystroke
+    key: String,
+    path: PathBuf,
+    dir: bool,
+}
+
+impl Score for &FileEntry {
+    fn score(
+        &self,
+        pattern: &Atom,
+        buf: &mut Vec<char>,
+        matcher: &mut Matcher,
+    ) -> Option<(Self, u16)> {
+        let score = pattern.score(Utf32Str::new(&self.key, buf), matcher)?;
+        Some((*self, score))
+    }
+}
+
+impl FileEntry {
+    fn get_item(&self) -> Item {
+        let img = if self.dir {
+            Img::default().icon(Icon::Folder)
+        } else if self
+            .path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+            .unwrap_or(false)
+        {
+            Img::default().file(self.path.clone())
+        } else {
+            Img::default().icon(Icon::File)
+        };
+        let parent = self
+            .path
+            .parent()
+            .map(|parent| match parent.strip_prefix(&paths().home) {
+                Ok(relative) => Path::new("~").join(relative),
+                Err(_) => parent.to_path_buf(),
+            })
+            .map(|parent| parent.to_string_lossy().to_string());
+        ItemBuilder::new(
+            self.path.clone(),
+            ListItem::new(Some(img), self.name.clone(), parent, vec![]),
+        )
+        .keywords(vec![self.name.clone()])
+        .actions(vec![
+            Action::new(
+                Img::default().icon(Icon::ArrowUpRightFromSquare),
+                "Open",
+                None,
+                {
+                    let path = self.path.clone();
+                    move |_, cx| {
+                        Window::close(cx);
+                        open_path(&path);
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::FolderOpen),
+                REVEAL_LABEL,
+                Some(Shortcut::new("enter").shift()),
+                {
+                    let path = self.path.clone();
+                    move |_, cx| {
+                        Window::close(cx);
+                        reveal_path(&path);
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::Copy),
+                "Copy Path",
+                Some(Shortcut::new("c").cmd().shift()),
+                {
+                    let path = self.path.to_string_lossy().to_string();
+                    move |actions, cx| {
+                        cx.write_to_clipboard(ClipboardItem::new_string(path.clone()));
+                        actions
+                            .toast
+                            .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+                    }
+                },
+                false,
+            ),
+        ])
+        .build()
+    }
+}
+
+struct FileIndex {
+    entries: Arc<Vec<FileEntry>>,
+    started: bool,
+    indexed: bool,
+}
+
+impl FileIndex {
+    // Indexing only starts once the command is first opened, then refreshes periodically
+    fn start(index: &Model<Self>, cx: &mut WindowContext) {
+        if index.read(cx).started {
+            return;
+        }
+        index.update(cx, |this, cx| {
+            this.started = true;
+            cx.spawn(|this, mut cx| async move {
+                loop {
+                    let entries = cx
+                        .background_executor()
+                        .spawn(async { FileIndex::walk(&paths().home) })
+                        .await;
+                    if this
+                        .update(&mut cx, |this: &mut FileIndex, cx| {
+                            this.entries = Arc::new(entries);
+                            this.indexed = true;
+                            cx.notify();
+                        })
+                        .is_err()
+                    {
+                        break;
+                    }
+                    cx.background_executor().timer(REINDEX_INTERVAL).await;
+                }
+            })
+            .detach();
+        });
+    }
+    fn walk(root: &Path) -> Vec<FileEntry> {
+        WalkDir::new(root)
+            .into_iter()
+            .filter_entry(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                entry.depth() == 0 || !(name.starts_with('.') || IGNORED.contains(&name.as_ref()))
+            })
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.depth() > 0)
+            .take(MAX_ENTRIES)
+            .map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                FileEntry {
+                    key: normalize(&name).0,
+                    name,
+                    dir: entry.file_type().is_dir(),
+                    path: entry.into_path(),
+                }
+            })
+            .collect()
+    }
+}
+
+// Drops stale matches, so the next filter pass matches against the fresh index
+fn clear_matches(matches: &WeakView<AsyncListItems>, cx: &mut WindowContext) {
+    let _ = matches.update(cx, |this, cx| {
+        this.items.clear();
+        this.update(String::new(), vec![], cx);
+    });
+}
+
+#[derive(Clone)]
+struct FileListBuilder {
+    index: Model<FileIndex>,
+}
+command!(FileListBuilder);
+impl StateViewBuilder for FileListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder("Search your files...", cx);
+        let index = self.index.clone();
+        FileIndex::start(&index, cx);
+
+        // Matches for the latest query, keyed by that query
+        let matches = cx.new_view(|_| AsyncListItems::new());
+        AsyncListItems::loader(&matches, &context.actions, cx);
+        if index.read(cx).indexed {
+            clear_matches(&matches.downgrade(), cx);
+        }
+        cx.observe(&index, {
+            let matches = matches.downgrade();
+            move |_, cx| clear_matches(&matches, cx)
+        })
+        .detach();
+
+        ListBuilder::new()
+            .filter_debounce(Duration::from_millis(100))
+            .filter(move |this, cx| {
+                let query = this.query.get_text(cx);
+                if query.trim().is_empty() || !index.read(cx).indexed {
+                    return vec![];
+                }
+                let cached = matches.read(cx).items.clone();
+                if let Some(items) = cached.get(&query) {
+                    return items.clone();
+                }
+                let entries = index.read(cx).entries.clone();
+                let matches = matches.downgrade();
+                cx.spawn(|_, mut cx| async move {
+                    let found = cx
+                        .background_executor()
+                        .spawn({
+                            let query = query.clone();
+                            async move {
+                                fuzzy_match(&query, entries.iter().collect(), false)
+                                    .into_iter()
+                                    .take(RESULT_LIMIT)
+                                    .cloned()
+                                    .collect::<Vec<FileEntry>>()
+                            }
+                        })
+                        .await;
+                    // Only the best matches get turned into list items
+                    let _ = matches.update(&mut cx, |this, cx| {
+                        let items = found.iter().map(|entry| entry.get_item()).collect();
+                        this.items.clear();
+                        this.update(query, items, cx);
+                    });
+                })
+                .detach();
+                // Keep showing the previous matches until the new ones are in
+                cached.into_values().next().unwrap_or_default()
+            })
+            .build(|_, _, _| Ok(Some(vec![])), context, cx)
+            .into()
+    }
+}
+
+pub struct FilesCommandBuilder;
+command!(FilesCommandBuilder);
+impl RootCommandBuilder for FilesCommandBuilder {
+    fn build(&self, cx: &mut WindowContext) -> RootCommand {
+        let index = cx.new_model(|_| FileIndex {
+            entries: Arc::new(vec![]),
+            started: false,
+            indexed: false,
+        });
+
+        RootCommand::new(
+            "files",
+            "Search Files",
+            "Files",
+            Icon::FileSearch,
+            vec!["Finder", "Documents"],
+            None,
+            move |_, cx| {
+                let index = index.clone();
+                StateModel::update(|this, cx| this.push(FileListBuilder { index }, cx), cx);
+            },
+        )
+    }
+}
@@ -0,0 +1,485 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use gpui::*;
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "clipboard")]
+use crate::commands::clipboard::settings::ClipboardSettings;
+#[cfg(target_os = "macos")]
+use crate::platform::{close_and_paste, require_accessibility};
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        form::{Form, Input, InputKind},
+        list::{Accessory, Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    db::db,
+    platform::copy_text,
+    state::{
+        Action, Actions, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext,
+    },
+};
+
+const WORDS: &str = include_str!("words.txt");
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "!@#$%^&*()-_=+[]{};:,.<>?/~";
+// Characters that are easily confused with each other in most fonts
+const AMBIGUOUS: &str = "Il1O0o|`'\"";
+const LENGTHS: [usize; 7] = [12, 16, 20, 24, 32, 48, 64];
+// Six words from the 7776 word diceware list, which is what people expect from a passphrase
+const DICEWARE_BITS: f64 = 6.0 * 12.925;
+
+// Our word list is shorter than the diceware one, so it takes more words to be as strong
+fn default_words() -> usize {
+    (DICEWARE_BITS / (WORDS.lines().count() as f64).log2()).ceil() as usize
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerateSettings {
+    length: usize,
+    digits: bool,
+    symbols: bool,
+    exclude_ambiguous: bool,
+    words: usize,
+    separator: String,
+    token_bytes: usize,
+}
+
+impl Default for GenerateSettings {
+    fn default() -> Self {
+        Self {
+            length: 20,
+            digits: true,
+            symbols: true,
+            exclude_ambiguous: false,
+            words: default_words(),
+            separator: "-".to_string(),
+            token_bytes: 32,
+        }
+    }
+}
+
+impl GenerateSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("generate").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("generate", self)?;
+        Ok(())
+    }
+    fn charsets(&self) -> Vec<Vec<char>> {
+        let mut sets = vec![LOWERCASE, UPPERCASE];
+        if self.digits {
+            sets.push(DIGITS);
+        }
+        if self.symbols {
+            sets.push(SYMBOLS);
+        }
+        sets.into_iter()
+            .map(|set| {
+                set.chars()
+                    .filter(|c| !self.exclude_ambiguous || !AMBIGUOUS.contains(*c))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Password,
+    Passphrase,
+    Uuid,
+    Hex,
+    Base64,
+}
+
+impl Kind {
+    const ALL: [Kind; 5] = [
+        Kind::Password,
+        Kind::Passphrase,
+        Kind::Uuid,
+        Kind::Hex,
+        Kind::Base64,
+    ];
+    fn title(&self) -> &'static str {
+        match self {
+            Kind::Password => "Password",
+            Kind::Passphrase => "Passphrase",
+            Kind::Uuid => "UUID",
+            Kind::Hex => "Hex Token",
+            Kind::Base64 => "Base64 Token",
+        }
+    }
+    fn icon(&self) -> Icon {
+        match self {
+            Kind::Password => Icon::KeyRound,
+            Kind::Passphrase => Icon::Dices,
+            Kind::Uuid => Icon::Fingerprint,
+            Kind::Hex => Icon::Hash,
+            Kind::Base64 => Icon::Binary,
+        }
+    }
+    fn generate(&self, settings: &GenerateSettings) -> (String, usize) {
+        let mut rng = rand::thread_rng();
+        match self {
+            Kind::Password => {
+                let sets = settings.charsets();
+                let all: Vec<char> = sets.iter().flatten().copied().collect();
+                // Every enabled character class shows up at least once
+                let mut password: Vec<char> = sets
+                    .iter()
+                    .filter_map(|set| set.choose(&mut rng))
+                    .copied()
+                    .collect();
+                while password.len() < settings.length {
+                    password.push(*all.choose(&mut rng).unwrap());
+                }
+                password.truncate(settings.length);
+                password.shuffle(&mut rng);
+                let bits = settings.length as f64 * (all.len() as f64).log2();
+                (password.into_iter().collect(), bits as usize)
+            }
+            Kind::Passphrase => {
+                let words: Vec<&str> = WORDS.lines().collect();
+                let phrase: Vec<&str> = (0..settings.words)
+                    .map(|_| *words.choose(&mut rng).unwrap())
+                    .collect();
+                let bits = settings.words as f64 * (words.len() as f64).log2();
+                (phrase.join(&settings.separator), bits as usize)
+            }
+            Kind::Uuid => {
+                let mut bytes: [u8; 16] = rng.gen();
+                // Version 4, variant 1
+                bytes[6] = (bytes[6] & 0x0f) | 0x40;
+                bytes[8] = (bytes[8] & 0x3f) | 0x80;
+                let hex = hex(&bytes);
+                (
+                    format!(
+                        "{}-{}-{}-{}-{}",
+                        &hex[0..8],
+                        &hex[8..12],
+                        &hex[12..16],
+                        &hex[16..20],
+                        &hex[20..32]
+                    ),
+                    122,
+                )
+            }
+            Kind::Hex => {
+                let bytes = random_bytes(settings.token_bytes);
+                (hex(&bytes), settings.token_bytes * 8)
+            }
+            Kind::Base64 => {
+                let bytes = random_bytes(settings.token_bytes);
+                (URL_SAFE_NO_PAD.encode(bytes), settings.token_bytes * 8)
+            }
+        }
+    }
+    fn get_item(self, value: String, bits: usize) -> Item {
+        // UUIDs are identifiers rather than secrets
+        #[cfg(feature = "clipboard")]
+        let sensitive = !matches!(self, Kind::Uuid);
+        #[cfg(target_os = "macos")]
+        let paste = value.clone();
+        let copy = value.clone();
+        ItemBuilder::new(
+            self.title(),
+            ListItem::new(
+                Some(Img::default().icon(self.icon())),
+                value,
+                Some(self.title().to_string()),
+                vec![Accessory::new(format!("{} bits", bits), None)],
+            ),
+        )
+        .keywords(vec![self.title()])
+        .actions(vec![
+            #[cfg(target_os = "macos")]
+            Action::new(
+                Img::default().icon(Icon::ClipboardPaste),
+                format!("Paste {}", self.title()),
+                None,
+                move |actions, cx| {
+                    if !require_accessibility("paste", &mut actions.toast, cx) {
+                        return;
+                    }
+                    close_and_paste(&paste, false, cx);
+                    #[cfg(feature = "clipboard")]
+                    if sensitive {
+                        ClipboardSettings::clear_sensitive(&paste, cx);
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::ClipboardCopy),
+                "Copy to Clipboard",
+                Some(Shortcut::new("c").cmd().shift()),
+                move |actions, cx| {
+                    if copy_text(&copy, cx).is_err() {
+                        actions.toast.error("Failed to copy to clipboard", cx);
+                        return;
+                    }
+                    #[cfg(feature = "clipboard")]
+                    if sensitive {
+                        ClipboardSettings::clear_sensitive(&copy, cx);
+                    }
+                    actions
+                        .toast
+                        .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+                },
+                false,
+            ),
+        ])
+        .build()
+    }
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut rng = rand::thread_rng();
+    (0..len).map(|_| rng.gen()).collect()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+// Values are only regenerated when asked to or when the settings change, so
+// refreshing the list (e.g. when opening the actions) keeps them stable
+#[derive(Default)]
+struct Generated {
+    settings: Option<GenerateSettings>,
+    values: Vec<(Kind, String, usize)>,
+}
+
+impl Generated {
+    fn refresh(&mut self, settings: GenerateSettings) {
+        if self.settings.as_ref() == Some(&settings) {
+            return;
+        }
+        self.values = Kind::ALL
+            .iter()
+            .map(|kind| {
+                let (value, bits) = kind.generate(&settings);
+                (*kind, value, bits)
+            })
+            .collect();
+        self.settings = Some(settings);
+    }
+}
+
+#[derive(Clone)]
+struct GenerateOptionsBuilder {
+    list: Actions,
+}
+command!(GenerateOptionsBuilder);
+impl StateViewBuilder for GenerateOptionsBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let settings = GenerateSettings::get();
+        let list = self.list.clone();
+        Form::new(
+            vec![
+                Input::new(
+                    "digits",
+                    "Include Digits",
+                    InputKind::Toggle {
+                        value: settings.digits,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "symbols",
+                    "Include Symbols",
+                    InputKind::Toggle {
+                        value: settings.symbols,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "exclude_ambiguous",
+                    "Exclude Ambiguous",
+                    InputKind::Toggle {
+                        value: settings.exclude_ambiguous,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "words",
+                    "Passphrase Words",
+                    InputKind::TextField {
+                        placeholder: "Number of words".to_string(),
+                        value: settings.words.to_string(),
+                        validate: Some(|v| {
+                            v.parse::<usize>()
+                                .map_or(true, |words| !(3..=20).contains(&words))
+                                .then_some("Expected 3 to 20 words")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "separator",
+                    "Word Separator",
+                    InputKind::Select {
+                        options: vec![
+                            ("-".to_string(), "Hyphen".to_string()),
+                            (" ".to_string(), "Space".to_string()),
+                            (".".to_string(), "Period".to_string()),
+                            ("_".to_string(), "Underscore".to_string()),
+                        ],
+                        value: settings.separator.clone(),
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "token_bytes",
+                    "Token Size",
+                    InputKind::Select {
+                        options: [16, 32, 64]
+                            .iter()
+                            .map(|bytes| (bytes.to_string(), format!("{} Bytes", bytes)))
+                            .collect(),
+                        value: settings.token_bytes.to_string(),
+                    },
+                    cx,
+                ),
+            ],
+            move |values, actions, cx| {
+                let mut settings = GenerateSettings::get();
+                settings.digits = values["digits"].value::<bool>();
+                settings.symbols = values["symbols"].value::<bool>();
+                settings.exclude_ambiguous = values["exclude_ambiguous"].value::<bool>();
+                settings.words = values["words"]
+                    .value::<String>()
+                    .parse()
+                    .unwrap_or(settings.words);
+                settings.separator = values["separator"].value::<String>();
+                settings.token_bytes = values["token_bytes"]
+                    .value::<String>()
+                    .parse()
+                    .unwrap_or(settings.token_bytes);
+                if settings.set().is_err() {
+                    actions.toast.error("Failed to save generator options", cx);
+                    return;
+                }
+                actions.toast.success("Saved generator options", cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+                list.update();
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
+
+#[derive(Clone)]
+struct GenerateListBuilder;
+command!(GenerateListBuilder);
+impl StateViewBuilder for GenerateListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let settings = GenerateSettings::get();
+        context.query.set_placeholder("Filter generators...", cx);
+        context.actions.set_dropdown(
+            settings.length,
+            LENGTHS
+                .iter()
+                .map(|length| (length.to_string(), format!("{} Characters", length)))
+                .collect(),
+            cx,
+        );
+
+        let generated = cx.new_model(|_| Generated::default());
+        let list = context.actions.clone();
+        let regenerate = generated.clone();
+        context.actions.update_global(
+            vec![
+                Action::new(
+                    Img::default().icon(Icon::RefreshCw),
+                    "Regenerate",
+                    Some(Shortcut::new("r").cmd()),
+                    move |actions, cx| {
+                        regenerate.update(cx, |this, _| this.settings = None);
+                        actions.update();
+                    },
+                    false,
+                ),
+                Action::new(
+                    Img::default().icon(Icon::SlidersHorizontal),
+                    "Generator Options",
+                    Some(Shortcut::new(",").cmd()),
+                    move |_, cx| {
+                        let list = list.clone();
+                        StateModel::update(
+                            |this, cx| this.push(GenerateOptionsBuilder { list }, cx),
+                            cx,
+                        );
+                    },
+                    false,
+                ),
+            ],
+            cx,
+        );
+
+        ListBuilder::new()
+            .build(
+                move |this, _, cx| {
+                    let mut settings = GenerateSettings::get();
+                    if let Ok(length) = this.actions.get_dropdown_value(cx).parse() {
+                        if length != settings.length {
+                            settings.length = length;
+                            settings.set()?;
+                        }
+                    }
+                    let values = generated.update(cx, |this, _| {
+                        this.refresh(settings);
+                        this.values.clone()
+                    });
+                    Ok(Some(
+                        values
+                            .into_iter()
+                            .map(|(kind, value, bits)| kind.get_item(value, bits))
+                            .collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct GenerateCommandBuilder;
+command!(GenerateCommandBuilder);
+impl RootCommandBuilder for GenerateCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "generate",
+            "Generate Password",
+            "Generator",
+            Icon::KeyRound,
+            vec!["Password", "Passphrase", "UUID", "Token", "Random"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(GenerateListBuilder, cx), cx);
+            },
+        )
+    }
+}
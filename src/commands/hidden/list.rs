@@ -0,0 +1,106 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::*;
+
+use crate::{
+    command,
+    commands::{HiddenCommands, RootCommand, RootCommandBuilder, RootCommands},
+    components::{
+        list::{ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+#[derive(Clone)]
+pub struct HiddenListBuilder;
+command!(HiddenListBuilder);
+
+impl StateViewBuilder for HiddenListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Search hidden commands...", cx);
+        ListBuilder::new()
+            .build(
+                |_, _, cx| {
+                    let hidden = HiddenCommands::get();
+                    let mut commands: Vec<RootCommand> = cx
+                        .global::<RootCommands>()
+                        .commands
+                        .values()
+                        .filter(|command| hidden.contains(&command.id))
+                        .cloned()
+                        .collect();
+                    commands.sort_by(|a, b| a.title.cmp(&b.title));
+                    Ok(Some(
+                        commands
+                            .into_iter()
+                            .map(|command| {
+                                ItemBuilder::new(
+                                    command.id.clone(),
+                                    ListItem::new(
+                                        Some(Img::default().icon(command.icon.clone())),
+                                        command.title.clone(),
+                                        Some(command.subtitle.clone()),
+                                        vec![],
+                                    ),
+                                )
+                                .keywords(vec![command.title.clone()])
+                                .actions(vec![Action::new(
+                                    Img::default().icon(Icon::Eye),
+                                    "Show in Root List",
+                                    None,
+                                    {
+                                        let id = command.id.clone();
+                                        move |this, cx| {
+                                            if HiddenCommands::toggle(&id).is_err() {
+                                                this.toast.error("Failed to show command", cx);
+                                            } else {
+                                                this.toast.success("Command shown", cx);
+                                                this.update();
+                                                RootCommands::refresh(cx);
+                                            }
+                                        }
+                                    },
+                                    false,
+                                )])
+                                .build()
+                            })
+                            .collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct HiddenCommandBuilder;
+command!(HiddenCommandBuilder);
+
+impl RootCommandBuilder for HiddenCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "hidden",
+            "Hidden Commands",
+            "Customization",
+            Icon::EyeOff,
+            vec!["Disabled", "Show"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(HiddenListBuilder, cx), cx);
+            },
+        )
+    }
+}
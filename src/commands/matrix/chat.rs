@@ -15,16 +15,24 @@ use async_std::{
 };
 use futures::{future::Shared, FutureExt};
 use jiff::Timestamp;
-use std::{rc::Rc, sync::Arc, time::Duration};
+use std::{
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use url::Url;
 
 use gpui::*;
 use log::debug;
 use matrix_sdk::{
     ruma::{
+        api::client::receipt::create_receipt::v3::ReceiptType,
         events::{
             relation::Annotation,
-            room::{message::MessageType, MediaSource},
+            room::{
+                message::{MessageFormat, MessageType},
+                MediaSource,
+            },
         },
         OwnedUserId,
     },
@@ -39,19 +47,22 @@ use matrix_sdk_ui::{
 use crate::{
     command,
     components::{
-        list::{AsyncListItems, ItemBuilder, ItemComponent, ItemPreset, ListBuilder},
-        shared::{Icon, Img, ImgMask},
+        list::{AsyncListItems, ItemBuilder, ItemComponent, ItemPreset, List, ListBuilder},
+        shared::{animated_img, Icon, Img, ImgMask},
     },
     date::format_date,
     loader::Loader,
     state::{
-        Action, CommandTrait, Shortcut, StateItem, StateModel, StateViewBuilder, StateViewContext,
+        Action, Actions, CommandTrait, Shortcut, StateItem, StateModel, StateViewBuilder,
+        StateViewContext,
     },
-    theme::Theme,
+    theme::{ReduceMotion, Theme},
 };
 
 use super::{
     compose::{Compose, ComposeKind},
+    emoji::EmojiPicker,
+    formatted::{parse_html, render_blocks, Block},
     mxc::mxc_to_http,
 };
 
@@ -61,8 +72,13 @@ pub(super) struct ChatRoom {
     pub(super) timeline: Arc<Timeline>,
     pub(super) sync_service: Arc<SyncService>,
     pub(super) room: Arc<Room>,
+    // Event id of a message to select once the room is open
+    pub(super) focus: Option<String>,
 }
 
+// Number of history pages loaded while looking for the focused message
+const FOCUS_PAGES: usize = 10;
+
 pub trait OnMouseDown: Fn(&MouseDownEvent, &mut WindowContext) + 'static {}
 impl<F> OnMouseDown for F where F: Fn(&MouseDownEvent, &mut WindowContext) + 'static {}
 
@@ -114,7 +130,9 @@ impl RenderOnce for Reactions {
 #[derive(Clone, IntoElement)]
 pub(super) enum MessageContent {
     Text(String),
-    Image(ImageSource),
+    Formatted { id: String, blocks: Vec<Block> },
+    // Animated tells whether the mimetype allows for an animated GIF or APNG
+    Image { src: ImageSource, animated: bool },
     // Notice(String),
     // Audio(Img),
     // Video(ImageSource),
@@ -123,10 +141,18 @@ pub(super) enum MessageContent {
 }
 
 impl RenderOnce for MessageContent {
-    fn render(self, _cx: &mut WindowContext) -> impl IntoElement {
+    fn render(self, cx: &mut WindowContext) -> impl IntoElement {
         match self {
             MessageContent::Text(t) => t.into_any_element(),
-            MessageContent::Image(i) => img(i).w_64().h_48().into_any_element(),
+            MessageContent::Formatted { id, blocks } => {
+                render_blocks(&id, blocks, cx).into_any_element()
+            }
+            MessageContent::Image { src, animated } => {
+                match animated_img(&src, animated, gpui::ObjectFit::Contain, cx) {
+                    Some(animated) => div().w_64().h_48().child(animated).into_any_element(),
+                    None => img(src).w_64().h_48().into_any_element(),
+                }
+            }
         }
     }
 }
@@ -144,30 +170,78 @@ pub(super) struct Message {
     pub reactions: Reactions,
     pub first: bool,
     pub last: bool,
-    pub in_reply_to: Option<String>,
+    pub in_reply_to: Option<InReplyTo>,
+    pub highlight: Model<Highlight>,
     pub meta: AnyModel,
 }
 
+#[derive(Clone)]
+pub(super) struct InReplyTo {
+    pub id: String,
+    pub sender: String,
+    pub body: String,
+}
+
+// Message jumped to from a reply quote, and when
+pub(super) type Highlight = Option<(String, Instant)>;
+
+const HIGHLIGHT_DURATION: Duration = Duration::from_millis(1500);
+const REPLY_PREVIEW_LENGTH: usize = 80;
+
+fn reply_preview(body: &str) -> String {
+    let line = body.lines().next().unwrap_or_default();
+    if line.chars().count() > REPLY_PREVIEW_LENGTH {
+        format!(
+            "{}…",
+            line.chars().take(REPLY_PREVIEW_LENGTH).collect::<String>()
+        )
+    } else {
+        line.to_string()
+    }
+}
+
 impl Message {
     fn actions(&self, timeline: Arc<Timeline>, room: Arc<Room>) -> Vec<Action> {
-        let mut actions = vec![Action::new(
-            Img::default().icon(Icon::MessageCircleReply),
-            "Reply",
-            Some(Shortcut::new("r").cmd()),
-            {
-                let timeline = timeline.clone();
-                move |this, cx| {
-                    let event = this.get_meta::<EventTimelineItem>(cx).unwrap();
-                    let item = StateItem::init(
-                        Compose::new(timeline.clone(), ComposeKind::Reply { event }),
-                        false,
-                        cx,
-                    );
-                    StateModel::update(|this, cx| this.push_item(item, cx), cx);
-                }
-            },
-            false,
-        )];
+        let mut actions = vec![
+            Action::new(
+                Img::default().icon(Icon::MessageCircleReply),
+                "Reply",
+                Some(Shortcut::new("r").cmd()),
+                {
+                    let timeline = timeline.clone();
+                    move |this, cx| {
+                        let event = this.get_meta::<EventTimelineItem>(cx).unwrap();
+                        let item = StateItem::init(
+                            Compose::new(timeline.clone(), ComposeKind::Reply { event }),
+                            false,
+                            cx,
+                        );
+                        StateModel::update(|this, cx| this.push_item(item, cx), cx);
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::SmilePlus),
+                "React",
+                Some(Shortcut::new("j").cmd()),
+                {
+                    let timeline = timeline.clone();
+                    move |this, cx| {
+                        let event = this.get_meta::<EventTimelineItem>(cx).unwrap();
+                        let Some(event_id) = event.event_id() else {
+                            return;
+                        };
+                        let picker = EmojiPicker {
+                            timeline: timeline.clone(),
+                            event_id: event_id.to_owned(),
+                        };
+                        StateModel::update(|this, cx| this.push(picker, cx), cx);
+                    }
+                },
+                false,
+            ),
+        ];
         if self.me {
             actions.append(&mut vec![
                 Action::new(
@@ -226,6 +300,13 @@ impl ItemComponent for Message {
         let theme = cx.global::<Theme>();
         let show_avatar = !self.me && self.first;
         let show_reactions = !self.reactions.0.is_empty();
+        let highlighted = self
+            .highlight
+            .read(cx)
+            .as_ref()
+            .filter(|(id, at)| id.eq(&self.id) && at.elapsed() < HIGHLIGHT_DURATION)
+            .map(|(_, at)| *at)
+            .filter(|_| !ReduceMotion::enabled(cx));
 
         if show_reactions {
             div().mb_8()
@@ -270,6 +351,37 @@ impl ItemComponent for Message {
             .border_color(theme.crust)
             .text_sm()
             .relative()
+            .children(highlighted.map(|at| {
+                let color = theme.accent();
+                div().absolute().inset_0().rounded_lg().with_animation(
+                    ElementId::Name(format!("highlight-{}-{:?}", self.id, at).into()),
+                    Animation::new(HIGHLIGHT_DURATION),
+                    move |div, delta| div.bg(color.opacity(0.3 * (1.0 - delta))),
+                )
+            }))
+            .children(self.in_reply_to.clone().map(|reply| {
+                let highlight = self.highlight.clone();
+                div()
+                    .mb_1()
+                    .pl_2()
+                    .border_l_2()
+                    .border_color(theme.accent())
+                    .text_xs()
+                    .cursor_pointer()
+                    .child(
+                        div()
+                            .text_color(theme.accent())
+                            .font_weight(FontWeight::SEMIBOLD)
+                            .child(reply.sender),
+                    )
+                    .child(div().text_color(theme.subtext0).child(reply.body))
+                    .on_mouse_down(MouseButton::Left, move |_, cx| {
+                        highlight.update(cx, |highlight, cx| {
+                            *highlight = Some((reply.id.clone(), Instant::now()));
+                            cx.notify();
+                        });
+                    })
+            }))
             .child(self.content.clone())
             .child(
                 div()
@@ -326,10 +438,61 @@ fn get_source(
     }
 }
 
+// Users currently typing in the room, alongside their display names
+type Typing = Vec<(OwnedUserId, String)>;
+
+async fn sync_typing(room: Arc<Room>, typing: WeakModel<Typing>, cx: &mut AsyncWindowContext) {
+    let (_guard, mut receiver) = room.subscribe_to_typing_notifications();
+    while let Ok(mut users) = receiver.recv().await {
+        // Typing notifications come in bursts, so only apply the latest one
+        sleep(Duration::from_millis(300)).await;
+        while let Ok(latest) = receiver.try_recv() {
+            users = latest;
+        }
+        let mut typers: Typing = vec![];
+        for user in users {
+            let name = room
+                .get_member_no_sync(&user)
+                .await
+                .ok()
+                .flatten()
+                .map(|member| member.name().to_string())
+                .unwrap_or(user.to_string());
+            typers.push((user, name));
+        }
+        let result = typing.update(cx, |typing, cx| {
+            *typing = typers;
+            cx.notify();
+        });
+        if result.is_err() {
+            break;
+        }
+    }
+}
+
+// Whether the room has been opened, as opposed to being previewed next to the room list
+fn is_open(actions: &WeakView<Actions>, cx: &AppContext) -> bool {
+    cx.global::<StateModel>()
+        .inner
+        .read(cx)
+        .stack
+        .last()
+        .is_some_and(|item| item.actions.entity_id() == actions.entity_id())
+}
+
+async fn mark_read(timeline: Arc<Timeline>) {
+    if let Err(err) = timeline.mark_as_read(ReceiptType::Read).await {
+        debug!("Failed to mark room as read: {:?}", err);
+    }
+}
+
 async fn sync(
     timeline: Arc<Timeline>,
     room: Arc<Room>,
+    actions: WeakView<Actions>,
     view: WeakView<AsyncListItems>,
+    typing: WeakModel<Typing>,
+    highlight: Model<Highlight>,
     cx: &mut AsyncWindowContext,
 ) -> anyhow::Result<()> {
     let (mut messages, mut stream) = timeline.subscribe().await;
@@ -368,13 +531,30 @@ async fn sync(
                     .unwrap_or(m.sender().to_string()),
                 content: match m.content() {
                     TimelineItemContent::Message(m) => match m.msgtype() {
-                        MessageType::Text(t) => MessageContent::Text(t.body.clone()),
-                        MessageType::Image(i) => MessageContent::Image(ImageSource::Uri({
-                            let Ok(url) = get_source(&i.source, server.clone()) else {
-                                continue;
-                            };
-                            url.to_string().into()
-                        })),
+                        MessageType::Text(t) => match &t.formatted {
+                            Some(formatted) if formatted.format == MessageFormat::Html => {
+                                MessageContent::Formatted {
+                                    id: id.clone(),
+                                    blocks: parse_html(&formatted.body),
+                                }
+                            }
+                            _ => MessageContent::Text(t.body.clone()),
+                        },
+                        MessageType::Image(i) => MessageContent::Image {
+                            src: ImageSource::Uri({
+                                let Ok(url) = get_source(&i.source, server.clone()) else {
+                                    continue;
+                                };
+                                url.to_string().into()
+                            }),
+                            animated: i
+                                .info
+                                .as_ref()
+                                .and_then(|info| info.mimetype.as_deref())
+                                .is_some_and(|mime| {
+                                    matches!(mime, "image/gif" | "image/png" | "image/apng")
+                                }),
+                        },
                         _ => MessageContent::Text("Unsupported message type".to_string()),
                     },
                     _ => {
@@ -410,7 +590,41 @@ async fn sync(
                 timestamp: Timestamp::from_second(m.timestamp().as_secs().into()).unwrap(),
                 first: false,
                 last: false,
-                in_reply_to: None,
+                in_reply_to: match m.content() {
+                    TimelineItemContent::Message(message) => message.in_reply_to().map(|reply| {
+                        let (sender, body) = match &reply.event {
+                            TimelineDetails::Ready(event) => (
+                                match event.sender_profile() {
+                                    TimelineDetails::Ready(profile) => profile.display_name.clone(),
+                                    _ => None,
+                                }
+                                .unwrap_or(event.sender().to_string()),
+                                match event.content() {
+                                    TimelineItemContent::Message(m) => reply_preview(m.body()),
+                                    _ => "Unsupported message".to_string(),
+                                },
+                            ),
+                            details => {
+                                // The replied to event isn't part of the loaded timeline
+                                if let TimelineDetails::Unavailable = details {
+                                    let timeline = timeline.clone();
+                                    let id = reply.event_id.clone();
+                                    spawn(async move {
+                                        let _ = timeline.fetch_details_for_event(&id).await;
+                                    });
+                                }
+                                ("…".to_string(), "Loading message".to_string())
+                            }
+                        };
+                        InReplyTo {
+                            id: reply.event_id.to_string(),
+                            sender,
+                            body,
+                        }
+                    }),
+                    _ => None,
+                },
+                highlight: highlight.clone(),
                 meta: cx.new_model(|_| m.clone()).unwrap().into_any(),
             };
             if !prev.as_ref().is_some_and(|s| s.eq(&m.sender())) {
@@ -444,12 +658,27 @@ async fn sync(
         if result.is_err() {
             break;
         }
+        // Everything up to the latest message has been seen, unless this is just the preview
+        if cx.update(|cx| is_open(&actions, cx)).unwrap_or(false) {
+            mark_read(timeline.clone()).await;
+        }
 
         if let Some(diff) = stream.next().await {
             diff.apply(&mut messages);
         } else {
             break;
         }
+        // Whoever just sent a message has stopped typing
+        if let Some(sender) = messages
+            .last()
+            .and_then(|m| m.as_event())
+            .map(|m| m.sender().to_owned())
+        {
+            let _ = typing.update(cx, |typing, cx| {
+                typing.retain(|(user, _)| user.ne(&sender));
+                cx.notify();
+            });
+        }
     }
 
     Ok(())
@@ -460,18 +689,46 @@ impl StateViewBuilder for ChatRoom {
     fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
         context.query.set_placeholder("Search this chat...", cx);
 
+        let typing = cx.new_model(|_| Typing::new());
+        let highlight = cx.new_model(|_| Highlight::None);
+        let actions = context.actions.inner.clone();
         let view = cx.new_view(|cx| {
             {
+                // Opening the room marks it as read, previewing it in the room list doesn't
+                let state = cx.global::<StateModel>().inner.clone();
+                cx.observe(&state, {
+                    let timeline = self.timeline.clone();
+                    let actions = actions.clone();
+                    let mut was_open = false;
+                    move |_, _, cx| {
+                        let open = is_open(&actions, cx);
+                        if open && !was_open {
+                            cx.spawn(|_, _| mark_read(timeline.clone())).detach();
+                        }
+                        was_open = open;
+                    }
+                })
+                .detach();
                 cx.spawn({
                     let timeline = self.timeline.clone();
                     let room = self.room.clone();
+                    let typing = typing.downgrade();
+                    let highlight = highlight.clone();
                     |view, mut cx| async move {
-                        if let Err(err) = sync(timeline, room, view, &mut cx).await {
+                        if let Err(err) =
+                            sync(timeline, room, actions, view, typing, highlight, &mut cx).await
+                        {
                             debug!("Updating room failed: {:?}", err);
                         }
                     }
                 })
                 .detach();
+                cx.spawn({
+                    let room = self.room.clone();
+                    let typing = typing.downgrade();
+                    |_, mut cx| async move { sync_typing(room, typing, &mut cx).await }
+                })
+                .detach();
             }
             AsyncListItems::new()
         });
@@ -511,6 +768,8 @@ impl StateViewBuilder for ChatRoom {
             .scroll_handler({
                 let timeline = self.timeline.clone();
                 let mut future: Option<Shared<JoinHandle<()>>> = None;
+                // The room timeline is live and always ends at the newest event, so there is
+                // never anything to paginate forwards, scrolling down just reveals what's loaded
                 move |ev, _| {
                     if ev.visible_range.start < 5
                         && (future.is_none()
@@ -542,6 +801,89 @@ impl StateViewBuilder for ChatRoom {
                 cx,
             );
 
-        list.into()
+        context.actions.update_global(
+            vec![Action::new(
+                Img::default().icon(Icon::ArrowDownToLine),
+                "Jump to Latest",
+                Some(Shortcut::new("down").cmd()),
+                {
+                    let list = list.downgrade();
+                    move |_, cx| {
+                        let _ = list.update(cx, |list, cx| list.scroll_to_bottom(cx));
+                    }
+                },
+                false,
+            )],
+            cx,
+        );
+
+        // Clicking a reply quote selects the original message, if it's loaded
+        cx.observe(&highlight, {
+            let list = list.downgrade();
+            move |highlight, cx| {
+                let Some((id, _)) = highlight.read(cx).clone() else {
+                    return;
+                };
+                let _ = list.update(cx, |list, cx| list.select_id(&id, cx));
+            }
+        })
+        .detach();
+
+        if let Some(focus) = self.focus.clone() {
+            let list = list.downgrade();
+            let timeline = self.timeline.clone();
+            cx.spawn(|mut cx| async move {
+                for _ in 0..FOCUS_PAGES {
+                    cx.background_executor()
+                        .timer(Duration::from_millis(250))
+                        .await;
+                    match list.update(&mut cx, |list, cx| list.select_id(&focus, cx)) {
+                        Ok(false) => {}
+                        _ => return,
+                    }
+                    let _ = timeline
+                        .paginate_backwards(PaginationOptions::simple_request(20))
+                        .await;
+                }
+            })
+            .detach();
+        }
+
+        cx.new_view(|cx| {
+            cx.observe(&typing, |_, _, cx| cx.notify()).detach();
+            ChatView { list, typing }
+        })
+        .into()
+    }
+}
+
+struct ChatView {
+    list: View<List>,
+    typing: Model<Typing>,
+}
+
+impl Render for ChatView {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let theme = cx.global::<Theme>();
+        let typing = self.typing.read(cx);
+        let line = match typing.as_slice() {
+            [] => None,
+            [(_, name)] => Some(format!("{} is typing…", name)),
+            [(_, a), (_, b)] => Some(format!("{} and {} are typing…", a, b)),
+            _ => Some("Several people are typing…".to_string()),
+        };
+        div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .child(div().flex_1().child(self.list.clone()))
+            .children(line.map(|line| {
+                div()
+                    .px_2()
+                    .pt_1()
+                    .text_xs()
+                    .text_color(theme.subtext0)
+                    .child(line)
+            }))
     }
 }
@@ -25,7 +25,7 @@ use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    db::Db,
+    db::{Db, Migrate},
     paths::{paths, NAME},
     state::{Actions, StateModel},
 };
@@ -39,6 +39,8 @@ pub(super) struct Session {
     passphrase: String,
 }
 
+impl Migrate for Session {}
+
 pub fn db() -> &'static Database {
     static DB: OnceLock<Database> = OnceLock::new();
     DB.get_or_init(Db::init_collection::<Session>)
@@ -9,16 +9,24 @@
  *
  */
 
-use std::sync::Arc;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
 use gpui::*;
-use matrix_sdk::ruma::events::room::message::{ForwardThread, RoomMessageEventContent};
+use matrix_sdk::{
+    attachment::AttachmentConfig,
+    ruma::events::room::message::{ForwardThread, RoomMessageEventContent},
+};
 use matrix_sdk_ui::{timeline::EventTimelineItem, Timeline};
+use mime::Mime;
 
 use crate::{
     command,
-    components::shared::{Icon, Img, NoView},
-    state::{Action, CommandTrait, StateViewBuilder, StateViewContext},
+    components::shared::{Icon, Img, ImgSize},
+    state::{Action, CommandTrait, Shortcut, StateViewBuilder, StateViewContext},
+    theme::Theme,
 };
 
 #[derive(Clone)]
@@ -61,7 +69,65 @@ impl Compose {
 
         Ok(())
     }
+    async fn send_attachment(&self, path: PathBuf) -> anyhow::Result<()> {
+        let mime = mime_type(&path);
+        self.timeline
+            .send_attachment(
+                path.to_string_lossy().to_string(),
+                mime,
+                AttachmentConfig::new(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+fn mime_type(path: &Path) -> Mime {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => mime::IMAGE_PNG,
+        "jpg" | "jpeg" => mime::IMAGE_JPEG,
+        "gif" => mime::IMAGE_GIF,
+        "svg" => mime::IMAGE_SVG,
+        "webp" => "image/webp"
+            .parse()
+            .unwrap_or(mime::APPLICATION_OCTET_STREAM),
+        "pdf" => mime::APPLICATION_PDF,
+        "txt" => mime::TEXT_PLAIN,
+        _ => mime::APPLICATION_OCTET_STREAM,
+    }
+}
+
+struct Attachment {
+    path: Model<Option<PathBuf>>,
+}
+
+impl Render for Attachment {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let theme = cx.global::<Theme>();
+        let Some(path) = self.path.read(cx) else {
+            return div();
+        };
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        div()
+            .flex()
+            .items_center()
+            .p_2()
+            .rounded_lg()
+            .bg(theme.mantle)
+            .border_1()
+            .border_color(theme.crust)
+            .child(Img::default().icon(Icon::Paperclip).size(ImgSize::SM))
+            .child(div().ml_2().text_sm().child(name))
+    }
 }
+
 command!(Compose);
 impl StateViewBuilder for Compose {
     fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
@@ -69,36 +135,102 @@ impl StateViewBuilder for Compose {
 
         let query = context.query.clone();
         let self_clone = self.clone();
+        let attachment = cx.new_model(|_| None::<PathBuf>);
 
-        context.actions.update_global(
-            vec![Action::new(
-                Img::default().icon(Icon::Send),
-                "Send Message",
-                None,
-                move |this, cx| {
-                    let query = query.clone();
-                    let text = query.get_text(cx);
-                    if text.is_empty() {
-                        return;
+        let mut actions = vec![Action::new(
+            Img::default().icon(Icon::Send),
+            "Send Message",
+            None,
+            move |this, cx| {
+                let query = query.clone();
+                let text = query.get_text(cx);
+                if text.is_empty() {
+                    return;
+                }
+                query.set_text("", cx);
+                let mut toast = this.toast.clone();
+                let self_clone = self_clone.clone();
+                cx.spawn(|mut cx| async move {
+                    let content = RoomMessageEventContent::text_markdown(text);
+                    if self_clone.send(content).await.is_ok() {
+                        toast.success("Messagen sent", &mut cx);
+                    } else {
+                        toast.error("Failed to send message", &mut cx);
                     }
-                    query.set_text("", cx);
-                    let mut toast = this.toast.clone();
-                    let self_clone = self_clone.clone();
-                    cx.spawn(|mut cx| async move {
-                        let content = RoomMessageEventContent::text_markdown(text);
-                        if self_clone.send(content).await.is_ok() {
-                            toast.success("Messagen sent", &mut cx);
-                        } else {
-                            toast.error("Failed to send message", &mut cx);
+                })
+                .detach();
+            },
+            false,
+        )];
+        // Attachments are sent as standalone messages, so editing doesn't support them
+        if !matches!(self.kind, ComposeKind::Edit { .. }) {
+            actions.append(&mut vec![
+                Action::new(
+                    Img::default().icon(Icon::Paperclip),
+                    "Attach File",
+                    Some(Shortcut::new("o").cmd()),
+                    {
+                        let attachment = attachment.clone();
+                        move |_, cx| {
+                            let paths = cx.prompt_for_paths(PathPromptOptions {
+                                files: true,
+                                directories: false,
+                                multiple: false,
+                            });
+                            let attachment = attachment.clone();
+                            cx.spawn(|mut cx| async move {
+                                let Ok(Some(paths)) = paths.await else {
+                                    return;
+                                };
+                                let _ = attachment.update(&mut cx, |attachment, cx| {
+                                    *attachment = paths.into_iter().next();
+                                    cx.notify();
+                                });
+                            })
+                            .detach();
+                        }
+                    },
+                    false,
+                ),
+                Action::new(
+                    Img::default().icon(Icon::Upload),
+                    "Send Attachment",
+                    Some(Shortcut::new("enter").cmd()),
+                    {
+                        let attachment = attachment.clone();
+                        let self_clone = self.clone();
+                        move |this, cx| {
+                            let mut toast = this.toast.clone();
+                            let Some(path) = attachment.read(cx).clone() else {
+                                toast.error("No file selected", cx);
+                                return;
+                            };
+                            attachment.update(cx, |attachment, cx| {
+                                *attachment = None;
+                                cx.notify();
+                            });
+                            let self_clone = self_clone.clone();
+                            cx.spawn(|mut cx| async move {
+                                toast.loading("Uploading attachment...", &mut cx);
+                                match self_clone.send_attachment(path).await {
+                                    Ok(_) => {
+                                        toast.success("Attachment sent", &mut cx);
+                                    }
+                                    Err(err) => {
+                                        log::error!("Failed to send attachment {:?}", err);
+                                        toast.error("Failed to send attachment", &mut cx);
+                                    }
+                                }
+                            })
+                            .detach();
                         }
-                    })
-                    .detach();
-                },
-                false,
-            )],
-            cx,
-        );
-
-        cx.new_view(|_| NoView).into()
+                    },
+                    false,
+                ),
+            ]);
+        }
+        context.actions.update_global(actions, cx);
+
+        cx.new_view(|_| Attachment { path: attachment }).into()
     }
 }
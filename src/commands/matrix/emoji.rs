@@ -0,0 +1,111 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::sync::Arc;
+
+use emojis::Emoji;
+use gpui::*;
+use matrix_sdk::ruma::{events::relation::Annotation, OwnedEventId};
+use matrix_sdk_ui::Timeline;
+
+use crate::{
+    command,
+    commands::emoji::list::{recent, remember, skin_tone},
+    components::{
+        list::{Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+#[derive(Clone)]
+pub(super) struct EmojiPicker {
+    pub(super) timeline: Arc<Timeline>,
+    pub(super) event_id: OwnedEventId,
+}
+
+impl EmojiPicker {
+    fn item(&self, emoji: &'static Emoji, section: &str) -> Item {
+        let timeline = self.timeline.clone();
+        let event_id = self.event_id.clone();
+        let value = emoji.as_str();
+        let mut keywords = vec![emoji.name().to_string()];
+        keywords.extend(emoji.shortcodes().map(|s| s.to_string()));
+        ItemBuilder::new(
+            format!("{}{}", section, value),
+            ListItem::new(None, format!("{}  {}", value, emoji.name()), None, vec![]),
+        )
+        .keywords(keywords)
+        .section(Some(section))
+        .actions(vec![Action::new(
+            Img::default().icon(Icon::SmilePlus),
+            "React",
+            None,
+            move |this, cx| {
+                remember(value);
+                let annotation = Annotation::new(event_id.clone(), value.to_string());
+                let timeline = timeline.clone();
+                let mut toast = this.toast.clone();
+                cx.spawn(|mut cx| async move {
+                    if let Err(err) = timeline.toggle_reaction(&annotation).await {
+                        log::error!("Failed to add reaction {:?}", err);
+                        toast.error("Failed to add reaction", &mut cx);
+                    }
+                })
+                .detach();
+                StateModel::update(|this, cx| this.pop(cx), cx);
+            },
+            false,
+        )])
+        .build()
+    }
+}
+
+command!(EmojiPicker);
+impl StateViewBuilder for EmojiPicker {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder("Search emoji...", cx);
+        context.actions.set_dropdown(
+            "",
+            vec![
+                ("", "Default Skin Tone"),
+                ("light", "Light"),
+                ("medium-light", "Medium Light"),
+                ("medium", "Medium"),
+                ("medium-dark", "Medium Dark"),
+                ("dark", "Dark"),
+            ],
+            cx,
+        );
+
+        let picker = self.clone();
+        ListBuilder::new()
+            .build(
+                move |list, _, cx| {
+                    let tone = skin_tone(&list.actions.get_dropdown_value(cx));
+                    let toned = |emoji: &'static Emoji| {
+                        tone.and_then(|tone| emoji.with_skin_tone(tone))
+                            .unwrap_or(emoji)
+                    };
+                    let mut items: Vec<Item> = recent()
+                        .iter()
+                        .filter_map(|e| emojis::get(e))
+                        .map(|emoji| picker.item(emoji, "Recently Used"))
+                        .collect();
+                    items.extend(emojis::iter().map(|emoji| picker.item(toned(emoji), "Emoji")));
+                    Ok(Some(items))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
@@ -0,0 +1,230 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::ops::Range;
+
+use gpui::*;
+use scraper::{ElementRef, Html, Node};
+
+use crate::theme::Theme;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Style {
+    Bold,
+    Italic,
+    Code,
+    Link,
+}
+
+#[derive(Clone)]
+pub(super) enum Block {
+    Text {
+        text: String,
+        styles: Vec<(Range<usize>, Style)>,
+        links: Vec<(Range<usize>, String)>,
+    },
+    Code(String),
+    Quote(Vec<Block>),
+}
+
+#[derive(Default)]
+struct Parser {
+    blocks: Vec<Block>,
+    text: String,
+    styles: Vec<(Range<usize>, Style)>,
+    links: Vec<(Range<usize>, String)>,
+}
+
+impl Parser {
+    fn newline(&mut self) {
+        if !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push('\n');
+        }
+    }
+    fn flush(&mut self) {
+        let len = self.text.trim_end().len();
+        self.text.truncate(len);
+        let clamp = |range: Range<usize>| range.start.min(len)..range.end.min(len);
+        let styles = self
+            .styles
+            .drain(..)
+            .map(|(range, style)| (clamp(range), style))
+            .filter(|(range, _)| !range.is_empty())
+            .collect();
+        let links = self
+            .links
+            .drain(..)
+            .map(|(range, url)| (clamp(range), url))
+            .filter(|(range, _)| !range.is_empty())
+            .collect();
+        let text = std::mem::take(&mut self.text);
+        if !text.is_empty() {
+            self.blocks.push(Block::Text {
+                text,
+                styles,
+                links,
+            });
+        }
+    }
+    fn styled(&mut self, element: ElementRef, style: Style) {
+        let start = self.text.len();
+        self.children(element);
+        self.styles.push((start..self.text.len(), style));
+    }
+    fn children(&mut self, element: ElementRef) {
+        for child in element.children() {
+            match child.value() {
+                Node::Text(text) => self.text.push_str(text),
+                Node::Element(_) => {
+                    if let Some(element) = ElementRef::wrap(child) {
+                        self.element(element);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    fn element(&mut self, element: ElementRef) {
+        match element.value().name() {
+            // Reply fallbacks duplicate the quoted message, which is rendered separately
+            "mx-reply" => {}
+            "strong" | "b" => self.styled(element, Style::Bold),
+            "em" | "i" => self.styled(element, Style::Italic),
+            "code" => self.styled(element, Style::Code),
+            "a" => {
+                let start = self.text.len();
+                self.children(element);
+                let range = start..self.text.len();
+                if let Some(href) = element.value().attr("href") {
+                    self.links.push((range.clone(), href.to_string()));
+                    self.styles.push((range, Style::Link));
+                }
+            }
+            "pre" => {
+                self.flush();
+                let code: String = element.text().collect();
+                self.blocks
+                    .push(Block::Code(code.trim_end_matches('\n').to_string()));
+            }
+            "blockquote" => {
+                self.flush();
+                let mut inner = Parser::default();
+                inner.children(element);
+                inner.flush();
+                self.blocks.push(Block::Quote(inner.blocks));
+            }
+            "br" => self.text.push('\n'),
+            "li" => {
+                self.newline();
+                self.text.push_str("• ");
+                self.children(element);
+                self.newline();
+            }
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.newline();
+                self.styled(element, Style::Bold);
+                self.newline();
+            }
+            "p" | "div" | "ul" | "ol" => {
+                self.newline();
+                self.children(element);
+                self.newline();
+            }
+            // Anything else degrades to its plain text
+            _ => self.children(element),
+        }
+    }
+}
+
+pub(super) fn parse_html(html: &str) -> Vec<Block> {
+    let fragment = Html::parse_fragment(html);
+    let mut parser = Parser::default();
+    parser.children(fragment.root_element());
+    parser.flush();
+    parser.blocks
+}
+
+pub(super) fn render_blocks(id: &str, blocks: Vec<Block>, cx: &WindowContext) -> Div {
+    let theme = cx.global::<Theme>();
+    div()
+        .flex()
+        .flex_col()
+        .gap_1()
+        .children(blocks.into_iter().enumerate().map(|(i, block)| {
+            let id = format!("{}-{}", id, i);
+            match block {
+                Block::Text {
+                    text,
+                    styles,
+                    links,
+                } => {
+                    let highlights = styles.into_iter().map(|(range, style)| {
+                        let highlight = match style {
+                            Style::Bold => HighlightStyle {
+                                font_weight: Some(FontWeight::BOLD),
+                                ..HighlightStyle::default()
+                            },
+                            Style::Italic => HighlightStyle {
+                                font_style: Some(FontStyle::Italic),
+                                ..HighlightStyle::default()
+                            },
+                            Style::Code => HighlightStyle {
+                                background_color: Some(theme.surface0),
+                                ..HighlightStyle::default()
+                            },
+                            Style::Link => HighlightStyle {
+                                color: Some(theme.blue),
+                                underline: Some(UnderlineStyle {
+                                    thickness: px(1.),
+                                    color: Some(theme.blue),
+                                    wavy: false,
+                                }),
+                                ..HighlightStyle::default()
+                            },
+                        };
+                        (range, highlight)
+                    });
+                    let style = TextStyle {
+                        color: theme.text,
+                        font_family: theme.font_sans.clone(),
+                        ..TextStyle::default()
+                    };
+                    let (ranges, urls): (Vec<Range<usize>>, Vec<String>) =
+                        links.into_iter().unzip();
+                    InteractiveText::new(
+                        ElementId::Name(id.into()),
+                        StyledText::new(text).with_highlights(&style, highlights),
+                    )
+                    .on_click(ranges, move |ix, cx| {
+                        if let Some(url) = urls.get(ix) {
+                            cx.open_url(url);
+                        }
+                    })
+                    .into_any_element()
+                }
+                Block::Code(code) => div()
+                    .p_2()
+                    .rounded_md()
+                    .bg(theme.mantle)
+                    .font_family(theme.font_mono.clone())
+                    .text_sm()
+                    .child(code)
+                    .into_any_element(),
+                Block::Quote(blocks) => div()
+                    .pl_2()
+                    .border_l_2()
+                    .border_color(theme.overlay0)
+                    .text_color(theme.subtext0)
+                    .child(render_blocks(&id, blocks, cx))
+                    .into_any_element(),
+            }
+        }))
+}
@@ -13,20 +13,34 @@ use std::{cmp::Reverse, collections::HashMap, sync::Arc};
 
 use async_std::{stream::StreamExt, task::spawn};
 use bonsaidb::core::schema::SerializedCollection;
+use futures::channel::mpsc::unbounded;
 use gpui::*;
-use matrix_sdk::ruma::OwnedRoomId;
+use matrix_sdk::{
+    deserialized_responses::RawAnySyncOrStrippedTimelineEvent,
+    ruma::{
+        events::{AnySyncMessageLikeEvent, AnySyncTimelineEvent},
+        OwnedRoomId, RoomId,
+    },
+    sync::Notification,
+    Client, Room,
+};
 use matrix_sdk_ui::{sync_service::State, timeline::RoomExt};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     command,
     commands::{RootCommand, RootCommandBuilder},
     components::{
-        list::{AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
+        list::{Accessory, AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
         shared::{Icon, Img, ImgMask},
     },
+    platform::{send_notification, NotificationRouter},
     state::{
-        Action, CommandTrait, Shortcut, StateItem, StateModel, StateViewBuilder, StateViewContext,
+        Action, CommandTrait, LazyMutex, Shortcut, StateItem, StateModel, StateViewBuilder,
+        StateViewContext,
     },
+    theme::Theme,
+    window::Window,
 };
 
 use super::{
@@ -37,6 +51,31 @@ use super::{
     mxc::mxc_to_http,
 };
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct MatrixSettings {
+    // Show desktop notifications for new messages while Loungy is closed
+    notifications: bool,
+}
+
+impl Default for MatrixSettings {
+    fn default() -> Self {
+        Self {
+            notifications: true,
+        }
+    }
+}
+
+impl MatrixSettings {
+    fn get() -> Self {
+        crate::db::db().get::<Self>("matrix").unwrap_or_default()
+    }
+    fn set(&self) -> anyhow::Result<()> {
+        crate::db::db().set::<Self>("matrix", self)?;
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 struct RoomList {
     view: View<AsyncListItems>,
@@ -56,6 +95,33 @@ impl StateViewBuilder for RoomList {
                 context.actions.set_dropdown("", options, cx);
             }
         }
+        context.actions.update_global(
+            vec![Action::new(
+                Img::default().icon(Icon::Bell),
+                "Toggle Notifications",
+                None,
+                |actions, cx| {
+                    let mut settings = MatrixSettings::get();
+                    settings.notifications = !settings.notifications;
+                    if settings.set().is_err() {
+                        actions
+                            .toast
+                            .error("Failed to save notification setting", cx);
+                        return;
+                    }
+                    actions.toast.success(
+                        if settings.notifications {
+                            "Enabled notifications"
+                        } else {
+                            "Disabled notifications"
+                        },
+                        cx,
+                    );
+                },
+                false,
+            )],
+            cx,
+        );
 
         AsyncListItems::loader(&self.view, &context.actions, cx);
         let view = self.view.clone();
@@ -69,7 +135,11 @@ impl StateViewBuilder for RoomList {
                     } else {
                         items.get(&account).cloned().unwrap_or_default()
                     };
-                    items.sort_unstable_by_key(|item| Reverse(item.get_meta::<u64>(cx).unwrap()));
+                    // Rooms with unread messages first, then by latest activity
+                    items.sort_unstable_by_key(|item| {
+                        let (unread, timestamp) = item.get_meta::<(u64, u64)>(cx).unwrap();
+                        Reverse((unread > 0, timestamp))
+                    });
                     Ok(Some(items))
                 },
                 context,
@@ -79,8 +149,86 @@ impl StateViewBuilder for RoomList {
     }
 }
 
+// Rooms of all accounts, shared with the message search
+pub(super) static ROOMS: LazyMutex<HashMap<OwnedRoomId, ChatRoom>> = LazyMutex::new(HashMap::new);
+
+// Notification ids are the room id behind this prefix
+static NOTIFICATION_PREFIX: &str = "matrix-";
+
 pub struct MatrixCommandBuilder;
 
+// Messages the push rules deem notification worthy, which leaves out muted rooms, are shown
+// as desktop notifications while the window is closed
+async fn notifications(client: &Client, cx: &mut AsyncWindowContext) {
+    let (sender, mut receiver) = unbounded::<(String, String, String)>();
+    client
+        .register_notification_handler(move |notification: Notification, room: Room, _| {
+            let sender = sender.clone();
+            async move {
+                if !notification
+                    .actions
+                    .iter()
+                    .any(|action| action.should_notify())
+                {
+                    return;
+                }
+                let RawAnySyncOrStrippedTimelineEvent::Sync(event) = notification.event else {
+                    return;
+                };
+                let Ok(AnySyncTimelineEvent::MessageLike(AnySyncMessageLikeEvent::RoomMessage(
+                    event,
+                ))) = event.deserialize()
+                else {
+                    return;
+                };
+                let Some(event) = event.as_original() else {
+                    return;
+                };
+                let name = room
+                    .get_member_no_sync(&event.sender)
+                    .await
+                    .ok()
+                    .flatten()
+                    .map(|member| member.name().to_string())
+                    .unwrap_or(event.sender.to_string());
+                let _ = sender.unbounded_send((
+                    room.room_id().to_string(),
+                    room.name().unwrap_or(name.clone()),
+                    format!("{}: {}", name, event.content.body()),
+                ));
+            }
+        })
+        .await;
+    cx.spawn(|cx| async move {
+        while let Some((id, title, body)) = receiver.next().await {
+            if Window::is_open(&cx) || !MatrixSettings::get().notifications {
+                continue;
+            }
+            send_notification(&format!("{}{}", NOTIFICATION_PREFIX, id), &title, &body);
+        }
+    })
+    .detach();
+}
+
+// Clicking a notification opens Loungy right into the room
+fn open_clicked(id: &str, cx: &mut WindowContext) {
+    let Some(room) = id
+        .strip_prefix(NOTIFICATION_PREFIX)
+        .and_then(|id| RoomId::parse(id).ok())
+        .and_then(|id| ROOMS.lock().get(&id).cloned())
+    else {
+        return;
+    };
+    Window::open(cx);
+    StateModel::update(
+        |this, cx| {
+            this.reset(cx);
+            this.push(room, cx);
+        },
+        cx,
+    );
+}
+
 async fn sync(
     session: Session,
     view: WeakView<AsyncListItems>,
@@ -92,10 +240,10 @@ async fn sync(
     };
 
     ss.start().await;
+    notifications(&client, &mut cx).await;
 
     let server = client.homeserver();
 
-    let mut previews = HashMap::<OwnedRoomId, ChatRoom>::new();
     let (mut rooms, mut stream) = ss.room_list_service().all_rooms().await?.entries();
 
     {
@@ -134,6 +282,23 @@ async fn sync(
                 .map(|ev| ev.timestamp().as_secs().into())
                 .unwrap_or(0);
 
+            let unread = room.unread_notification_counts();
+            let accessories = if unread.notification_count > 0 {
+                // Mentions get a red dot to stand out from regular unread messages
+                let dot = if unread.highlight_count > 0 {
+                    cx.update(|cx| Img::default().dot(cx.global::<Theme>().red))
+                        .ok()
+                } else {
+                    None
+                };
+                vec![Accessory::Tag {
+                    tag: unread.notification_count.to_string(),
+                    img: dot,
+                }]
+            } else {
+                vec![]
+            };
+
             let name = room.name().unwrap_or("".to_string());
             let dm = room.is_direct().await?;
 
@@ -166,21 +331,20 @@ async fn sync(
             img.mask = ImgMask::Circle;
 
             let room_id = room.room_id().to_owned();
-            let preview = if let Some(preview) = previews.get(&room_id) {
-                preview.clone()
-            } else {
-                let preview = ChatRoom {
+            let preview = ROOMS
+                .lock()
+                .entry(room_id.clone())
+                .or_insert_with(|| ChatRoom {
                     timeline: timeline.clone(),
                     sync_service: ss.clone(),
                     room: room.clone(),
-                };
-                previews.insert(room_id.clone(), preview.clone());
-                preview
-            };
+                    focus: None,
+                })
+                .clone();
 
             let item = ItemBuilder::new(
                 room_id.clone(),
-                ListItem::new(Some(img), name.clone(), None, vec![]),
+                ListItem::new(Some(img), name.clone(), None, accessories),
             )
             .keywords(vec![name.clone()])
             .actions(vec![
@@ -215,7 +379,11 @@ async fn sync(
                 ),
             ])
             .preview(0.66, move |cx| StateItem::init(preview.clone(), false, cx))
-            .meta(cx.new_model(|_| timestamp).unwrap().into_any())
+            .meta(
+                cx.new_model(|_| (unread.notification_count, timestamp))
+                    .unwrap()
+                    .into_any(),
+            )
             .build();
 
             items.push(item);
@@ -242,6 +410,7 @@ impl RootCommandBuilder for MatrixCommandBuilder {
                 cx.spawn(move |view, cx| async { sync(session.contents, view, cx).await })
                     .detach();
             }
+            NotificationRouter::register(NOTIFICATION_PREFIX, open_clicked, cx);
 
             AsyncListItems::new()
         });
@@ -13,5 +13,8 @@ mod account;
 mod chat;
 mod client;
 mod compose;
+mod emoji;
+mod formatted;
 pub mod list;
 mod mxc;
+pub mod search;
@@ -0,0 +1,299 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{cell::Cell, collections::HashMap, rc::Rc, time::Duration};
+
+use bonsaidb::core::schema::SerializedCollection;
+use gpui::*;
+use jiff::Timestamp;
+use log::debug;
+use matrix_sdk::{
+    ruma::{
+        api::client::search::search_events::v3::{Categories, Criteria, OrderBy, Request},
+        events::{AnyMessageLikeEvent, AnyTimelineEvent},
+    },
+    Client,
+};
+use matrix_sdk_ui::timeline::{TimelineDetails, TimelineItemContent};
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{Accessory, AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    date::format_date,
+    loader::Loader,
+    query::TextEvent,
+    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+};
+
+use super::{
+    chat::ChatRoom,
+    client::{db, Session},
+    list::ROOMS,
+};
+
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(300);
+const RESULT_LIMIT: usize = 50;
+
+struct Hit {
+    room: ChatRoom,
+    event_id: String,
+    sender: String,
+    body: String,
+    timestamp: u64,
+}
+
+impl Hit {
+    fn get_item(self, cx: &AppContext) -> Item {
+        let room = self.room.room.name().unwrap_or_default();
+        let date = Timestamp::from_second(self.timestamp as i64)
+            .map(|ts| format_date(ts, cx))
+            .unwrap_or_default();
+        let chat = ChatRoom {
+            focus: Some(self.event_id.clone()),
+            ..self.room
+        };
+        ItemBuilder::new(
+            self.event_id.clone(),
+            ListItem::new(
+                Some(Img::default().icon(Icon::MessageCircle)),
+                self.body.lines().next().unwrap_or_default(),
+                Some(format!("{} in {}", self.sender, room)),
+                vec![Accessory::new(date, None)],
+            ),
+        )
+        .actions(vec![Action::new(
+            Img::default().icon(Icon::MessageCircle),
+            "Open Message",
+            None,
+            move |_, cx| {
+                let chat = chat.clone();
+                StateModel::update(|this, cx| this.push(chat, cx), cx);
+            },
+            false,
+        )])
+        .build()
+    }
+}
+
+// Searches the server for unencrypted rooms, encrypted rooms can only be searched locally
+async fn search_server(client: &Client, text: &str) -> anyhow::Result<Vec<Hit>> {
+    let mut criteria = Criteria::new(text.to_string());
+    criteria.order_by = Some(OrderBy::Recent);
+    let mut categories = Categories::new();
+    categories.room_events = Some(criteria);
+    let response = client.send(Request::new(categories), None).await?;
+
+    let mut hits = vec![];
+    for result in response.search_categories.room_events.results {
+        let Some(Ok(AnyTimelineEvent::MessageLike(AnyMessageLikeEvent::RoomMessage(ev)))) =
+            result.result.map(|raw| raw.deserialize())
+        else {
+            continue;
+        };
+        let Some(ev) = ev.as_original() else {
+            continue;
+        };
+        let Some(room) = ROOMS.lock().get(&ev.room_id).cloned() else {
+            continue;
+        };
+        let sender = room
+            .room
+            .get_member_no_sync(&ev.sender)
+            .await
+            .ok()
+            .flatten()
+            .map(|member| member.name().to_string())
+            .unwrap_or(ev.sender.to_string());
+        hits.push(Hit {
+            room,
+            event_id: ev.event_id.to_string(),
+            sender,
+            body: ev.content.body().to_string(),
+            timestamp: ev.origin_server_ts.as_secs().into(),
+        });
+    }
+    Ok(hits)
+}
+
+// Falls back to the events that have already been synced into the room timeline
+async fn search_local(room: &ChatRoom, text: &str) -> Vec<Hit> {
+    let text = text.to_lowercase();
+    room.timeline
+        .items()
+        .await
+        .iter()
+        .filter_map(|item| {
+            let ev = item.as_event()?;
+            let TimelineItemContent::Message(m) = ev.content() else {
+                return None;
+            };
+            if !m.body().to_lowercase().contains(&text) {
+                return None;
+            }
+            let sender = match ev.sender_profile() {
+                TimelineDetails::Ready(profile) => profile.display_name.clone(),
+                _ => None,
+            };
+            Some(Hit {
+                room: room.clone(),
+                event_id: ev.event_id()?.to_string(),
+                sender: sender.unwrap_or(ev.sender().to_string()),
+                body: m.body().to_string(),
+                timestamp: ev.timestamp().as_secs().into(),
+            })
+        })
+        .collect()
+}
+
+async fn search(text: &str) -> Vec<Hit> {
+    let mut accounts = HashMap::<String, (Client, Vec<ChatRoom>)>::new();
+    for room in ROOMS.lock().values() {
+        let client = room.room.client();
+        let id = client
+            .user_id()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        accounts
+            .entry(id)
+            .or_insert_with(|| (client, vec![]))
+            .1
+            .push(room.clone());
+    }
+
+    let mut hits = vec![];
+    for (client, rooms) in accounts.values() {
+        let server = match search_server(client, text).await {
+            Ok(server) => Some(server),
+            Err(err) => {
+                debug!("Server side search failed: {:?}", err);
+                None
+            }
+        };
+        for room in rooms {
+            let encrypted = room.room.is_encrypted().await.unwrap_or(true);
+            if server.is_none() || encrypted {
+                hits.append(&mut search_local(room, text).await);
+            }
+        }
+        if let Some(mut server) = server {
+            hits.append(&mut server);
+        }
+    }
+
+    // Newest first, a message may be found both on the server and locally
+    hits.sort_unstable_by_key(|hit| std::cmp::Reverse(hit.timestamp));
+    let mut seen = vec![];
+    hits.retain(|hit| {
+        if seen.contains(&hit.event_id) {
+            return false;
+        }
+        seen.push(hit.event_id.clone());
+        true
+    });
+    hits.truncate(RESULT_LIMIT);
+    hits
+}
+
+#[derive(Clone)]
+struct MessageSearch;
+
+command!(MessageSearch);
+impl StateViewBuilder for MessageSearch {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Search messages in all rooms...", cx);
+
+        let view = cx.new_view(|_| AsyncListItems::new());
+        AsyncListItems::loader(&view, &context.actions, cx);
+        view.update(cx, |view, cx| {
+            view.update("results".to_string(), vec![], cx)
+        });
+
+        if let Some(query) = context.query.view.upgrade() {
+            let view = view.downgrade();
+            // Only the most recent input gets to search
+            let pending = Rc::new(Cell::new(0u64));
+            cx.subscribe(&query, move |_, event: &TextEvent, cx| {
+                let TextEvent::Input { text } = event else {
+                    return;
+                };
+                let text = text.trim().to_string();
+                pending.set(pending.get() + 1);
+                let current = pending.get();
+                let pending = pending.clone();
+                let view = view.clone();
+                cx.spawn(|mut cx| async move {
+                    cx.background_executor().timer(SEARCH_DEBOUNCE).await;
+                    if pending.get() != current {
+                        return;
+                    }
+                    let mut loader = Loader::add();
+                    let hits = if text.is_empty() {
+                        vec![]
+                    } else {
+                        search(&text).await
+                    };
+                    loader.remove();
+                    if pending.get() != current {
+                        return;
+                    }
+                    let _ = view.update(&mut cx, |view, cx| {
+                        let items = hits.into_iter().map(|hit| hit.get_item(cx)).collect();
+                        view.update("results".to_string(), items, cx);
+                    });
+                })
+                .detach();
+            })
+            .detach();
+        }
+
+        ListBuilder::new()
+            // The results already match the query, so they are shown as the search returns them
+            .filter(|this, _| this.items_all.clone())
+            .build(
+                move |_, _, cx| {
+                    Ok(Some(
+                        view.read(cx).items.values().flatten().cloned().collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct MatrixSearchCommandBuilder;
+command!(MatrixSearchCommandBuilder);
+impl RootCommandBuilder for MatrixSearchCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "matrix_search",
+            "Search Messages",
+            "Matrix",
+            Icon::MessageSquareText,
+            vec!["Chat", "Messages"],
+            None,
+            |actions, cx| {
+                if Session::all(db()).count().unwrap_or_default() == 0 {
+                    actions.toast.error("No Matrix account has been added", cx);
+                    return;
+                }
+                StateModel::update(|this, cx| this.push(MessageSearch, cx), cx);
+            },
+        )
+    }
+}
@@ -23,6 +23,7 @@ use crate::{
         list::{Accessory, ItemBuilder, ListBuilder, ListItem},
         shared::{Icon, Img},
     },
+    platform::require_accessibility,
     state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
 };
 
@@ -62,7 +63,7 @@ impl StateViewBuilder for MenuListBuilder {
                                 .map(|item| {
                                     let mut path = item.path.clone();
                                     let name = path.pop().unwrap();
-                                    let subtitle = path.join(" -> ");
+                                    let subtitle = path.join(" → ");
                                     let actions = if let Some(indices) = item.path_indices {
                                         let indices = indices.clone();
                                         vec![Action::new(
@@ -95,7 +96,11 @@ impl StateViewBuilder for MenuListBuilder {
                                             accessories,
                                         )
                                     })
-                                    .keywords(vec![name.clone(), subtitle.clone()])
+                                    .keywords(vec![
+                                        name.clone(),
+                                        subtitle.clone(),
+                                        item.path.join(" "),
+                                    ])
                                     .actions(actions)
                                     .build()
                                 })
@@ -128,7 +133,10 @@ impl RootCommandBuilder for MenuCommandBuilder {
             Icon::Library,
             vec!["MacOS", "Apple"],
             None,
-            |_, cx| {
+            |actions, cx| {
+                if !require_accessibility("read menu items", &mut actions.toast, cx) {
+                    return;
+                }
                 StateModel::update(|this, cx| this.push(MenuListBuilder, cx), cx);
             },
         )
@@ -9,7 +9,11 @@
  *
  */
 
-use std::{collections::HashMap, rc::Rc};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use gpui::*;
 use log::error;
@@ -19,9 +23,10 @@ use crate::{
     command,
     components::{
         form::{Form, Input, InputKind},
-        list::{Accessory, Item, ItemBuilder, ListItem},
+        list::{frecency::Frecency, item_id, Accessory, Item, ItemBuilder, ListItem},
         shared::{Icon, Img},
     },
+    db::db,
     hotkey::HotkeyManager,
     state::{
         Action, ActionFn, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext,
@@ -32,17 +37,34 @@ use self::root::list;
 
 #[cfg(feature = "bitwarden")]
 mod bitwarden;
+mod calculator;
 #[cfg(feature = "clipboard")]
 mod clipboard;
+mod dictionary;
+mod display;
+#[cfg(feature = "emoji")]
+mod emoji;
+mod encode;
+mod files;
+mod generate;
+mod hidden;
 #[cfg(feature = "matrix")]
 mod matrix;
 #[cfg(target_os = "macos")]
 mod menu;
 mod process;
+#[cfg(feature = "quicklinks")]
+mod quicklinks;
 pub mod root;
+#[cfg(feature = "snippets")]
+mod snippets;
+mod system;
 #[cfg(feature = "tailscale")]
 mod tailscale;
 mod theme;
+mod timer;
+#[cfg(target_os = "macos")]
+mod window_manager;
 
 fn def() -> Rc<dyn ActionFn> {
     Rc::new(|_, _| {})
@@ -51,14 +73,17 @@ fn def() -> Rc<dyn ActionFn> {
 #[derive(Clone, Serialize, Deserialize)]
 pub struct RootCommand {
     pub id: String,
-    title: String,
-    subtitle: String,
-    icon: Icon,
+    pub title: String,
+    pub subtitle: String,
+    pub icon: Icon,
     keywords: Vec<String>,
     #[serde(skip)]
-    shortcut: Option<Shortcut>,
+    pub shortcut: Option<Shortcut>,
     #[serde(skip, default = "def")]
     pub action: Rc<dyn ActionFn>,
+    // Whether the command may be reopened on launch
+    #[serde(skip)]
+    pub restore: bool,
 }
 impl RootCommand {
     pub fn new(
@@ -78,8 +103,13 @@ impl RootCommand {
             keywords: keywords.into_iter().map(|s| s.to_string()).collect(),
             shortcut,
             action: Rc::new(action),
+            restore: true,
         }
     }
+    pub fn no_restore(mut self) -> Self {
+        self.restore = false;
+        self
+    }
 }
 
 pub trait RootCommandBuilder: CommandTrait {
@@ -91,6 +121,119 @@ pub struct RootCommands {
     pub commands: HashMap<String, RootCommand>,
 }
 
+// User defined aliases per command id
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct CommandAliases {
+    aliases: HashMap<String, Vec<String>>,
+}
+
+impl CommandAliases {
+    pub fn get() -> Self {
+        db().get::<Self>("aliases").unwrap_or_default()
+    }
+    pub fn set(id: &str, aliases: Vec<String>) -> anyhow::Result<()> {
+        let mut all = Self::get();
+        if aliases.is_empty() {
+            all.aliases.remove(id);
+        } else {
+            all.aliases.insert(id.to_string(), aliases);
+        }
+        db().set::<Self>("aliases", &all)?;
+        Ok(())
+    }
+    pub fn of(&self, id: &str) -> Vec<String> {
+        self.aliases.get(id).cloned().unwrap_or_default()
+    }
+}
+
+// Commands left out of the root list, they still run via hotkey or the CLI
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct HiddenCommands {
+    ids: HashSet<String>,
+}
+
+impl HiddenCommands {
+    pub fn get() -> Self {
+        db().get::<Self>("commands.hidden").unwrap_or_default()
+    }
+    pub fn contains(&self, id: &str) -> bool {
+        self.ids.contains(id)
+    }
+    pub fn toggle(id: &str) -> anyhow::Result<()> {
+        let mut hidden = Self::get();
+        if !hidden.ids.remove(id) {
+            hidden.ids.insert(id.to_string());
+        }
+        db().set::<Self>("commands.hidden", &hidden)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandOrder {
+    #[default]
+    Alphabetical,
+    // Most frequently and recently launched first
+    Frecency,
+}
+
+impl CommandOrder {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "frecency" => Self::Frecency,
+            _ => Self::Alphabetical,
+        }
+    }
+}
+
+impl From<CommandOrder> for String {
+    fn from(val: CommandOrder) -> Self {
+        match val {
+            CommandOrder::Alphabetical => "alphabetical",
+            CommandOrder::Frecency => "frecency",
+        }
+        .to_string()
+    }
+}
+
+// How the root list orders commands, pinned ones come first in the order they were pinned
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandOrderSettings {
+    pub order: CommandOrder,
+    pinned: Vec<String>,
+}
+
+impl CommandOrderSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("commands.order").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("commands.order", self)?;
+        Ok(())
+    }
+    pub fn is_pinned(&self, id: &str) -> bool {
+        self.pinned.iter().any(|pinned| pinned.eq(id))
+    }
+    pub fn toggle_pin(id: &str) -> anyhow::Result<()> {
+        let mut settings = Self::get();
+        if settings.is_pinned(id) {
+            settings.pinned.retain(|pinned| pinned.ne(id));
+        } else {
+            settings.pinned.push(id.to_string());
+        }
+        settings.set()
+    }
+    // Position among the pinned commands by list item id, unpinned items sort last
+    pub fn rank(&self, item: u64) -> usize {
+        self.pinned
+            .iter()
+            .position(|id| item_id(id) == item)
+            .unwrap_or(usize::MAX)
+    }
+}
+
 impl RootCommands {
     pub fn init(cx: &mut WindowContext) {
         let commands: Vec<Box<dyn RootCommandBuilder>> = vec![
@@ -98,15 +241,48 @@ impl RootCommands {
             #[cfg(target_os = "macos")]
             Box::new(menu::list::MenuCommandBuilder),
             Box::new(process::list::ProcessCommandBuilder),
+            Box::new(calculator::list::CalculatorCommandBuilder),
+            Box::new(encode::list::EncodeCommandBuilder),
+            Box::new(dictionary::list::DictionaryCommandBuilder),
             Box::new(theme::list::ThemeCommandBuilder),
+            Box::new(display::list::DisplayCommandBuilder),
+            Box::new(hidden::list::HiddenCommandBuilder),
             #[cfg(feature = "tailscale")]
             Box::new(tailscale::list::TailscaleCommandBuilder),
             #[cfg(feature = "bitwarden")]
             Box::new(bitwarden::list::BitwardenCommandBuilder),
             #[cfg(feature = "matrix")]
             Box::new(matrix::list::MatrixCommandBuilder),
+            #[cfg(feature = "matrix")]
+            Box::new(matrix::search::MatrixSearchCommandBuilder),
             #[cfg(feature = "clipboard")]
             Box::new(clipboard::list::ClipboardCommandBuilder),
+            #[cfg(feature = "emoji")]
+            Box::new(emoji::list::EmojiCommandBuilder),
+            #[cfg(feature = "snippets")]
+            Box::new(snippets::list::SnippetsCommandBuilder),
+            #[cfg(feature = "quicklinks")]
+            Box::new(quicklinks::list::QuicklinksCommandBuilder),
+            Box::new(files::list::FilesCommandBuilder),
+            Box::new(generate::list::GenerateCommandBuilder),
+            Box::new(system::list::SystemCommandBuilder),
+            Box::new(timer::list::TimerCommandBuilder),
+            #[cfg(target_os = "macos")]
+            Box::new(window_manager::list::WindowCommandBuilder {
+                position: window_manager::list::WindowPosition::LeftHalf,
+            }),
+            #[cfg(target_os = "macos")]
+            Box::new(window_manager::list::WindowCommandBuilder {
+                position: window_manager::list::WindowPosition::RightHalf,
+            }),
+            #[cfg(target_os = "macos")]
+            Box::new(window_manager::list::WindowCommandBuilder {
+                position: window_manager::list::WindowPosition::Maximize,
+            }),
+            #[cfg(target_os = "macos")]
+            Box::new(window_manager::list::WindowCommandBuilder {
+                position: window_manager::list::WindowPosition::Center,
+            }),
         ];
         let mut map = HashMap::new();
         for command in commands {
@@ -117,28 +293,47 @@ impl RootCommands {
         }
         cx.set_global(Self { commands: map });
     }
+    // Aliases, pins and hidden commands are baked into the items, so the root list rebuilds them on change
+    pub fn refresh(cx: &mut WindowContext) {
+        let state = cx.global::<StateModel>().inner.read(cx);
+        if let Some(root) = state.stack.first() {
+            root.actions.read(cx).update();
+        }
+    }
     pub fn list(cx: &mut WindowContext) -> Vec<Item> {
-        let commands = cx.global::<Self>().commands.clone();
-        let items: Vec<Item> = commands
+        let aliases = CommandAliases::get();
+        // The list of hidden commands can't be hidden itself, otherwise there'd be no way back
+        let manager = hidden::list::HiddenCommandBuilder.command();
+        let hidden = HiddenCommands::get();
+        let order = CommandOrderSettings::get();
+        let frecency = match order.order {
+            CommandOrder::Frecency => Frecency::scores(cx),
+            CommandOrder::Alphabetical => Rc::default(),
+        };
+        let mut commands: Vec<RootCommand> = cx
+            .global::<Self>()
+            .commands
             .values()
+            .filter(|command| !hidden.contains(&command.id))
+            .cloned()
+            .collect();
+        // The commands live in a map, so they need a stable order for an empty query
+        commands.sort_by_cached_key(|command| {
+            let id = item_id(&command.id);
+            (
+                order.rank(id),
+                Reverse(frecency.get(&id).map(|score| (score * 1000.0) as u64)),
+                command.title.to_lowercase(),
+            )
+        });
+        let items: Vec<Item> = commands
+            .iter()
             .map(|command| {
+                let aliases = aliases.of(&command.id);
                 let mut keywords = vec![command.title.clone(), command.subtitle.clone()];
                 keywords.append(&mut command.keywords.clone());
-                ItemBuilder::new(
-                    command.id.clone(),
-                    ListItem::new(
-                        Some(Img::default().icon(command.icon.clone())),
-                        command.title.clone(),
-                        Some(command.subtitle.clone()),
-                        command
-                            .shortcut
-                            .clone()
-                            .map(|shortcut| vec![Accessory::shortcut(shortcut)])
-                            .unwrap_or(vec![Accessory::new("Command", None)]),
-                    ),
-                )
-                .keywords(keywords)
-                .actions(vec![
+                keywords.append(&mut aliases.clone());
+                let mut actions = vec![
                     Action::new_rc(
                         Img::default().icon(command.icon.clone()),
                         command.title.clone(),
@@ -162,7 +357,75 @@ impl RootCommands {
                         },
                         false,
                     ),
-                ])
+                    Action::new(
+                        Img::default().icon(Icon::Tags),
+                        "Edit Aliases",
+                        None,
+                        {
+                            let id = command.id.clone();
+                            move |_, cx| {
+                                let id = id.clone();
+                                StateModel::update(
+                                    |this, cx| this.push(AliasesBuilder { id }, cx),
+                                    cx,
+                                );
+                            }
+                        },
+                        false,
+                    ),
+                ];
+                let pinned = order.is_pinned(&command.id);
+                actions.push(Action::new(
+                    Img::default().icon(if pinned { Icon::PinOff } else { Icon::Pin }),
+                    if pinned { "Unpin" } else { "Pin to Top" },
+                    None,
+                    {
+                        let id = command.id.clone();
+                        move |this, cx| {
+                            if CommandOrderSettings::toggle_pin(&id).is_err() {
+                                this.toast.error("Failed to pin command", cx);
+                            } else {
+                                this.update();
+                            }
+                        }
+                    },
+                    false,
+                ));
+                if command.id.ne(&manager) {
+                    actions.push(Action::new(
+                        Img::default().icon(Icon::EyeOff),
+                        "Hide from Root List",
+                        None,
+                        {
+                            let id = command.id.clone();
+                            move |this, cx| {
+                                if HiddenCommands::toggle(&id).is_err() {
+                                    this.toast.error("Failed to hide command", cx);
+                                } else {
+                                    this.toast.success("Command hidden", cx);
+                                    this.update();
+                                }
+                            }
+                        },
+                        false,
+                    ));
+                }
+                ItemBuilder::new(
+                    command.id.clone(),
+                    ListItem::new(
+                        Some(Img::default().icon(command.icon.clone())),
+                        command.title.clone(),
+                        Some(command.subtitle.clone()),
+                        command
+                            .shortcut
+                            .clone()
+                            .map(|shortcut| vec![Accessory::shortcut(shortcut)])
+                            .unwrap_or(vec![Accessory::new("Command", None)]),
+                    ),
+                )
+                .keywords(keywords)
+                .aliases(aliases)
+                .actions(actions)
                 .weight(3)
                 .build()
             })
@@ -195,7 +458,28 @@ impl StateViewBuilder for HotkeyBuilder {
             move |values, actions, cx| {
                 let shortcut = values["hotkey"].value::<Option<Shortcut>>();
                 if let Some(shortcut) = shortcut {
-                    if let Err(err) = HotkeyManager::set(&id, shortcut.get(), cx) {
+                    let keystroke = shortcut.get();
+                    if let Some(other) =
+                        HotkeyManager::lookup(&keystroke, cx).filter(|other| other.ne(&id))
+                    {
+                        let title = cx
+                            .global::<RootCommands>()
+                            .commands
+                            .get(&other)
+                            .map(|command| command.title.clone())
+                            .unwrap_or(other);
+                        actions
+                            .toast
+                            .error(format!("Hotkey is already used by {}", title), cx);
+                        return;
+                    }
+                    if let Some(label) = actions.shortcut_label(&keystroke, cx) {
+                        actions
+                            .toast
+                            .error(format!("Hotkey conflicts with the {} action", label), cx);
+                        return;
+                    }
+                    if let Err(err) = HotkeyManager::set(&id, keystroke, cx) {
                         error!("Failed to set hotkey: {}", err);
                         actions.toast.error("Failed to set hotkey", cx);
                     } else {
@@ -221,3 +505,46 @@ impl StateViewBuilder for HotkeyBuilder {
         .into()
     }
 }
+
+#[derive(Clone)]
+pub struct AliasesBuilder {
+    id: String,
+}
+command!(AliasesBuilder);
+impl StateViewBuilder for AliasesBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let id = self.id.clone();
+        Form::new(
+            vec![Input::new(
+                "aliases",
+                "Aliases",
+                InputKind::TextField {
+                    placeholder: "Comma separated aliases...".to_string(),
+                    value: CommandAliases::get().of(&id).join(", "),
+                    validate: None,
+                    password: false,
+                },
+                cx,
+            )],
+            move |values, actions, cx| {
+                let aliases = values["aliases"]
+                    .value::<String>()
+                    .split(',')
+                    .map(|alias| alias.trim().to_string())
+                    .filter(|alias| !alias.is_empty())
+                    .collect();
+                if let Err(err) = CommandAliases::set(&id, aliases) {
+                    error!("Failed to save aliases: {}", err);
+                    actions.toast.error("Failed to save aliases", cx);
+                    return;
+                }
+                actions.toast.success("Aliases saved", cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+                RootCommands::refresh(cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
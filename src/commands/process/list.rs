@@ -11,7 +11,12 @@
 
 use gpui::*;
 use std::{
-    cmp::Reverse, collections::HashMap, fs, path::PathBuf, process::Command, time::Duration,
+    cmp::Reverse,
+    collections::{HashMap, VecDeque},
+    fs,
+    path::PathBuf,
+    process::Command,
+    time::Duration,
 };
 
 use regex::Regex;
@@ -25,7 +30,9 @@ use crate::{
     },
     paths::paths,
     platform::{get_application_data, AppData},
-    state::{Action, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+    state::{
+        Action, CommandTrait, Shortcut, StateItem, StateModel, StateViewBuilder, StateViewContext,
+    },
     theme::Theme,
 };
 
@@ -35,13 +42,14 @@ struct Process {
     ppid: u64,
     cpu: f32,
     mem: u64,
+    user: String,
     name: String,
 }
 
 impl Process {
     fn parse(line: &str) -> anyhow::Result<Self> {
         let split: Vec<&str> = line.split_whitespace().collect();
-        if split.len() < 5 {
+        if split.len() < 6 {
             return Err(anyhow::anyhow!("invalid line"));
         }
 
@@ -50,11 +58,46 @@ impl Process {
             ppid: split[1].parse()?,
             cpu: split[2].parse()?,
             mem: split[3].parse()?,
-            name: split[4].to_string(),
+            user: split[4].to_string(),
+            name: split[5..].join(" "),
         })
     }
 }
 
+fn processes() -> Vec<Process> {
+    let Ok(ps) = Command::new("ps")
+        .arg("-eo")
+        .arg("pid,ppid,pcpu,rss,user,comm")
+        .output()
+    else {
+        return vec![];
+    };
+    String::from_utf8_lossy(&ps.stdout)
+        .split('\n')
+        .skip(1)
+        .filter_map(|line| Process::parse(line).ok())
+        .collect()
+}
+
+// The process and all of its descendants in depth first order, along with their depth
+fn tree(processes: &[Process], pid: u64) -> Vec<(usize, Process)> {
+    let mut tree = vec![];
+    let mut stack: Vec<(usize, u64)> = vec![(0, pid)];
+    while let Some((depth, pid)) = stack.pop() {
+        if let Some(p) = processes.iter().find(|p| p.pid == pid) {
+            tree.push((depth, p.clone()));
+        }
+        stack.extend(
+            processes
+                .iter()
+                .filter(|p| p.ppid == pid && p.pid != pid)
+                .rev()
+                .map(|p| (depth + 1, p.pid)),
+        );
+    }
+    tree
+}
+
 fn format_bytes(bytes: u64) -> String {
     let kb = bytes / 1000;
     let mb = kb / 1000;
@@ -70,6 +113,193 @@ fn format_bytes(bytes: u64) -> String {
     }
 }
 
+#[derive(Clone, Copy)]
+enum Signal {
+    Term,
+    Kill,
+    Int,
+}
+
+impl Signal {
+    fn send(&self, pids: Vec<String>) -> bool {
+        let flag = match self {
+            Signal::Term => "-TERM",
+            Signal::Kill => "-KILL",
+            Signal::Int => "-INT",
+        };
+        Command::new("kill")
+            .arg(flag)
+            .args(pids)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+    // Terminate lets the process shut down cleanly, killing it is the escalation
+    fn action(self, pid: u64) -> Action {
+        let (icon, label, shortcut, done) = match self {
+            Signal::Term => (Icon::StopCircle, "Terminate Process", None, "Terminated"),
+            Signal::Kill => (
+                Icon::Skull,
+                "Kill Process",
+                Some(Shortcut::new("enter").cmd()),
+                "Killed",
+            ),
+            Signal::Int => (Icon::Hand, "Interrupt Process", None, "Interrupted"),
+        };
+        let action = Action::new(
+            Img::default().icon(icon),
+            label,
+            shortcut,
+            move |this, cx| {
+                if self.send(vec![pid.to_string()]) {
+                    this.toast.success(format!("{} process", done), cx);
+                } else {
+                    this.toast.error("Failed to signal process", cx);
+                }
+                this.update();
+            },
+            false,
+        )
+        .bulk(move |items, this, cx| {
+            let pids: Vec<String> = items
+                .iter()
+                .filter_map(|item| item.get_meta::<u64>(cx))
+                .map(|pid| pid.to_string())
+                .collect();
+            if self.send(pids) {
+                this.toast.success(format!("{} processes", done), cx);
+            } else {
+                this.toast.error("Failed to signal processes", cx);
+            }
+            this.update();
+        });
+        match self {
+            Signal::Kill => action.confirm(),
+            _ => action,
+        }
+    }
+    // Kills the process along with everything it spawned
+    fn tree_action(pid: u64) -> Action {
+        Action::new(
+            Img::default().icon(Icon::Skull),
+            "Kill Process Tree",
+            Some(Shortcut::new("enter").cmd().shift()),
+            move |this, cx| {
+                let pids = tree(&processes(), pid)
+                    .into_iter()
+                    .map(|(_, p)| p.pid.to_string())
+                    .collect();
+                if Signal::Kill.send(pids) {
+                    this.toast.success("Killed process tree", cx);
+                } else {
+                    this.toast.error("Failed to kill process tree", cx);
+                }
+                this.update();
+            },
+            false,
+        )
+        .confirm()
+    }
+}
+
+// Number of refreshes kept per process for the preview graphs
+static HISTORY_SAMPLES: usize = 30;
+
+// Recent (cpu, mem) samples keyed by pid
+type ProcessHistory = Model<HashMap<u64, VecDeque<(f32, u64)>>>;
+
+fn sparkline(samples: Vec<f32>, color: Hsla) -> impl IntoElement {
+    canvas(
+        |_, _| {},
+        move |bounds, _, cx| {
+            if samples.len() < 2 {
+                return;
+            }
+            let max = samples.iter().cloned().fold(f32::EPSILON, f32::max);
+            let step = bounds.size.width / (HISTORY_SAMPLES - 1) as f32;
+            let offset = (HISTORY_SAMPLES - samples.len()) as f32;
+            let x = |i: usize| bounds.origin.x + step * (offset + i as f32);
+            let y = |value: f32| bounds.bottom() - bounds.size.height * (value / max);
+            let mut path = Path::new(point(x(0), bounds.bottom()));
+            for (i, value) in samples.iter().enumerate() {
+                path.line_to(point(x(i), y(*value)));
+            }
+            path.line_to(point(x(samples.len() - 1), bounds.bottom()));
+            cx.paint_path(path, color);
+        },
+    )
+    .size_full()
+}
+
+#[derive(Clone)]
+struct ProcessPreview {
+    pid: u64,
+    history: ProcessHistory,
+}
+command!(ProcessPreview);
+
+impl StateViewBuilder for ProcessPreview {
+    fn build(&self, _context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        cx.new_view(|cx| {
+            cx.observe(&self.history, |_, _, cx| cx.notify()).detach();
+            self.clone()
+        })
+        .into()
+    }
+}
+
+impl Render for ProcessPreview {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let theme = cx.global::<Theme>();
+        let samples = self
+            .history
+            .read(cx)
+            .get(&self.pid)
+            .cloned()
+            .unwrap_or_default();
+        let (cpu, mem) = samples.back().cloned().unwrap_or_default();
+        let graph = |label: String, samples: Vec<f32>, color: Hsla| {
+            let mut fill = color;
+            fill.fade_out(0.6);
+            div()
+                .flex()
+                .flex_col()
+                .flex_1()
+                .child(
+                    div()
+                        .text_xs()
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .text_color(theme.subtext0)
+                        .child(label),
+                )
+                .child(
+                    div()
+                        .flex_1()
+                        .mt_1()
+                        .rounded_md()
+                        .bg(theme.mantle)
+                        .overflow_hidden()
+                        .child(sparkline(samples, fill)),
+                )
+        };
+        div()
+            .size_full()
+            .p_2()
+            .flex()
+            .flex_col()
+            .gap_4()
+            .child(graph(
+                format!("CPU {:.2}%", cpu),
+                samples.iter().map(|(cpu, _)| *cpu).collect(),
+                theme.lavender,
+            ))
+            .child(graph(
+                format!("Memory {}", format_bytes(mem * 1024)),
+                samples.iter().map(|(_, mem)| *mem as f32).collect(),
+                theme.blue,
+            ))
+    }
+}
+
 #[derive(Clone)]
 pub struct ProcessListBuilder;
 command!(ProcessListBuilder);
@@ -84,30 +314,29 @@ impl StateViewBuilder for ProcessListBuilder {
             vec![("memory", "Sort by Memory"), ("cpu", "Sort by CPU")],
             cx,
         );
+        context.actions.set_filter(
+            "all",
+            vec![
+                ("all", "All Users"),
+                ("me", "My Processes"),
+                ("root", "Root"),
+            ],
+            cx,
+        );
 
+        let history: ProcessHistory = cx.new_model(|_| HashMap::new());
         ListBuilder::new()
             .interval(Duration::from_secs(5))
+            .multi_select()
             .build(
-                |this, _, cx| {
+                move |this, _, cx| {
                     let theme = cx.global::<Theme>().clone();
                     let cache_dir = paths().cache.join("apps");
                     if !cache_dir.exists() {
                         fs::create_dir_all(cache_dir.clone()).unwrap();
                     }
 
-                    let ps = Command::new("ps")
-                        .arg("-eo")
-                        .arg("pid,ppid,pcpu,rss,comm")
-                        .output()
-                        .expect("failed to get process list")
-                        .stdout;
-
-                    let parsed: Vec<Process> = String::from_utf8(ps)
-                        .unwrap()
-                        .split('\n')
-                        .skip(1)
-                        .filter_map(|line| Process::parse(line).ok())
-                        .collect();
+                    let parsed = processes();
 
                     let mut aggregated = HashMap::<u64, Process>::new();
                     parsed.iter().for_each(|p| {
@@ -121,6 +350,25 @@ impl StateViewBuilder for ProcessListBuilder {
                         }
                     });
                     let mut parsed = aggregated.values().cloned().collect::<Vec<Process>>();
+                    match this.actions.get_filter_value(cx).as_str() {
+                        "me" => {
+                            let user = whoami::username();
+                            parsed.retain(|p| p.user.eq(&user));
+                        }
+                        "root" => parsed.retain(|p| p.user.eq("root")),
+                        _ => {}
+                    }
+                    history.update(cx, |history, cx| {
+                        history.retain(|pid, _| aggregated.contains_key(pid));
+                        for p in parsed.iter() {
+                            let samples = history.entry(p.pid).or_default();
+                            if samples.len() == HISTORY_SAMPLES {
+                                samples.pop_front();
+                            }
+                            samples.push_back((p.cpu, p.mem));
+                        }
+                        cx.notify();
+                    });
 
                     let sort_by_cpu = "cpu".to_string().eq(&this.actions.get_dropdown_value(cx));
                     if sort_by_cpu {
@@ -130,6 +378,8 @@ impl StateViewBuilder for ProcessListBuilder {
                     }
 
                     let re = Regex::new(r"(.+\.(?:prefPane|app))(?:/.*)?$").unwrap();
+                    // Group applications above everything else, keeping the chosen sort order
+                    parsed.sort_by_key(|p| !re.is_match(p.name.as_str()));
                     Ok(Some(
                         parsed
                             .iter()
@@ -140,6 +390,11 @@ impl StateViewBuilder for ProcessListBuilder {
                                     .map(|m| String::from(m.as_str()))
                                     .unwrap_or_default();
 
+                                let section = if path.is_empty() {
+                                    "Background Processes"
+                                } else {
+                                    "Applications"
+                                };
                                 let data =
                                     get_application_data(&PathBuf::from(path)).unwrap_or(AppData {
                                         id: "".to_string(),
@@ -184,28 +439,116 @@ impl StateViewBuilder for ProcessListBuilder {
                                     )
                                 })
                                 .keywords(vec![data.name.clone()])
-                                .actions(vec![Action::new(
-                                    Img::default().icon(Icon::Skull),
-                                    "Kill Process",
-                                    None,
-                                    {
-                                        let pid = p.pid.to_string();
-                                        move |this, cx| {
-                                            if Command::new("kill")
-                                                .arg("-9")
-                                                .arg(pid.clone())
-                                                .output()
-                                                .is_err()
-                                            {
-                                                this.toast.error("Failed to kill process", cx);
-                                            } else {
-                                                this.toast.success("Killed process", cx);
+                                .section(Some(section))
+                                .meta(cx.new_model(|_| p.pid).into_any())
+                                .preview(0.4, {
+                                    let preview = ProcessPreview {
+                                        pid: p.pid,
+                                        history: history.clone(),
+                                    };
+                                    move |cx| StateItem::init(preview.clone(), false, cx)
+                                })
+                                .actions(vec![
+                                    Signal::Term.action(p.pid),
+                                    Signal::Kill.action(p.pid),
+                                    Signal::Int.action(p.pid),
+                                    Signal::tree_action(p.pid),
+                                    Action::new(
+                                        Img::default().icon(Icon::ListTree),
+                                        "Show Process Tree",
+                                        Some(Shortcut::new("t").cmd()),
+                                        {
+                                            let pid = p.pid;
+                                            move |_, cx| {
+                                                StateModel::update(
+                                                    |this, cx| {
+                                                        this.push(ProcessTreeBuilder { pid }, cx)
+                                                    },
+                                                    cx,
+                                                );
                                             }
-                                            this.update();
-                                        }
-                                    },
-                                    false,
-                                )])
+                                        },
+                                        false,
+                                    ),
+                                ])
+                                .build()
+                            })
+                            .collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+#[derive(Clone)]
+pub struct ProcessTreeBuilder {
+    pid: u64,
+}
+command!(ProcessTreeBuilder);
+
+impl StateViewBuilder for ProcessTreeBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Search child processes...", cx);
+        let pid = self.pid;
+        ListBuilder::new()
+            .interval(Duration::from_secs(5))
+            .build(
+                move |_, _, cx| {
+                    let theme = cx.global::<Theme>().clone();
+                    Ok(Some(
+                        tree(&processes(), pid)
+                            .into_iter()
+                            .map(|(depth, p)| {
+                                let name = p.name.split('/').last().unwrap_or_default().to_string();
+                                ItemBuilder::new(
+                                    p.pid,
+                                    ListItem::new(
+                                        Some(
+                                            Img::default()
+                                                .icon(if depth == 0 {
+                                                    Icon::Cpu
+                                                } else {
+                                                    Icon::CornerDownRight
+                                                })
+                                                .icon_color(theme.subtext0),
+                                        ),
+                                        format!("{}{}", "    ".repeat(depth), name),
+                                        None,
+                                        vec![
+                                            Accessory::new(format!("PID {}", p.pid), None),
+                                            Accessory::new(
+                                                format!("{: >8}", format_bytes(p.mem * 1024)),
+                                                Some(
+                                                    Img::default()
+                                                        .icon(Icon::MemoryStick)
+                                                        .mask(ImgMask::None)
+                                                        .size(ImgSize::SM),
+                                                ),
+                                            ),
+                                            Accessory::new(
+                                                format!("{: >6.2}%", p.cpu),
+                                                Some(
+                                                    Img::default()
+                                                        .icon(Icon::Cpu)
+                                                        .mask(ImgMask::None)
+                                                        .size(ImgSize::SM),
+                                                ),
+                                            ),
+                                        ],
+                                    ),
+                                )
+                                .keywords(vec![name, p.pid.to_string()])
+                                .actions(vec![
+                                    Signal::Term.action(p.pid),
+                                    Signal::Kill.action(p.pid),
+                                    Signal::Int.action(p.pid),
+                                    Signal::tree_action(p.pid),
+                                ])
                                 .build()
                             })
                             .collect(),
@@ -0,0 +1,439 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{collections::HashMap, sync::OnceLock};
+
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
+use gpui::*;
+use log::error;
+use serde::{Deserialize, Serialize};
+use url::form_urlencoded::byte_serialize;
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        form::{Form, Input, InputKind},
+        list::{
+            frecency::{fuzzy_match_frecency, DEFAULT_FRECENCY_WEIGHT},
+            AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem,
+        },
+        shared::{Icon, Img},
+    },
+    db::{Db, Migrate},
+    state::{
+        Action, Actions, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext,
+    },
+};
+
+static QUICKLINKS_KEY: &str = "quicklinks";
+
+#[derive(Clone, Serialize, Deserialize, Collection)]
+#[collection(name = "quicklinks.item")]
+struct Quicklink {
+    #[natural_id]
+    id: u64,
+    name: String,
+    // URL with {placeholder}s, e.g. https://github.com/search?q={query}
+    url: String,
+}
+
+impl Migrate for Quicklink {}
+
+impl Quicklink {
+    // Placeholder names in the order they first appear
+    fn placeholders(&self) -> Vec<String> {
+        let mut placeholders: Vec<String> = vec![];
+        let mut rest = self.url.as_str();
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                break;
+            };
+            let name = rest[start + 1..start + end].to_string();
+            if !name.is_empty() && !placeholders.contains(&name) {
+                placeholders.push(name);
+            }
+            rest = &rest[start + end + 1..];
+        }
+        placeholders
+    }
+    // Substitutes the URL encoded values, placeholders without a value are left empty
+    fn expand(&self, values: &HashMap<String, String>) -> String {
+        self.placeholders()
+            .iter()
+            .fold(self.url.clone(), |url, name| {
+                // Spaces become %20 rather than +, so values also work in paths
+                let value = values
+                    .get(name)
+                    .map(|value| byte_serialize(value.as_bytes()).collect::<String>())
+                    .unwrap_or_default()
+                    .replace('+', "%20");
+                url.replace(&format!("{{{}}}", name), &value)
+            })
+    }
+    fn open(
+        &self,
+        values: &HashMap<String, String>,
+        actions: &mut Actions,
+        cx: &mut WindowContext,
+    ) {
+        cx.open_url(&self.expand(values));
+        actions.toast.floating(
+            format!("Opened {}", self.name),
+            Some(Icon::ArrowUpRightFromSquare),
+            cx,
+        );
+    }
+    // Text typed after the name fills the first placeholder, the remaining ones are asked for
+    fn get_item(
+        &self,
+        view: View<AsyncListItems>,
+        inline: Option<String>,
+        cx: &mut WindowContext,
+    ) -> Item {
+        ItemBuilder::new(
+            self.id,
+            ListItem::new(
+                Some(Img::default().favicon(self.expand(&HashMap::new()), Icon::Link, cx)),
+                self.name.clone(),
+                Some(self.url.clone()),
+                vec![],
+            ),
+        )
+        .keywords(vec![self.name.clone()])
+        .actions(vec![
+            Action::new(
+                Img::default().icon(Icon::ArrowUpRightFromSquare),
+                "Open",
+                None,
+                {
+                    let link = self.clone();
+                    move |actions, cx| {
+                        let mut values = HashMap::new();
+                        let placeholders = link.placeholders();
+                        if let (Some(first), Some(inline)) = (placeholders.first(), &inline) {
+                            values.insert(first.clone(), inline.clone());
+                        }
+                        if placeholders.len() > values.len() {
+                            let link = link.clone();
+                            StateModel::update(
+                                |this, cx| {
+                                    this.push(QuicklinkArgumentsBuilder { link, values }, cx)
+                                },
+                                cx,
+                            );
+                        } else {
+                            link.open(&values, actions, cx);
+                        }
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::Clipboard),
+                "Copy Link",
+                Some(Shortcut::new("c").cmd().shift()),
+                {
+                    let url = self.url.clone();
+                    move |actions, cx| {
+                        cx.write_to_clipboard(ClipboardItem::new_string(url.clone()));
+                        actions
+                            .toast
+                            .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::Pencil),
+                "Edit",
+                Some(Shortcut::new("e").cmd()),
+                {
+                    let link = self.clone();
+                    let view = view.clone();
+                    move |_, cx| {
+                        let link = Some(link.clone());
+                        let view = view.clone();
+                        StateModel::update(
+                            |this, cx| this.push(QuicklinkFormBuilder { view, link }, cx),
+                            cx,
+                        );
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::Trash),
+                "Delete",
+                Some(Shortcut::new("backspace").cmd()),
+                {
+                    let link = self.clone();
+                    move |actions, cx| {
+                        if let Err(err) = link.delete(&view, cx) {
+                            error!("Failed to delete quicklink: {:?}", err);
+                            actions.toast.error("Failed to delete quicklink", cx);
+                        } else {
+                            actions.toast.success("Successfully deleted quicklink", cx);
+                        }
+                    }
+                },
+                false,
+            ),
+        ])
+        .meta(cx.new_model(|_| self.clone()).into_any())
+        .build()
+    }
+    fn delete(&self, view: &View<AsyncListItems>, cx: &mut WindowContext) -> anyhow::Result<()> {
+        if let Some(link) = Self::get(&self.id, db())? {
+            link.delete(db())?;
+        }
+        view.update(cx, |view, cx| {
+            view.remove(QUICKLINKS_KEY.to_string(), self.id, cx);
+        });
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct QuicklinkArgumentsBuilder {
+    link: Quicklink,
+    values: HashMap<String, String>,
+}
+command!(QuicklinkArgumentsBuilder);
+impl StateViewBuilder for QuicklinkArgumentsBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let link = self.link.clone();
+        let inputs = link
+            .placeholders()
+            .into_iter()
+            .map(|name| {
+                Input::new(
+                    name.clone(),
+                    name.clone(),
+                    InputKind::TextField {
+                        placeholder: format!("Enter the {}...", name),
+                        value: self.values.get(&name).cloned().unwrap_or_default(),
+                        validate: None,
+                        password: false,
+                    },
+                    cx,
+                )
+            })
+            .collect();
+        Form::new(
+            inputs,
+            move |inputs, actions, cx| {
+                let values = link
+                    .placeholders()
+                    .into_iter()
+                    .map(|name| {
+                        let value = inputs[name.as_str()].value::<String>();
+                        (name, value)
+                    })
+                    .collect();
+                link.open(&values, actions, cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
+
+#[derive(Clone)]
+struct QuicklinkFormBuilder {
+    view: View<AsyncListItems>,
+    link: Option<Quicklink>,
+}
+command!(QuicklinkFormBuilder);
+impl StateViewBuilder for QuicklinkFormBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let view = self.view.clone();
+        let id = self.link.as_ref().map(|l| l.id);
+        let (name, url) = self
+            .link
+            .clone()
+            .map(|l| (l.name, l.url))
+            .unwrap_or_default();
+        Form::new(
+            vec![
+                Input::new(
+                    "name",
+                    "Name",
+                    InputKind::TextField {
+                        placeholder: "Enter a name...".to_string(),
+                        value: name,
+                        validate: Some(|v| v.is_empty().then_some("Name is required")),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "url",
+                    "URL",
+                    InputKind::TextField {
+                        placeholder: "https://github.com/search?q={query}".to_string(),
+                        value: url,
+                        validate: Some(|v| {
+                            url::Url::parse(v)
+                                .is_err()
+                                .then_some("Expected a URL, e.g. https://example.com/{query}")
+                        }),
+                        password: false,
+                    },
+                    cx,
+                ),
+            ],
+            move |values, actions, cx| {
+                let link = Quicklink {
+                    id: id.unwrap_or_else(rand::random),
+                    name: values["name"].value::<String>(),
+                    url: values["url"].value::<String>(),
+                };
+                if let Err(err) = link.clone().overwrite_into(&link.id, db()) {
+                    error!("Failed to save quicklink: {:?}", err);
+                    actions.toast.error("Failed to save quicklink", cx);
+                    return;
+                }
+                let item = link.get_item(view.clone(), None, cx);
+                view.update(cx, |view, cx| {
+                    view.push(QUICKLINKS_KEY.to_string(), item, cx);
+                });
+                actions.toast.success("Successfully saved quicklink", cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
+
+#[derive(Clone)]
+struct QuicklinkListBuilder {
+    view: View<AsyncListItems>,
+}
+command!(QuicklinkListBuilder);
+impl StateViewBuilder for QuicklinkListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder(
+            "Search quicklinks, text after a space fills the link...",
+            cx,
+        );
+        context.actions.update_global(
+            vec![Action::new(
+                Img::default().icon(Icon::PlusSquare),
+                "Create Quicklink",
+                Some(Shortcut::new("n").cmd()),
+                {
+                    let view = self.view.clone();
+                    move |_, cx| {
+                        let view = view.clone();
+                        StateModel::update(
+                            |this, cx| this.push(QuicklinkFormBuilder { view, link: None }, cx),
+                            cx,
+                        );
+                    }
+                },
+                false,
+            )],
+            cx,
+        );
+
+        AsyncListItems::loader(&self.view, &context.actions, cx);
+        let view = self.view.clone();
+        ListBuilder::new()
+            .frecency(DEFAULT_FRECENCY_WEIGHT)
+            .filter({
+                let view = view.clone();
+                move |this, cx| {
+                    let text = this.query.get_text(cx);
+                    let (name, inline) = match text.split_once(' ') {
+                        Some((name, inline)) if !inline.trim().is_empty() => {
+                            (name.to_string(), Some(inline.trim().to_string()))
+                        }
+                        _ => (text.trim().to_string(), None),
+                    };
+                    let items =
+                        fuzzy_match_frecency(&name, this.items_all.clone(), this.frecency, cx);
+                    let Some(inline) = inline else {
+                        return items;
+                    };
+                    // Rebuild the matches so opening them picks up the inline text
+                    items
+                        .into_iter()
+                        .filter_map(|item| item.get_meta::<Quicklink>(cx))
+                        .filter(|link| !link.placeholders().is_empty())
+                        .map(|link| link.get_item(view.clone(), Some(inline.clone()), cx))
+                        .collect()
+                }
+            })
+            .build(
+                move |_, _, cx| {
+                    let mut items = view
+                        .read(cx)
+                        .items
+                        .get(QUICKLINKS_KEY)
+                        .cloned()
+                        .unwrap_or_default();
+                    items.sort_by_cached_key(|item| {
+                        item.get_meta::<Quicklink>(cx)
+                            .map(|l| l.name.to_lowercase())
+                            .unwrap_or_default()
+                    });
+                    Ok(Some(items))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(Db::init_collection::<Quicklink>)
+}
+
+pub struct QuicklinksCommandBuilder;
+command!(QuicklinksCommandBuilder);
+impl RootCommandBuilder for QuicklinksCommandBuilder {
+    fn build(&self, cx: &mut WindowContext) -> RootCommand {
+        let view = cx.new_view(|cx| {
+            let mut list_items = AsyncListItems::new();
+            let links = Quicklink::all(db()).query().unwrap_or_default();
+            let view = cx.view().clone();
+            let items = links
+                .into_iter()
+                .map(|link| link.contents.get_item(view.clone(), None, cx))
+                .collect();
+            list_items.update(QUICKLINKS_KEY.to_string(), items, cx);
+            list_items
+        });
+
+        RootCommand::new(
+            "quicklinks",
+            "Search Quicklinks",
+            "Quicklinks",
+            Icon::Link,
+            vec!["Bookmarks", "Web Search"],
+            None,
+            move |_, cx| {
+                let view = view.clone();
+                StateModel::update(|this, cx| this.push(QuicklinkListBuilder { view }, cx), cx);
+            },
+        )
+    }
+}
@@ -11,23 +11,31 @@
 
 use std::{collections::HashMap, time::Duration};
 
+use futures::StreamExt;
 use gpui::*;
+use log::error;
 use notify::Watcher;
 use notify_debouncer_full::new_debouncer;
 
 use crate::{
     command,
-    commands::{RootCommand, RootCommandBuilder, RootCommands},
+    commands::{CommandOrder, CommandOrderSettings, RootCommand, RootCommandBuilder, RootCommands},
     components::{
-        list::{nucleo::fuzzy_match, Accessory, Item, ItemBuilder, ListBuilder, ListItem},
+        form::{Form, Input, InputKind},
+        list::{
+            frecency::{fuzzy_match_frecency, FrecencyModel, DEFAULT_FRECENCY_WEIGHT},
+            Accessory, Item, ItemBuilder, ListBuilder, ListItem, ListSettings,
+        },
         shared::{Icon, Img},
     },
-    platform::{get_application_data, get_application_files, get_application_folders},
-    state::{Action, CommandTrait, StateViewBuilder, StateViewContext},
+    platform::{get_application_data, get_application_files, get_application_watch_folders},
+    state::{
+        Action, CommandTrait, RestoreSettings, StateModel, StateViewBuilder, StateViewContext,
+    },
     window::Window,
 };
 
-use super::numbat::{Numbat, NumbatWrapper};
+use super::numbat::{is_calculation, Numbat};
 
 #[derive(Clone)]
 pub struct RootListBuilder;
@@ -38,54 +46,33 @@ impl StateViewBuilder for RootListBuilder {
             .query
             .set_placeholder("Search for apps and commands...", cx);
         let numbat = Numbat::init(&context.query, cx);
-        let commands = RootCommands::list(cx);
 
         let list = ListBuilder::new()
+            .frecency(DEFAULT_FRECENCY_WEIGHT)
             .filter(move |this, cx| {
-                let mut items = this.items_all.clone();
-                items.append(&mut commands.clone());
                 let query = this.query.view.upgrade();
                 if query.is_none() {
                     return vec![];
                 }
                 let query = query.unwrap().read(cx).text.clone();
-                let mut items = fuzzy_match(&query, items, false);
-                if items.is_empty() {
-                    if let Some(result) = numbat.read(cx).result.clone() {
-                        items.push(
-                            ItemBuilder::new(
-                                "Numbat",
-                                NumbatWrapper {
-                                    inner: numbat.clone(),
-                                },
-                            )
-                            .actions(vec![Action::new(
-                                Img::default().icon(Icon::Copy),
-                                "Copy",
-                                None,
-                                {
-                                    move |this, cx: &mut WindowContext| {
-                                        cx.write_to_clipboard(ClipboardItem::new_string(
-                                            result.result.to_string(),
-                                        ));
-                                        this.toast.floating(
-                                            "Copied to clipboard",
-                                            Some(Icon::Clipboard),
-                                            cx,
-                                        );
-                                        Window::close(cx);
-                                    }
-                                },
-                                false,
-                            )])
-                            .build(),
-                        );
+                // Until something is typed, keep the order from the last update, with pinned commands first
+                let mut items = if query.is_empty() {
+                    this.items_all.clone()
+                } else {
+                    fuzzy_match_frecency(&query, this.items_all.clone(), this.frecency, cx)
+                };
+                // Calculations and conversions lead the list, anything else Numbat evaluates only trails it
+                if let Some(result) = Numbat::item(&numbat, cx) {
+                    if is_calculation(&query) || items.is_empty() {
+                        items.insert(0, result);
+                    } else {
+                        items.push(result);
                     }
                 }
                 items
             })
             .build(
-                |_, _, _cx| {
+                |_, _, cx| {
                     {
                         let application_entries = get_application_files();
 
@@ -152,6 +139,10 @@ impl StateViewBuilder for RootListBuilder {
                         }
                         let mut apps: Vec<Item> = apps.values().cloned().collect();
                         apps.sort_unstable_by_key(|a| a.get_keywords()[0].clone());
+                        // Rebuilt here rather than per keystroke, see `RootCommands::refresh`
+                        apps.append(&mut RootCommands::list(cx));
+                        let order = CommandOrderSettings::get();
+                        apps.sort_by_key(|item| order.rank(item.id));
                         Ok(Some(apps))
                     }
                 },
@@ -159,27 +150,52 @@ impl StateViewBuilder for RootListBuilder {
                 cx,
             );
 
-        let list_clone = list.clone();
+        // Re-index apps whenever something gets installed or removed
+        let list_clone = list.downgrade();
         cx.spawn(|mut cx| async move {
-            let (tx, rx) = std::sync::mpsc::channel();
-            let mut debouncer = new_debouncer(Duration::from_secs(1), None, tx).unwrap();
-
-            let dirs = get_application_folders();
-            for dir in dirs {
-                let _ = debouncer
-                    .watcher()
-                    .watch(&dir, notify::RecursiveMode::NonRecursive);
+            let (tx, mut rx) = futures::channel::mpsc::unbounded();
+            // Installers touch lots of files, so coalesce them into a single refresh
+            let debouncer = new_debouncer(Duration::from_millis(500), None, move |result| {
+                let _ = tx.unbounded_send(result);
+            });
+            let mut debouncer = match debouncer {
+                Ok(debouncer) => debouncer,
+                Err(err) => {
+                    error!("Failed to watch application folders: {}", err);
+                    return;
+                }
+            };
+
+            for (dir, mode) in get_application_watch_folders() {
+                if let Err(err) = debouncer.watcher().watch(&dir, mode) {
+                    error!("Failed to watch {:?}: {}", dir, err);
+                }
             }
 
-            loop {
-                if rx.try_recv().is_ok() {
-                    let _ = list_clone.update(&mut cx, |this, cx| {
-                        this.update(true, cx);
-                    });
-                };
+            while let Some(result) = rx.next().await {
+                if result.is_err() {
+                    continue;
+                }
+                let update = list_clone.update(&mut cx, |this, cx| {
+                    this.update(true, cx);
+                });
+                if update.is_err() {
+                    break;
+                }
+            }
+        })
+        .detach();
 
-                cx.background_executor().timer(Duration::from_secs(1)).await;
+        // Launches reorder the commands when they are sorted by frecency
+        let frecency = cx.global::<FrecencyModel>().inner.clone();
+        let list_clone = list.downgrade();
+        cx.observe(&frecency, move |_, cx| {
+            if CommandOrderSettings::get().order != CommandOrder::Frecency {
+                return;
             }
+            let _ = list_clone.update(cx, |this, cx| {
+                this.update(true, cx);
+            });
         })
         .detach();
 
@@ -198,9 +214,74 @@ impl RootCommandBuilder for LoungyCommandBuilder {
             Icon::Rocket,
             vec!["Settings"],
             None,
-            |actions, cx| {
-                actions.toast.error("Preferences not yet implemented", cx);
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(LoungyPreferencesBuilder, cx), cx);
+            },
+        )
+    }
+}
+
+#[derive(Clone)]
+pub struct LoungyPreferencesBuilder;
+command!(LoungyPreferencesBuilder);
+impl StateViewBuilder for LoungyPreferencesBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let settings = ListSettings::get();
+        Form::new(
+            vec![
+                Input::new(
+                    "vim_navigation",
+                    "Vim Navigation",
+                    InputKind::Toggle {
+                        value: settings.vim_navigation,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "restore",
+                    "Reopen Last Command",
+                    InputKind::Toggle {
+                        value: RestoreSettings::get().enabled,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "command_order",
+                    "Command Order",
+                    InputKind::Select {
+                        options: vec![
+                            (
+                                CommandOrder::Alphabetical.into(),
+                                "Alphabetical".to_string(),
+                            ),
+                            (CommandOrder::Frecency.into(), "Most Used".to_string()),
+                        ],
+                        value: CommandOrderSettings::get().order.into(),
+                    },
+                    cx,
+                ),
+            ],
+            |values, actions, cx| {
+                let mut settings = ListSettings::get();
+                settings.vim_navigation = values["vim_navigation"].value::<bool>();
+                let restore = values["restore"].value::<bool>();
+                let mut order = CommandOrderSettings::get();
+                order.order = CommandOrder::parse(&values["command_order"].value::<String>());
+                if settings.set().is_err()
+                    || order.set().is_err()
+                    || (restore != RestoreSettings::get().enabled
+                        && RestoreSettings::enable(restore).is_err())
+                {
+                    actions.toast.error("Failed to save preferences", cx);
+                    return;
+                }
+                actions.toast.success("Saved preferences", cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+                RootCommands::refresh(cx);
             },
+            context,
+            cx,
         )
+        .into()
     }
 }
@@ -18,9 +18,14 @@ use numbat::{
 };
 
 use crate::{
-    components::{list::ItemComponent, shared::Icon},
+    components::{
+        list::{Item, ItemBuilder, ItemComponent},
+        shared::{Icon, Img},
+    },
     query::{TextEvent, TextInputWeak},
+    state::Action,
     theme::Theme,
+    window::Window,
 };
 
 #[derive(Clone)]
@@ -37,6 +42,16 @@ pub struct Numbat {
     pub result: Option<NumbatResult>,
 }
 
+// Whether the query reads like a calculation or conversion, rather than a bare identifier like
+// "pi" or "day" that Numbat happens to know
+pub fn is_calculation(query: &str) -> bool {
+    let query = query.to_lowercase();
+    query.contains(|c| "+-*/^%()×÷·²³=→➞".contains(c))
+        || [" to ", " in ", " as ", " and ", " from now", "->"]
+            .iter()
+            .any(|word| query.contains(word))
+}
+
 fn rephraser(s: &str) -> String {
     let mut s = s.to_string();
     if s.contains(" and ") {
@@ -114,6 +129,32 @@ impl Numbat {
             Numbat { result: None }
         })
     }
+
+    // List item showing the current result, shared by the root list and the calculator command
+    pub fn item(view: &View<Numbat>, cx: &AppContext) -> Option<Item> {
+        let result = view.read(cx).result.clone()?;
+        Some(
+            ItemBuilder::new(
+                "Numbat",
+                NumbatWrapper {
+                    inner: view.clone(),
+                },
+            )
+            .actions(vec![Action::new(
+                Img::default().icon(Icon::Copy),
+                "Copy Result",
+                None,
+                move |this, cx: &mut WindowContext| {
+                    cx.write_to_clipboard(ClipboardItem::new_string(result.result.to_string()));
+                    this.toast
+                        .floating("Copied to clipboard", Some(Icon::Clipboard), cx);
+                    Window::close(cx);
+                },
+                false,
+            )])
+            .build(),
+        )
+    }
 }
 
 #[derive(Clone)]
@@ -0,0 +1,305 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::sync::OnceLock;
+
+use arboard::Clipboard;
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
+use gpui::*;
+use jiff::Timestamp;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        form::{Form, Input, InputKind},
+        list::{AsyncListItems, Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    date::format_plain_date,
+    db::{Db, Migrate},
+    platform::close_and_paste,
+    state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
+};
+
+static SNIPPETS_KEY: &str = "snippets";
+
+#[derive(Clone, Serialize, Deserialize, Collection)]
+#[collection(name = "snippets.item")]
+struct Snippet {
+    #[natural_id]
+    id: u64,
+    name: String,
+    text: String,
+}
+
+impl Migrate for Snippet {}
+
+impl Snippet {
+    // Replaces placeholders with their current values
+    fn expand(&self) -> String {
+        let mut text = self
+            .text
+            .replace("{date}", &format_plain_date(Timestamp::now()));
+        if text.contains("{clipboard}") {
+            let clipboard = Clipboard::new()
+                .and_then(|mut cp| cp.get_text())
+                .unwrap_or_default();
+            text = text.replace("{clipboard}", &clipboard);
+        }
+        text
+    }
+    fn get_item(&self, view: View<AsyncListItems>, cx: &mut WindowContext) -> Item {
+        let mut preview = self.text.lines().next().unwrap_or_default().to_string();
+        if preview.len() > 40 {
+            preview = preview.chars().take(40).collect();
+            preview.push_str("...");
+        }
+        ItemBuilder::new(
+            self.id,
+            ListItem::new(
+                Some(Img::default().icon(Icon::TextQuote)),
+                self.name.clone(),
+                Some(preview),
+                vec![],
+            ),
+        )
+        .keywords(vec![self.name.clone(), self.text.clone()])
+        .actions(vec![
+            Action::new(
+                Img::default().icon(Icon::ClipboardPaste),
+                "Paste",
+                None,
+                {
+                    let snippet = self.clone();
+                    move |_, cx| {
+                        close_and_paste(snippet.expand().as_str(), false, cx);
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::Pencil),
+                "Edit",
+                Some(Shortcut::new("e").cmd()),
+                {
+                    let snippet = self.clone();
+                    let view = view.clone();
+                    move |_, cx| {
+                        let snippet = Some(snippet.clone());
+                        let view = view.clone();
+                        StateModel::update(
+                            |this, cx| this.push(SnippetFormBuilder { view, snippet }, cx),
+                            cx,
+                        );
+                    }
+                },
+                false,
+            ),
+            Action::new(
+                Img::default().icon(Icon::Trash),
+                "Delete",
+                Some(Shortcut::new("backspace").cmd()),
+                {
+                    let snippet = self.clone();
+                    move |actions, cx| {
+                        if let Err(err) = snippet.delete(&view, cx) {
+                            error!("Failed to delete snippet: {:?}", err);
+                            actions.toast.error("Failed to delete snippet", cx);
+                        } else {
+                            actions.toast.success("Successfully deleted snippet", cx);
+                        }
+                    }
+                },
+                false,
+            ),
+        ])
+        .meta(cx.new_model(|_| self.clone()).into_any())
+        .build()
+    }
+    fn delete(&self, view: &View<AsyncListItems>, cx: &mut WindowContext) -> anyhow::Result<()> {
+        if let Some(snippet) = Self::get(&self.id, db())? {
+            snippet.delete(db())?;
+        }
+        view.update(cx, |view, cx| {
+            view.remove(SNIPPETS_KEY.to_string(), self.id, cx);
+        });
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+struct SnippetFormBuilder {
+    view: View<AsyncListItems>,
+    snippet: Option<Snippet>,
+}
+command!(SnippetFormBuilder);
+impl StateViewBuilder for SnippetFormBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        let view = self.view.clone();
+        let id = self.snippet.as_ref().map(|s| s.id);
+        let (name, text) = self
+            .snippet
+            .clone()
+            .map(|s| (s.name, s.text))
+            .unwrap_or_default();
+        Form::new(
+            vec![
+                Input::new(
+                    "name",
+                    "Name",
+                    InputKind::TextField {
+                        placeholder: "Enter a name...".to_string(),
+                        value: name,
+                        validate: Some(|v| v.is_empty().then_some("Name is required")),
+                        password: false,
+                    },
+                    cx,
+                ),
+                Input::new(
+                    "text",
+                    "Text",
+                    InputKind::TextArea {
+                        placeholder:
+                            "Enter the snippet, {date} and {clipboard} are replaced when pasting..."
+                                .to_string(),
+                        value: text,
+                        validate: Some(|v| v.is_empty().then_some("Text is required")),
+                    },
+                    cx,
+                ),
+            ],
+            move |values, actions, cx| {
+                let snippet = Snippet {
+                    id: id.unwrap_or_else(rand::random),
+                    name: values["name"].value::<String>(),
+                    text: values["text"].value::<String>(),
+                };
+                if let Err(err) = snippet.clone().overwrite_into(&snippet.id, db()) {
+                    error!("Failed to save snippet: {:?}", err);
+                    actions.toast.error("Failed to save snippet", cx);
+                    return;
+                }
+                let item = snippet.get_item(view.clone(), cx);
+                view.update(cx, |view, cx| {
+                    view.push(SNIPPETS_KEY.to_string(), item, cx);
+                });
+                actions.toast.success("Successfully saved snippet", cx);
+                StateModel::update(|this, cx| this.pop(cx), cx);
+            },
+            context,
+            cx,
+        )
+        .into()
+    }
+}
+
+#[derive(Clone)]
+struct SnippetListBuilder {
+    view: View<AsyncListItems>,
+}
+command!(SnippetListBuilder);
+impl StateViewBuilder for SnippetListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context.query.set_placeholder("Search your snippets...", cx);
+        context.actions.update_global(
+            vec![Action::new(
+                Img::default().icon(Icon::PlusSquare),
+                "Create Snippet",
+                Some(Shortcut::new("n").cmd()),
+                {
+                    let view = self.view.clone();
+                    move |_, cx| {
+                        let view = view.clone();
+                        StateModel::update(
+                            |this, cx| {
+                                this.push(
+                                    SnippetFormBuilder {
+                                        view,
+                                        snippet: None,
+                                    },
+                                    cx,
+                                )
+                            },
+                            cx,
+                        );
+                    }
+                },
+                false,
+            )],
+            cx,
+        );
+
+        AsyncListItems::loader(&self.view, &context.actions, cx);
+        let view = self.view.clone();
+        ListBuilder::new()
+            .build(
+                move |_, _, cx| {
+                    let mut items = view
+                        .read(cx)
+                        .items
+                        .get(SNIPPETS_KEY)
+                        .cloned()
+                        .unwrap_or_default();
+                    items.sort_by_cached_key(|item| {
+                        item.get_meta::<Snippet>(cx)
+                            .map(|s| s.name.to_lowercase())
+                            .unwrap_or_default()
+                    });
+                    Ok(Some(items))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(Db::init_collection::<Snippet>)
+}
+
+pub struct SnippetsCommandBuilder;
+command!(SnippetsCommandBuilder);
+impl RootCommandBuilder for SnippetsCommandBuilder {
+    fn build(&self, cx: &mut WindowContext) -> RootCommand {
+        let view = cx.new_view(|cx| {
+            let mut list_items = AsyncListItems::new();
+            let snippets = Snippet::all(db()).query().unwrap_or_default();
+            let view = cx.view().clone();
+            let items = snippets
+                .into_iter()
+                .map(|snippet| snippet.contents.get_item(view.clone(), cx))
+                .collect();
+            list_items.update(SNIPPETS_KEY.to_string(), items, cx);
+            list_items
+        });
+
+        RootCommand::new(
+            "snippets",
+            "Search Snippets",
+            "Snippets",
+            Icon::TextQuote,
+            vec!["Text Expansion", "Templates"],
+            None,
+            move |_, cx| {
+                let view = view.clone();
+                StateModel::update(|this, cx| this.push(SnippetListBuilder { view }, cx), cx);
+            },
+        )
+    }
+}
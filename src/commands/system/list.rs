@@ -0,0 +1,124 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::*;
+use log::error;
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    platform::{system_action, SystemAction},
+    state::{Action, Actions, CommandTrait, StateModel, StateViewBuilder, StateViewContext},
+    window::Window,
+};
+
+static SYSTEM_ACTIONS: [(SystemAction, &str, Icon, bool); 6] = [
+    (SystemAction::Lock, "Lock Screen", Icon::Lock, false),
+    (SystemAction::Sleep, "Sleep", Icon::Moon, false),
+    (SystemAction::LogOut, "Log Out", Icon::LogOut, false),
+    (SystemAction::Restart, "Restart", Icon::RotateCcw, true),
+    (SystemAction::ShutDown, "Shut Down", Icon::Power, true),
+    (SystemAction::EmptyTrash, "Empty Trash", Icon::Trash, true),
+];
+
+fn run(action: SystemAction, actions: &mut Actions, cx: &mut WindowContext) {
+    if let Err(err) = system_action(action) {
+        error!("Failed to run system action: {}", err);
+        actions.toast.error("Failed to run system action", cx);
+        return;
+    }
+    // The floating toast hides the window by itself once it is shown
+    if let SystemAction::EmptyTrash = action {
+        actions
+            .toast
+            .floating("Emptied trash", Some(Icon::Trash), cx);
+        return;
+    }
+    Window::close(cx);
+}
+
+#[derive(Clone)]
+struct SystemListBuilder;
+command!(SystemListBuilder);
+impl StateViewBuilder for SystemListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Search for system actions...", cx);
+        ListBuilder::new()
+            .build(
+                |_, _, _| {
+                    Ok(Some(
+                        SYSTEM_ACTIONS
+                            .iter()
+                            .map(|(action, title, icon, destructive)| {
+                                let action = *action;
+                                let mut primary = Action::new(
+                                    Img::default().icon(icon.clone()),
+                                    *title,
+                                    None,
+                                    move |actions, cx| run(action, actions, cx),
+                                    false,
+                                );
+                                if *destructive {
+                                    primary = primary.confirm();
+                                }
+                                ItemBuilder::new(
+                                    *title,
+                                    ListItem::new(
+                                        Some(Img::default().icon(icon.clone())),
+                                        *title,
+                                        None,
+                                        vec![],
+                                    ),
+                                )
+                                .keywords(vec![*title])
+                                .actions(vec![primary])
+                                .build()
+                            })
+                            .collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct SystemCommandBuilder;
+command!(SystemCommandBuilder);
+impl RootCommandBuilder for SystemCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        RootCommand::new(
+            "system",
+            "System Actions",
+            "System",
+            Icon::Power,
+            vec![
+                "Lock",
+                "Sleep",
+                "Log Out",
+                "Restart",
+                "Shut Down",
+                "Empty Trash",
+            ],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(SystemListBuilder, cx), cx);
+            },
+        )
+    }
+}
@@ -11,8 +11,10 @@
 
 use std::{collections::HashMap, process::Command, time::Duration};
 
+use anyhow::anyhow;
 use gpui::*;
 use jiff::Timestamp;
+use log::error;
 use serde::Deserialize;
 
 use crate::{
@@ -24,7 +26,9 @@ use crate::{
     },
     date::format_date,
     paths::paths,
-    state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
+    state::{
+        Action, Actions, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext,
+    },
     theme::Theme,
 };
 
@@ -47,6 +51,10 @@ struct Peer {
     last_seen: Timestamp,
     online: bool,
     active: bool,
+    #[serde(default)]
+    exit_node: bool,
+    #[serde(default)]
+    exit_node_option: bool,
 }
 
 #[derive(Deserialize)]
@@ -55,6 +63,37 @@ struct Status {
     peer: HashMap<String, Peer>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Prefs {
+    route_all: bool,
+}
+
+// Runs the tailscale cli, failing with its stderr
+fn tailscale(args: &[&str]) -> anyhow::Result<Vec<u8>> {
+    let mut env: HashMap<String, String> = HashMap::new();
+    env.insert("PATH".to_string(), paths().path_env.clone());
+    let output = Command::new("tailscale").envs(env).args(args).output()?;
+    if !output.status.success() {
+        return Err(anyhow!(String::from_utf8_lossy(&output.stderr)
+            .trim()
+            .to_string()));
+    }
+    Ok(output.stdout)
+}
+
+// Applies `tailscale set` and refreshes the peers to reflect the change
+fn tailscale_set(arg: String, success: &str, this: &mut Actions, cx: &mut WindowContext) {
+    match tailscale(&["set", &arg]) {
+        Ok(_) => this.toast.success(success, cx),
+        Err(err) => {
+            error!("Failed to run tailscale set {}: {:?}", arg, err);
+            this.toast.error(format!("Tailscale: {}", err), cx);
+        }
+    }
+    this.update();
+}
+
 #[derive(Clone)]
 pub struct TailscaleListBuilder;
 
@@ -67,6 +106,46 @@ impl StateViewBuilder for TailscaleListBuilder {
             vec![("online", "Hide Offline"), ("offline", "Show Offline")],
             cx,
         );
+        context.actions.update_global(
+            vec![
+                Action::new(
+                    Img::default().icon(Icon::Route),
+                    "Toggle Accept Routes",
+                    None,
+                    |this, cx| {
+                        let prefs = tailscale(&["debug", "prefs"])
+                            .and_then(|prefs| Ok(serde_json::from_slice::<Prefs>(&prefs)?));
+                        match prefs {
+                            Ok(prefs) => tailscale_set(
+                                format!("--accept-routes={}", !prefs.route_all),
+                                if prefs.route_all {
+                                    "No longer accepting routes"
+                                } else {
+                                    "Accepting routes"
+                                },
+                                this,
+                                cx,
+                            ),
+                            Err(err) => {
+                                error!("Failed to read tailscale prefs: {:?}", err);
+                                this.toast.error("Failed to read tailscale preferences", cx);
+                            }
+                        }
+                    },
+                    false,
+                ),
+                Action::new(
+                    Img::default().icon(Icon::RouteOff),
+                    "Clear Exit Node",
+                    None,
+                    |this, cx| {
+                        tailscale_set("--exit-node=".to_string(), "Cleared exit node", this, cx)
+                    },
+                    false,
+                ),
+            ],
+            cx,
+        );
         ListBuilder::new()
             .interval(Duration::from_secs(10))
             .build(
@@ -75,14 +154,7 @@ impl StateViewBuilder for TailscaleListBuilder {
                         .to_string()
                         .eq(&this.actions.get_dropdown_value(cx));
                     let theme = cx.global::<Theme>().clone();
-                    let mut env: HashMap<String, String> = HashMap::new();
-                    env.insert("PATH".to_string(), paths().path_env.clone());
-                    let status = Command::new("tailscale")
-                        .envs(env)
-                        .arg("status")
-                        .arg("--json")
-                        .output()?
-                        .stdout;
+                    let status = tailscale(&["status", "--json"])?;
                     let json = serde_json::from_slice::<Status>(&status)?;
 
                     let mut items: Vec<Item> = json
@@ -102,7 +174,125 @@ impl StateViewBuilder for TailscaleListBuilder {
                             };
                             let ip = p.tailscale_ips.first().unwrap();
                             let ipv6 = p.tailscale_ips.last().unwrap();
-                            let url = format!("https://{}", &ip);
+                            // MagicDNS names are fully qualified with a trailing dot
+                            let dns = p.dns_name.trim_end_matches('.').to_string();
+                            let mut actions = vec![];
+                            // Offline peers can't serve anything, so there is nothing to open
+                            if p.online {
+                                // Peers without MagicDNS are only reachable by their address
+                                let host = if dns.is_empty() { ip } else { &dns };
+                                let url = format!("http://{}", host);
+                                actions.push(Action::new(
+                                    Img::default().icon(Icon::ArrowUpRightFromSquare),
+                                    "Open",
+                                    None,
+                                    move |this, cx| {
+                                        cx.open_url(&url.clone());
+                                        this.toast.floating(
+                                            "Opened peer in browser",
+                                            Some(Icon::ArrowUpRightFromSquare),
+                                            cx,
+                                        )
+                                    },
+                                    false,
+                                ));
+                            }
+                            actions.extend(vec![
+                                Action::new(
+                                    Img::default().icon(Icon::Clipboard),
+                                    "Copy IPv4",
+                                    Some(Shortcut::new("c").cmd()),
+                                    {
+                                        let ip = ip.clone();
+                                        move |this, cx| {
+                                            cx.write_to_clipboard(ClipboardItem::new_string(
+                                                ip.clone(),
+                                            ));
+                                            this.toast.floating(
+                                                "Copied IPv4 to Clipboard",
+                                                Some(Icon::Clipboard),
+                                                cx,
+                                            )
+                                        }
+                                    },
+                                    false,
+                                ),
+                                Action::new(
+                                    Img::default().icon(Icon::Clipboard),
+                                    "Copy IPv6",
+                                    Some(Shortcut::new("c").cmd().shift()),
+                                    {
+                                        let ip = ipv6.clone();
+                                        move |this, cx| {
+                                            cx.write_to_clipboard(ClipboardItem::new_string(
+                                                ip.clone(),
+                                            ));
+                                            this.toast.floating(
+                                                "Copied IPv6 to Clipboard",
+                                                Some(Icon::Clipboard),
+                                                cx,
+                                            )
+                                        }
+                                    },
+                                    false,
+                                ),
+                                Action::new(
+                                    Img::default().icon(Icon::Clipboard),
+                                    "Copy MagicDNS Name",
+                                    Some(Shortcut::new("c").cmd().alt()),
+                                    move |this, cx| {
+                                        cx.write_to_clipboard(ClipboardItem::new_string(
+                                            dns.clone(),
+                                        ));
+                                        this.toast.floating(
+                                            "Copied MagicDNS name to Clipboard",
+                                            Some(Icon::Clipboard),
+                                            cx,
+                                        )
+                                    },
+                                    false,
+                                ),
+                            ]);
+                            let mut accessories = vec![Accessory::Tag { tag, img: None }];
+                            if p.exit_node {
+                                accessories.insert(
+                                    0,
+                                    Accessory::new(
+                                        "Exit Node",
+                                        Some(Img::default().icon(Icon::Globe)),
+                                    ),
+                                );
+                                actions.push(Action::new(
+                                    Img::default().icon(Icon::RouteOff),
+                                    "Clear Exit Node",
+                                    None,
+                                    |this, cx| {
+                                        tailscale_set(
+                                            "--exit-node=".to_string(),
+                                            "Cleared exit node",
+                                            this,
+                                            cx,
+                                        )
+                                    },
+                                    false,
+                                ));
+                            } else if p.exit_node_option && p.online {
+                                let ip = ip.clone();
+                                actions.push(Action::new(
+                                    Img::default().icon(Icon::Globe),
+                                    "Use as Exit Node",
+                                    Some(Shortcut::new("e").cmd()),
+                                    move |this, cx| {
+                                        tailscale_set(
+                                            format!("--exit-node={}", ip),
+                                            "Switched exit node",
+                                            this,
+                                            cx,
+                                        )
+                                    },
+                                    false,
+                                ));
+                            }
                             Some(
                                 ItemBuilder::new(
                                     p.id.clone(),
@@ -110,63 +300,10 @@ impl StateViewBuilder for TailscaleListBuilder {
                                         Some(Img::default().dot(color)),
                                         name,
                                         Some(p.os.to_string()),
-                                        vec![Accessory::Tag { tag, img: None }],
+                                        accessories,
                                     ),
                                 )
-                                .actions(vec![
-                                    Action::new(
-                                        Img::default().icon(Icon::ArrowUpRightFromSquare),
-                                        "Open",
-                                        None,
-                                        move |this, cx| {
-                                            cx.open_url(&url.clone());
-                                            this.toast.floating(
-                                                "Opened peer in browser",
-                                                Some(Icon::ArrowUpRightFromSquare),
-                                                cx,
-                                            )
-                                        },
-                                        false,
-                                    ),
-                                    Action::new(
-                                        Img::default().icon(Icon::Clipboard),
-                                        "Copy IPv4",
-                                        Some(Shortcut::new("c").cmd()),
-                                        {
-                                            let ip = ip.clone();
-                                            move |this, cx| {
-                                                cx.write_to_clipboard(ClipboardItem::new_string(
-                                                    ip.clone(),
-                                                ));
-                                                this.toast.floating(
-                                                    "Copied IPv4 to Clipboard",
-                                                    Some(Icon::Clipboard),
-                                                    cx,
-                                                )
-                                            }
-                                        },
-                                        false,
-                                    ),
-                                    Action::new(
-                                        Img::default().icon(Icon::Clipboard),
-                                        "Copy IPv6",
-                                        Some(Shortcut::new("c").cmd().shift()),
-                                        {
-                                            let ip = ipv6.clone();
-                                            move |this, cx| {
-                                                cx.write_to_clipboard(ClipboardItem::new_string(
-                                                    ip.clone(),
-                                                ));
-                                                this.toast.floating(
-                                                    "Copied IPv6 to Clipboard",
-                                                    Some(Icon::Clipboard),
-                                                    cx,
-                                                )
-                                            }
-                                        },
-                                        false,
-                                    ),
-                                ])
+                                .actions(actions)
                                 .keywords(vec![name])
                                 .build(),
                             )
@@ -17,12 +17,12 @@ use crate::{
     command,
     commands::{RootCommand, RootCommandBuilder},
     components::{
-        list::{ItemBuilder, ListBuilder, ListItem},
-        shared::{Icon, Img},
+        list::{Accessory, ItemBuilder, ListBuilder, ListItem},
+        shared::{animations_frozen, freeze_animations, Icon, Img},
     },
-    db::db,
+    query::TextEvent,
     state::{Action, CommandTrait, Shortcut, StateModel, StateViewBuilder, StateViewContext},
-    theme::{Theme, ThemeSettings},
+    theme::{ReduceMotion, Theme, ThemeSettings},
 };
 
 #[derive(Clone)]
@@ -32,108 +32,228 @@ command!(ThemeListBuilder);
 impl StateViewBuilder for ThemeListBuilder {
     fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
         context.query.set_placeholder("Search for themes...", cx);
-        ListBuilder::new()
+        let list = ListBuilder::new()
             .interval(Duration::from_secs(10))
+            // Preview the highlighted theme without persisting it
+            .select_handler(|item, cx| {
+                if let Some(theme) = item.and_then(|item| item.get_meta::<Theme>(cx)) {
+                    Theme::activate(theme, cx);
+                }
+            })
             .build(
-                |_, _, _cx| {
+                |_, _, cx| {
                     let themes = Theme::list();
-                    Ok(Some(
-                        themes
-                            .into_iter()
-                            .map(|theme| {
-                                ItemBuilder::new(
-                                    theme.name.clone(),
-                                    ListItem::new(
-                                        Some(Img::default().dot(theme.base)),
-                                        theme.name.clone(),
-                                        None,
-                                        vec![],
-                                    ),
-                                )
-                                .keywords(vec![theme.name.clone()])
-                                .actions(vec![
-                                    Action::new(
-                                        Img::default().icon(Icon::Palette),
-                                        "Select Theme",
-                                        None,
-                                        {
-                                            let theme = theme.clone();
-                                            move |this, cx| {
-                                                cx.update_global::<Theme, _>(|this, cx| {
-                                                    *this = theme.clone();
-                                                    cx.set_background_appearance(
-                                                        WindowBackgroundAppearance::from(
-                                                            theme
-                                                                .window_background
-                                                                .clone()
-                                                                .unwrap_or_default(),
-                                                        ),
-                                                    )
-                                                });
-                                                this.toast.success("Theme activated", cx);
-                                                cx.refresh();
-                                            }
-                                        },
-                                        false,
-                                    ),
-                                    Action::new(
-                                        Img::default().icon(Icon::Sun),
-                                        "Default Light Theme",
-                                        Some(Shortcut::new("l").cmd()),
-                                        {
-                                            let name = theme.name.clone();
-                                            move |this, cx| {
-                                                let mut settings = db()
-                                                    .get::<ThemeSettings>("theme")
-                                                    .unwrap_or_default();
-                                                settings.light = name.clone().to_string();
-                                                if db()
-                                                    .set::<ThemeSettings>("theme", &settings)
-                                                    .is_err()
-                                                {
-                                                    this.toast
-                                                        .error("Failed to change light theme", cx);
-                                                } else {
-                                                    this.toast.success("Changed light theme", cx);
-                                                }
-                                            }
-                                        },
-                                        false,
-                                    ),
-                                    Action::new(
-                                        Img::default().icon(Icon::Moon),
-                                        "Default Dark Theme",
-                                        Some(Shortcut::new("d").cmd()),
-                                        {
-                                            let name = theme.name.clone();
-                                            move |this, cx| {
-                                                let mut settings = db()
-                                                    .get::<ThemeSettings>("theme")
-                                                    .unwrap_or_default();
-                                                settings.dark = name.clone().to_string();
-                                                if db()
-                                                    .set::<ThemeSettings>("theme", &settings)
-                                                    .is_err()
-                                                {
-                                                    this.toast
-                                                        .error("Failed to change dark theme", cx);
-                                                } else {
-                                                    this.toast.success("Changed dark theme", cx);
-                                                }
-                                            }
-                                        },
-                                        false,
-                                    ),
-                                ])
-                                .build()
-                            })
-                            .collect(),
-                    ))
+                    let settings = ThemeSettings::get();
+                    let active = |selected: bool| {
+                        if selected {
+                            vec![Accessory::new("Active", None)]
+                        } else {
+                            vec![]
+                        }
+                    };
+                    let auto = Theme::auto(Theme::appearance(cx));
+                    let mut items = vec![ItemBuilder::new(
+                        "auto",
+                        ListItem::new(
+                            Some(Img::default().icon(Icon::SunMoon)),
+                            "Auto",
+                            Some("Follow the system appearance".to_string()),
+                            active(settings.fixed.is_none()),
+                        ),
+                    )
+                    .meta(cx.new_model(|_| auto).into_any())
+                    .keywords(vec!["Auto", "System", "Appearance"])
+                    .actions(vec![Action::new(
+                        Img::default().icon(Icon::SunMoon),
+                        "Follow System Appearance",
+                        None,
+                        |this, cx| {
+                            let mut settings = ThemeSettings::get();
+                            settings.fixed = None;
+                            if settings.set().is_err() {
+                                this.toast.error("Failed to change theme", cx);
+                                return;
+                            }
+                            Theme::sync(cx);
+                            this.toast.success("Following system appearance", cx);
+                        },
+                        false,
+                    )])
+                    .build()];
+                    let frozen = animations_frozen();
+                    items.push(
+                        ItemBuilder::new(
+                            "animations",
+                            ListItem::new(
+                                Some(Img::default().icon(Icon::Film)),
+                                "Animated Images",
+                                Some("Play animated GIFs and APNGs".to_string()),
+                                vec![Accessory::new(
+                                    if frozen { "Frozen" } else { "Playing" },
+                                    None,
+                                )],
+                            ),
+                        )
+                        .keywords(vec!["Animations", "GIF", "Performance"])
+                        .actions(vec![Action::new(
+                            Img::default().icon(if frozen { Icon::Play } else { Icon::Pause }),
+                            if frozen {
+                                "Play Animations"
+                            } else {
+                                "Freeze Animations"
+                            },
+                            None,
+                            move |this, cx| {
+                                if freeze_animations(!frozen, cx).is_err() {
+                                    this.toast.error("Failed to change animation setting", cx);
+                                    return;
+                                }
+                                this.toast.success(
+                                    if frozen {
+                                        "Animations playing"
+                                    } else {
+                                        "Animations frozen"
+                                    },
+                                    cx,
+                                );
+                            },
+                            false,
+                        )])
+                        .build(),
+                    );
+                    let reduced = ReduceMotion::enabled(cx);
+                    items.push(
+                        ItemBuilder::new(
+                            "reduce-motion",
+                            ListItem::new(
+                                Some(Img::default().icon(Icon::Accessibility)),
+                                "Reduce Motion",
+                                Some("Replace animations with static renders".to_string()),
+                                active(reduced),
+                            ),
+                        )
+                        .keywords(vec!["Motion", "Animations", "Accessibility", "Battery"])
+                        .actions(vec![Action::new(
+                            Img::default().icon(Icon::Accessibility),
+                            if reduced {
+                                "Allow Motion"
+                            } else {
+                                "Reduce Motion"
+                            },
+                            None,
+                            move |this, cx| {
+                                if ReduceMotion::set(!reduced, cx).is_err() {
+                                    this.toast.error("Failed to change motion setting", cx);
+                                    return;
+                                }
+                                this.toast.success(
+                                    if reduced {
+                                        "Motion allowed"
+                                    } else {
+                                        "Motion reduced"
+                                    },
+                                    cx,
+                                );
+                            },
+                            false,
+                        )])
+                        .build(),
+                    );
+                    items.extend(themes.into_iter().map(|theme| {
+                        let fixed = settings
+                            .fixed
+                            .as_ref()
+                            .is_some_and(|fixed| theme.name.eq(fixed));
+                        ItemBuilder::new(
+                            theme.name.clone(),
+                            ListItem::new(
+                                Some(Img::default().dot(theme.base)),
+                                theme.name.clone(),
+                                None,
+                                active(fixed),
+                            ),
+                        )
+                        .meta(cx.new_model(|_| theme.clone()).into_any())
+                        .keywords(vec![theme.name.clone()])
+                        .actions(vec![
+                            Action::new(
+                                Img::default().icon(Icon::Palette),
+                                "Select Theme",
+                                None,
+                                {
+                                    let theme = theme.clone();
+                                    move |this, cx| {
+                                        let mut settings = ThemeSettings::get();
+                                        settings.fixed = Some(theme.name.to_string());
+                                        if settings.set().is_err() {
+                                            this.toast.error("Failed to change theme", cx);
+                                            return;
+                                        }
+                                        Theme::activate(theme.clone(), cx);
+                                        this.toast.success("Theme activated", cx);
+                                    }
+                                },
+                                false,
+                            ),
+                            Action::new(
+                                Img::default().icon(Icon::Sun),
+                                "Default Light Theme",
+                                Some(Shortcut::new("l").cmd()),
+                                {
+                                    let name = theme.name.clone();
+                                    move |this, cx| {
+                                        let mut settings = ThemeSettings::get();
+                                        settings.light = name.clone().to_string();
+                                        if settings.set().is_err() {
+                                            this.toast.error("Failed to change light theme", cx);
+                                        } else {
+                                            Theme::sync(cx);
+                                            this.toast.success("Changed light theme", cx);
+                                        }
+                                    }
+                                },
+                                false,
+                            ),
+                            Action::new(
+                                Img::default().icon(Icon::Moon),
+                                "Default Dark Theme",
+                                Some(Shortcut::new("d").cmd()),
+                                {
+                                    let name = theme.name.clone();
+                                    move |this, cx| {
+                                        let mut settings = ThemeSettings::get();
+                                        settings.dark = name.clone().to_string();
+                                        if settings.set().is_err() {
+                                            this.toast.error("Failed to change dark theme", cx);
+                                        } else {
+                                            Theme::sync(cx);
+                                            this.toast.success("Changed dark theme", cx);
+                                        }
+                                    }
+                                },
+                                false,
+                            ),
+                        ])
+                        .build()
+                    }));
+                    Ok(Some(items))
                 },
                 context,
                 cx,
-            )
-            .into()
+            );
+        // Leaving the list without confirming restores the persisted theme
+        cx.observe_release(&list, |_, cx| Theme::sync(cx)).detach();
+        if let Some(query) = context.query.view.upgrade() {
+            cx.subscribe(&query, |_, event, cx| {
+                if let TextEvent::KeyDown(ev) = event {
+                    if ev.keystroke.key.as_str() == "escape" {
+                        Theme::sync(cx);
+                    }
+                }
+            })
+            .detach();
+        }
+        list.into()
     }
 }
 
@@ -0,0 +1,343 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::time::{Duration, SystemTime};
+
+use gpui::*;
+use log::error;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    command,
+    commands::{RootCommand, RootCommandBuilder},
+    components::{
+        list::{Accessory, Item, ItemBuilder, ListBuilder, ListItem},
+        shared::{Icon, Img},
+    },
+    db::db,
+    platform::send_notification,
+    state::{
+        Action, Actions, CommandTrait, LazyMutex, Shortcut, StateModel, StateViewBuilder,
+        StateViewContext,
+    },
+    window::Window,
+};
+
+// Running timers and stopwatches, stored so they survive a restart
+static TIMERS: LazyMutex<Vec<Timer>> =
+    LazyMutex::new(|| db().get::<Vec<Timer>>("timers").unwrap_or_default());
+
+fn save(timers: &[Timer]) {
+    if let Err(err) = db().set("timers", &timers.to_vec()) {
+        error!("Failed to save timers: {}", err);
+    }
+}
+
+// Accepts 30s, 5m, 1h30m and so on
+fn parse_duration(text: &str) -> Option<Duration> {
+    let re = Regex::new(r"^(?:(\d+)h)?(?:(\d+)m)?(?:(\d+)s)?$").unwrap();
+    let captures = re.captures(text)?;
+    let secs = [(1, 3600u64), (2, 60), (3, 1)]
+        .iter()
+        .filter_map(|(i, unit)| {
+            let value = captures.get(*i)?.as_str().parse::<u64>().ok()?;
+            Some(value.saturating_mul(*unit))
+        })
+        .fold(0u64, |sum, secs| sum.saturating_add(secs));
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+fn format_clock(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    let (hours, minutes, secs) = (secs / 3600, secs / 60 % 60, secs % 60);
+    if hours > 0 {
+        format!("{}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{}:{:02}", minutes, secs)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Timer {
+    id: u64,
+    label: String,
+    started: SystemTime,
+    // Stopwatches count up and have no duration
+    duration: Option<Duration>,
+}
+
+impl Timer {
+    fn start(
+        label: String,
+        duration: Option<Duration>,
+        actions: &mut Actions,
+        cx: &mut WindowContext,
+    ) {
+        let message = match duration {
+            Some(duration) => format!("Started {} timer", format_clock(duration)),
+            None => "Started stopwatch".to_string(),
+        };
+        let mut timers = TIMERS.lock();
+        timers.push(Timer {
+            id: rand::random(),
+            label,
+            started: SystemTime::now(),
+            duration,
+        });
+        save(&timers);
+        drop(timers);
+        actions.toast.success(message, cx);
+        StateModel::update(|this, cx| this.set_query("", cx), cx);
+        actions.update();
+    }
+    fn elapsed(&self) -> Duration {
+        self.started.elapsed().unwrap_or_default()
+    }
+    fn remaining(&self) -> Option<Duration> {
+        Some(self.duration?.saturating_sub(self.elapsed()))
+    }
+    fn remove(id: u64) -> Option<Timer> {
+        let mut timers = TIMERS.lock();
+        let index = timers.iter().position(|timer| timer.id == id)?;
+        let timer = timers.remove(index);
+        save(&timers);
+        Some(timer)
+    }
+    fn get_item(&self) -> Item {
+        let id = self.id;
+        let (icon, subtitle, clock, actions) = match self.remaining() {
+            Some(remaining) => (
+                Icon::Timer,
+                format!("{} timer", format_clock(self.duration.unwrap_or_default())),
+                format_clock(remaining),
+                vec![
+                    Action::new(
+                        Img::default().icon(Icon::TimerOff),
+                        "Cancel Timer",
+                        None,
+                        move |actions, cx| {
+                            Timer::remove(id);
+                            actions.toast.success("Cancelled timer", cx);
+                            actions.update();
+                        },
+                        false,
+                    ),
+                    Action::new(
+                        Img::default().icon(Icon::TimerReset),
+                        "Restart Timer",
+                        Some(Shortcut::new("r").cmd()),
+                        move |actions, cx| {
+                            let mut timers = TIMERS.lock();
+                            if let Some(timer) = timers.iter_mut().find(|timer| timer.id == id) {
+                                timer.started = SystemTime::now();
+                            }
+                            save(&timers);
+                            drop(timers);
+                            actions.toast.success("Restarted timer", cx);
+                            actions.update();
+                        },
+                        false,
+                    ),
+                ],
+            ),
+            None => (
+                Icon::Watch,
+                "Stopwatch".to_string(),
+                format_clock(self.elapsed()),
+                vec![
+                    Action::new(
+                        Img::default().icon(Icon::StopCircle),
+                        "Stop Stopwatch",
+                        None,
+                        move |actions, cx| {
+                            if let Some(timer) = Timer::remove(id) {
+                                actions.toast.success(
+                                    format!("Stopped at {}", format_clock(timer.elapsed())),
+                                    cx,
+                                );
+                            }
+                            actions.update();
+                        },
+                        false,
+                    ),
+                    Action::new(
+                        Img::default().icon(Icon::Copy),
+                        "Copy Elapsed Time",
+                        Some(Shortcut::new("c").cmd().shift()),
+                        move |actions, cx| {
+                            let Some(elapsed) = TIMERS
+                                .lock()
+                                .iter()
+                                .find(|timer| timer.id == id)
+                                .map(|timer| format_clock(timer.elapsed()))
+                            else {
+                                return;
+                            };
+                            cx.write_to_clipboard(ClipboardItem::new_string(elapsed));
+                            actions.toast.floating(
+                                "Copied to clipboard",
+                                Some(Icon::Clipboard),
+                                cx,
+                            );
+                        },
+                        false,
+                    ),
+                ],
+            ),
+        };
+        ItemBuilder::new(
+            id,
+            ListItem::new(
+                Some(Img::default().icon(icon)),
+                self.label.clone(),
+                Some(subtitle),
+                vec![Accessory::new(clock, None)],
+            ),
+        )
+        .actions(actions)
+        .build()
+    }
+}
+
+// "5m tea" offers a 5 minute timer labeled tea, anything else a stopwatch
+fn start_item(query: &str) -> Item {
+    let query = query.trim();
+    let (first, rest) = query.split_once(' ').unwrap_or((query, ""));
+    let duration = parse_duration(first);
+    let label = match (duration, rest.trim(), query) {
+        (Some(_), "", _) => "Timer",
+        (Some(_), rest, _) => rest,
+        (None, _, "") => "Stopwatch",
+        (None, _, query) => query,
+    }
+    .to_string();
+    let (icon, title, subtitle) = match duration {
+        Some(duration) => (
+            Icon::Timer,
+            "Start Timer",
+            format!("{} timer", format_clock(duration)),
+        ),
+        None => (Icon::Watch, "Start Stopwatch", "Stopwatch".to_string()),
+    };
+    ItemBuilder::new(
+        "start",
+        ListItem::new(
+            Some(Img::default().icon(icon.clone())),
+            label.clone(),
+            Some(subtitle),
+            vec![],
+        ),
+    )
+    .actions(vec![Action::new(
+        Img::default().icon(icon),
+        title,
+        None,
+        move |actions, cx| Timer::start(label.clone(), duration, actions, cx),
+        false,
+    )])
+    .build()
+}
+
+// Shows the toast in whatever view is active, since the timers run in the background
+fn toast_done(message: String, cx: &mut WindowContext) {
+    let mut toast = {
+        let state = cx.global::<StateModel>().inner.read(cx);
+        let Some(item) = state.stack.last() else {
+            return;
+        };
+        item.actions.read(cx).toast.clone()
+    };
+    toast.success(message, cx);
+}
+
+#[derive(Clone)]
+struct TimerListBuilder;
+command!(TimerListBuilder);
+impl StateViewBuilder for TimerListBuilder {
+    fn build(&self, context: &mut StateViewContext, cx: &mut WindowContext) -> AnyView {
+        context
+            .query
+            .set_placeholder("Start a timer, e.g. 5m tea or 1h30m...", cx);
+        ListBuilder::new()
+            .interval(Duration::from_secs(1))
+            .filter(|this, cx| {
+                let query = this.query.get_text(cx);
+                let mut items = this.items_all.clone();
+                // Running timers come first until something is typed
+                if query.trim().is_empty() {
+                    items.push(start_item(&query));
+                } else {
+                    items.insert(0, start_item(&query));
+                }
+                items
+            })
+            .build(
+                |_, _, _| {
+                    Ok(Some(
+                        TIMERS
+                            .lock()
+                            .iter()
+                            .rev()
+                            .map(|timer| timer.get_item())
+                            .collect(),
+                    ))
+                },
+                context,
+                cx,
+            )
+            .into()
+    }
+}
+
+pub struct TimerCommandBuilder;
+command!(TimerCommandBuilder);
+impl RootCommandBuilder for TimerCommandBuilder {
+    fn build(&self, cx: &mut WindowContext) -> RootCommand {
+        cx.spawn(|mut cx| async move {
+            loop {
+                cx.background_executor().timer(Duration::from_secs(1)).await;
+                let done: Vec<Timer> = {
+                    let mut timers = TIMERS.lock();
+                    let (done, running) = timers
+                        .drain(..)
+                        .partition(|timer| timer.remaining() == Some(Duration::ZERO));
+                    *timers = running;
+                    if !done.is_empty() {
+                        save(&timers);
+                    }
+                    done
+                };
+                for timer in done {
+                    let message = format!("{} is done", timer.label);
+                    if Window::is_open(&cx) {
+                        let _ = cx.update(|cx| toast_done(message, cx));
+                    } else {
+                        send_notification(&format!("timer-{}", timer.id), "Timer Done", &message);
+                    }
+                }
+            }
+        })
+        .detach();
+
+        RootCommand::new(
+            "timer",
+            "Timers",
+            "Timer",
+            Icon::Timer,
+            vec!["Stopwatch", "Countdown", "Alarm"],
+            None,
+            |_, cx| {
+                StateModel::update(|this, cx| this.push(TimerListBuilder, cx), cx);
+            },
+        )
+    }
+}
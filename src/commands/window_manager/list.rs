@@ -0,0 +1,108 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use gpui::*;
+
+use crate::{
+    commands::{RootCommand, RootCommandBuilder},
+    components::shared::Icon,
+    platform::{move_window, WindowError},
+    state::{CommandTrait, Shortcut},
+    window::Window,
+};
+
+#[derive(Clone, Copy)]
+pub enum WindowPosition {
+    LeftHalf,
+    RightHalf,
+    Maximize,
+    Center,
+}
+
+impl WindowPosition {
+    fn id(&self) -> &'static str {
+        match self {
+            WindowPosition::LeftHalf => "left_half",
+            WindowPosition::RightHalf => "right_half",
+            WindowPosition::Maximize => "maximize",
+            WindowPosition::Center => "center",
+        }
+    }
+    fn title(&self) -> &'static str {
+        match self {
+            WindowPosition::LeftHalf => "Left Half",
+            WindowPosition::RightHalf => "Right Half",
+            WindowPosition::Maximize => "Maximize",
+            WindowPosition::Center => "Center",
+        }
+    }
+    fn icon(&self) -> Icon {
+        match self {
+            WindowPosition::LeftHalf => Icon::PanelLeft,
+            WindowPosition::RightHalf => Icon::PanelRight,
+            WindowPosition::Maximize => Icon::Maximize,
+            WindowPosition::Center => Icon::AlignCenter,
+        }
+    }
+    fn hotkey(&self) -> Shortcut {
+        let key = match self {
+            WindowPosition::LeftHalf => "left",
+            WindowPosition::RightHalf => "right",
+            WindowPosition::Maximize => "enter",
+            WindowPosition::Center => "c",
+        };
+        Shortcut::new(key).ctrl().alt()
+    }
+}
+
+pub struct WindowCommandBuilder {
+    pub position: WindowPosition,
+}
+
+// Every position is its own root command, so each one can have a hotkey
+impl CommandTrait for WindowCommandBuilder {
+    fn command(&self) -> String {
+        format!("{}::{}", module_path!(), self.position.id())
+    }
+}
+
+impl RootCommandBuilder for WindowCommandBuilder {
+    fn build(&self, _cx: &mut WindowContext) -> RootCommand {
+        let position = self.position;
+        RootCommand::new(
+            position.id(),
+            position.title(),
+            "Window Management",
+            position.icon(),
+            vec!["Window", "Resize", "Move"],
+            Some(position.hotkey()),
+            move |actions, cx| {
+                let mut toast = actions.toast.clone();
+                // The window has to be out of the way, otherwise Loungy is the frontmost application
+                Window::close(cx);
+                cx.spawn(|mut cx| async move {
+                    Window::wait_for_close(&mut cx).await;
+                    let message = match move_window(position.id()) {
+                        Ok(_) => return,
+                        Err(WindowError::NotTrusted) => {
+                            "Enable accessibility access for Loungy in System Settings"
+                        }
+                        Err(WindowError::NoWindow) => "No window to move",
+                    };
+                    let _ = cx.update(|cx| {
+                        toast.floating(message, Some(Icon::AppWindow), cx);
+                    });
+                })
+                .detach();
+            },
+        )
+    }
+}
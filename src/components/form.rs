@@ -9,14 +9,15 @@
  *
  */
 
-use std::{any::Any, collections::HashMap};
+use std::{any::Any, collections::HashMap, rc::Rc, time::Duration};
 
+use futures::future::LocalBoxFuture;
 use gpui::*;
 
 use crate::{
     components::shared::{Icon, Img},
     query::{TextEvent, TextInputWeak},
-    state::{Action, Actions, Shortcut, StateViewContext},
+    state::{dropdown_style, Action, Actions, Shortcut, StateViewContext},
     theme::Theme,
 };
 
@@ -27,8 +28,13 @@ pub struct Input {
     kind: InputKind,
     error: Option<String>,
     show_error: bool,
+    async_validate: Option<Rc<dyn AsyncValidateFn>>,
+    async_error: Option<String>,
 }
 
+pub trait AsyncValidateFn: Fn(String) -> LocalBoxFuture<'static, Option<String>> {}
+impl<F> AsyncValidateFn for F where F: Fn(String) -> LocalBoxFuture<'static, Option<String>> {}
+
 impl Input {
     pub fn new(
         id: impl ToString,
@@ -42,20 +48,35 @@ impl Input {
             kind,
             error: None,
             show_error: false,
+            async_validate: None,
+            async_error: None,
         }
     }
+    // Runs once the synchronous validation passes, debounced while typing
+    pub fn async_validate(mut self, validate: impl AsyncValidateFn + 'static) -> Self {
+        self.async_validate = Some(Rc::new(validate));
+        self
+    }
     pub fn validate(&mut self) {
         self.error = match &self.kind {
             InputKind::TextField {
                 value, validate, ..
+            }
+            | InputKind::TextArea {
+                value, validate, ..
             } => validate.map(|f| f(value)).flatten().map(|s| s.to_string()),
             _ => None,
         }
+        .or(self.async_error.clone())
     }
     pub fn value<V: Clone + 'static>(&self) -> V {
         let value: Box<dyn Any> = match self.kind.clone() {
-            InputKind::TextField { value, .. } => Box::new(value),
+            InputKind::TextField { value, .. } | InputKind::TextArea { value, .. } => {
+                Box::new(value)
+            }
             InputKind::Shortcut { value, .. } => Box::new(value),
+            InputKind::Select { value, .. } => Box::new(value),
+            InputKind::Toggle { value } => Box::new(value),
         };
         value.downcast_ref::<V>().unwrap().clone()
     }
@@ -67,6 +88,7 @@ pub struct InputView {
     focused: bool,
     index: usize,
     focus_model: Model<usize>,
+    validation: Option<Task<()>>,
 }
 
 impl Render for InputView {
@@ -98,6 +120,11 @@ impl Render for InputView {
                             InputKind::TextField { .. } => {
                                 self.input.view.upgrade().map(|q| q.into_any_element()).unwrap_or(div().into_any_element())
                             }
+                            // Grows with the text, the form re-measures the row on every change
+                            InputKind::TextArea { .. } => div()
+                                .min_h_16()
+                                .children(self.input.view.upgrade())
+                                .into_any_element(),
                             InputKind::Shortcut { tmp, .. } => div()
                                 .relative()
                                 .child(
@@ -136,6 +163,14 @@ impl Render for InputView {
                                 )
                                 .child("Recording...")
                                 .into_any_element(),
+                            InputKind::Select { .. } => div()
+                                .flex()
+                                .items_center()
+                                .child(Img::default().icon(Icon::ChevronLeft))
+                                .child(self.select_label(theme))
+                                .child(Img::default().icon(Icon::ChevronRight))
+                                .into_any_element(),
+                            InputKind::Toggle { value } => Self::switch(value, theme),
                         }
                     } else {
                         match self.inner.kind.clone() {
@@ -153,6 +188,15 @@ impl Render for InputView {
                                     value.into_any_element()
                                 }
                             }
+                            InputKind::TextArea {
+                                placeholder, value, ..
+                            } => {
+                                if value.is_empty() {
+                                    placeholder.into_any_element()
+                                } else {
+                                    div().min_h_16().child(value).into_any_element()
+                                }
+                            }
                             InputKind::Shortcut { value, .. } => {
                                 if let Some(shortcut) = value {
                                     div()
@@ -170,6 +214,10 @@ impl Render for InputView {
                                     "Record Hotkey".into_any_element()
                                 }
                             }
+                            InputKind::Select { .. } => {
+                                div().flex().child(self.select_label(theme)).into_any_element()
+                            }
+                            InputKind::Toggle { value } => Self::switch(value, theme),
                         }
                     })
                     .w_1_2()
@@ -177,7 +225,7 @@ impl Render for InputView {
                     .border_1()
                     .rounded_lg()
                     .border_color(if self.focused {
-                        theme.lavender
+                        theme.accent()
                     } else if self.inner.show_error && self.inner.error.is_some() {
                         theme.red
                     } else {
@@ -205,6 +253,27 @@ impl Render for InputView {
 }
 
 impl InputView {
+    fn switch(value: bool, theme: &Theme) -> AnyElement {
+        let mut switch = div().w_8().h_4().p_0p5().rounded_full().flex();
+        if value {
+            switch = switch.justify_end();
+        }
+        switch
+            .bg(if value { theme.green } else { theme.surface1 })
+            .child(div().size_3().rounded_full().bg(theme.base))
+            .into_any_element()
+    }
+    fn select_label(&self, theme: &Theme) -> Div {
+        let InputKind::Select { options, value } = &self.inner.kind else {
+            return div();
+        };
+        let label = options
+            .iter()
+            .find(|option| option.0.eq(value))
+            .map(|option| option.1.clone())
+            .unwrap_or_default();
+        dropdown_style(theme).child(label)
+    }
     pub fn on_focus(&mut self, cx: &mut ViewContext<Self>) {
         //
         match self.inner.kind.clone() {
@@ -218,9 +287,53 @@ impl InputView {
                 self.input.set_placeholder(placeholder, cx);
                 self.input.set_text(value, cx);
             }
+            InputKind::TextArea {
+                placeholder, value, ..
+            } => {
+                self.input.set_masked(false, cx);
+                self.input.set_placeholder(placeholder, cx);
+                self.input.set_text(value, cx);
+            }
             InputKind::Shortcut { .. } => self.input.set_text("Record hotkey", cx),
+            InputKind::Select { .. } => {
+                self.input.set_masked(false, cx);
+                self.input
+                    .set_placeholder("Use the arrow keys to choose...", cx);
+                self.input.set_text("", cx);
+            }
+            InputKind::Toggle { .. } => {
+                self.input.set_masked(false, cx);
+                self.input.set_placeholder("Press space to toggle...", cx);
+                self.input.set_text("", cx);
+            }
         };
     }
+    fn validate_async(&mut self, value: String, cx: &mut ViewContext<Self>) {
+        // Replacing the task cancels the previous check
+        self.validation = None;
+        if self.inner.async_error.take().is_some() {
+            self.inner.validate();
+        }
+        let Some(validate) = self.inner.async_validate.clone() else {
+            return;
+        };
+        if self.inner.error.is_some() {
+            return;
+        }
+        self.validation = Some(cx.spawn(|view, mut cx| async move {
+            cx.background_executor()
+                .timer(Duration::from_millis(300))
+                .await;
+            let error = validate(value).await;
+            let _ = view.update(&mut cx, |this, cx| {
+                this.inner.async_error = error;
+                this.inner.validate();
+                this.inner.show_error = true;
+                this.validation = None;
+                cx.notify();
+            });
+        }));
+    }
     pub fn on_blur(&mut self, _: &mut ViewContext<Self>) {
         self.inner.show_error = true;
         self.inner.validate();
@@ -241,10 +354,27 @@ impl InputView {
                         password,
                     };
                     self.inner.validate();
+                    self.validate_async(text.clone(), cx);
                 }
                 TextEvent::KeyDown(_) => {}
                 _ => {}
             },
+            InputKind::TextArea {
+                validate,
+                placeholder,
+                ..
+            } => {
+                if let TextEvent::Input { text } = event {
+                    self.inner.kind = InputKind::TextArea {
+                        value: text.clone(),
+                        validate,
+                        placeholder,
+                    };
+                    self.inner.validate();
+                    self.validate_async(text.clone(), cx);
+                    cx.notify();
+                }
+            }
             InputKind::Shortcut { value, .. } => {
                 if let TextEvent::KeyDown(e) = event {
                     self.input.set_text("Record hotkey", cx);
@@ -280,6 +410,42 @@ impl InputView {
                     }
                 }
             }
+            InputKind::Select { options, value } => match event {
+                TextEvent::KeyDown(e) => {
+                    let step = match e.keystroke.key.as_str() {
+                        "right" | "down" => 1,
+                        "left" | "up" => options.len().saturating_sub(1),
+                        _ => 0,
+                    };
+                    if step > 0 && !options.is_empty() {
+                        let index = options
+                            .iter()
+                            .position(|option| option.0.eq(&value))
+                            .unwrap_or(0);
+                        let next = (index + step) % options.len();
+                        self.inner.kind = InputKind::Select {
+                            value: options[next].0.clone(),
+                            options,
+                        };
+                        cx.notify();
+                    }
+                }
+                // Typing doesn't apply to a select
+                TextEvent::Input { text } if !text.is_empty() => {
+                    self.input.set_text("", cx);
+                }
+                _ => {}
+            },
+            InputKind::Toggle { value } => match event {
+                TextEvent::KeyDown(e) if e.keystroke.key.as_str() == "space" => {
+                    self.inner.kind = InputKind::Toggle { value: !value };
+                    cx.notify();
+                }
+                TextEvent::Input { text } if !text.is_empty() => {
+                    self.input.set_text("", cx);
+                }
+                _ => {}
+            },
         }
         if let TextEvent::KeyDown(e) = event {
             if (Shortcut::new("tab").shift().get()).eq(&e.keystroke) {
@@ -338,6 +504,7 @@ impl InputView {
                 focused: false,
                 index,
                 focus_model: focus_model.clone(),
+                validation: None,
             }
         })
     }
@@ -352,10 +519,25 @@ pub enum InputKind {
         password: bool,
         validate: Option<fn(&str) -> Option<&str>>,
     },
+    // Multiline text, new lines are entered with shift-enter
+    TextArea {
+        placeholder: String,
+        value: String,
+        validate: Option<fn(&str) -> Option<&str>>,
+    },
     Shortcut {
         value: Option<Shortcut>,
         tmp: Option<Shortcut>,
     },
+    // One of the (value, label) options, cycled with the arrow keys
+    Select {
+        options: Vec<(String, String)>,
+        value: String,
+    },
+    // On/off switch, toggled with space
+    Toggle {
+        value: bool,
+    },
 }
 
 pub trait SubmitFn: Fn(HashMap<String, Input>, &mut Actions, &mut WindowContext) {
@@ -401,9 +583,14 @@ impl Form {
                 InputView::new(input, context.query.clone(), i, focus_model.clone(), cx)
             })
             .collect();
-        focus_model.update(cx, |_, cx| {
-            cx.notify();
-        });
+        // The observer only reacts to changes, so engage the first field right away
+        if let Some(first) = inputs.first() {
+            first.update(cx, |input, cx| {
+                input.focused = true;
+                input.on_focus(cx);
+                cx.notify();
+            });
+        }
 
         if let Some(inner) = context.actions.inner.upgrade() {
             context.actions.update_local(
@@ -418,15 +605,23 @@ impl Form {
                         move |_, cx| {
                             let mut values = HashMap::<String, Input>::new();
                             let mut error = false;
+                            let mut pending = false;
                             for input in inputs.clone() {
                                 input.update(cx, |this, _| {
                                     if this.inner.error.is_some() {
                                         error = true;
                                     }
+                                    if this.validation.is_some() {
+                                        pending = true;
+                                    }
                                     this.inner.show_error = true;
                                     values.insert(this.inner.id.clone(), this.inner.clone());
                                 })
                             }
+                            if pending {
+                                actions.clone().toast.error("Still validating...", cx);
+                                return;
+                            }
                             if error {
                                 return;
                             }
@@ -441,13 +636,23 @@ impl Form {
             );
         }
 
-        cx.new_view(|_| Self {
-            list: ListState::new(
-                inputs.len(),
-                ListAlignment::Top,
-                Pixels(100.0),
-                move |i, _| div().child(inputs[i].clone()).py_2().into_any_element(),
-            ),
+        cx.new_view(|cx| {
+            for (i, input) in inputs.iter().enumerate() {
+                // Rows like text areas change their height while editing
+                cx.observe(input, move |this: &mut Self, _, cx| {
+                    this.list.splice(i..i + 1, 1);
+                    cx.notify();
+                })
+                .detach();
+            }
+            Self {
+                list: ListState::new(
+                    inputs.len(),
+                    ListAlignment::Top,
+                    Pixels(100.0),
+                    move |i, _| div().child(inputs[i].clone()).py_2().into_any_element(),
+                ),
+            }
         })
     }
 }
@@ -0,0 +1,157 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{collections::HashMap, rc::Rc, sync::OnceLock};
+
+use bonsaidb::{
+    core::schema::{Collection, SerializedCollection},
+    local::Database,
+};
+use gpui::*;
+use jiff::Timestamp;
+use log::error;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{Db, Migrate};
+
+use super::{nucleo::fuzzy_scores, Item};
+
+pub static DEFAULT_FRECENCY_WEIGHT: f32 = 0.5;
+
+// After this many seconds a launch only counts half as much
+static HALF_LIFE: f32 = 60.0 * 60.0 * 24.0 * 7.0;
+// Entries older than this barely boost anything anymore, so they are dropped
+static MAX_AGE: i64 = 60 * 60 * 24 * 7 * 8;
+// Only the highest scoring entries are kept beyond this
+static MAX_ENTRIES: usize = 1000;
+
+fn db() -> &'static Database {
+    static DB: OnceLock<Database> = OnceLock::new();
+    DB.get_or_init(Db::init_collection::<Frecency>)
+}
+
+#[derive(Serialize, Deserialize, Collection, Clone, Debug)]
+#[collection(name = "list.frecency")]
+pub struct Frecency {
+    #[natural_id]
+    id: u64,
+    count: u32,
+    last: Timestamp,
+}
+
+impl Migrate for Frecency {}
+
+// Scores of all entries, loaded on first use and dropped whenever a launch is recorded
+#[derive(Clone)]
+pub struct FrecencyModel {
+    pub inner: Model<Option<Rc<HashMap<u64, f32>>>>,
+}
+
+impl FrecencyModel {
+    pub fn init(cx: &mut AppContext) {
+        let inner = cx.new_model(|_| None);
+        cx.set_global(Self { inner });
+    }
+}
+
+impl Global for FrecencyModel {}
+
+impl Frecency {
+    pub fn record(id: u64, cx: &mut AppContext) {
+        let mut entry = Self::get(&id, db())
+            .ok()
+            .flatten()
+            .map(|doc| doc.contents)
+            .unwrap_or(Self {
+                id,
+                count: 0,
+                last: Timestamp::now(),
+            });
+        entry.count += 1;
+        entry.last = Timestamp::now();
+        if let Err(err) = entry.overwrite_into(&id, db()) {
+            error!("Failed to record launch: {:?}", err.error);
+        }
+        if let Err(err) = Self::prune() {
+            error!("Failed to prune launch history: {:?}", err);
+        }
+        let model = cx.global::<FrecencyModel>().inner.clone();
+        model.update(cx, |scores, cx| {
+            *scores = None;
+            cx.notify();
+        });
+    }
+    // Ages out stale entries and caps the rest, so the history doesn't grow with every item ever launched
+    fn prune() -> anyhow::Result<()> {
+        let now = Timestamp::now().as_second();
+        let mut entries = Self::all(db()).query()?;
+        entries.sort_by(|a, b| b.contents.score().total_cmp(&a.contents.score()));
+        for (i, entry) in entries.iter().enumerate() {
+            if i >= MAX_ENTRIES || now - entry.contents.last.as_second() > MAX_AGE {
+                entry.delete(db())?;
+            }
+        }
+        Ok(())
+    }
+    fn score(&self) -> f32 {
+        let age = (Timestamp::now().as_second() - self.last.as_second()).max(0) as f32;
+        (1.0 + self.count as f32).ln() * 0.5_f32.powf(age / HALF_LIFE)
+    }
+    pub fn scores(cx: &mut AppContext) -> Rc<HashMap<u64, f32>> {
+        let model = cx.global::<FrecencyModel>().inner.clone();
+        if let Some(scores) = model.read(cx).clone() {
+            return scores;
+        }
+        let scores: Rc<HashMap<u64, f32>> = Rc::new(
+            Self::all(db())
+                .query()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|doc| (doc.contents.id, doc.contents.score()))
+                .collect(),
+        );
+        model.update(cx, |cached, _| *cached = Some(scores.clone()));
+        scores
+    }
+}
+
+// Fuzzy match, but boost the score of items that have been launched frequently and recently.
+// Items that have never been launched keep their plain fuzzy score.
+pub fn fuzzy_match_frecency(
+    pattern: &str,
+    items: Vec<Item>,
+    weight: f32,
+    cx: &mut AppContext,
+) -> Vec<Item> {
+    let items = fuzzy_scores(pattern, items, false);
+    let frecency = if weight > 0.0 {
+        Frecency::scores(cx)
+    } else {
+        Rc::default()
+    };
+    let mut items: Vec<(Item, f32)> = items
+        .into_iter()
+        .map(|(item, score)| {
+            let boost = frecency
+                .get(&item.id)
+                .map(|f| 1.0 + weight * f)
+                .unwrap_or(1.0);
+            (item, score as f32 * boost)
+        })
+        .collect();
+    // Exact alias matches always come first
+    items.sort_by(|a, b| {
+        b.0.is_alias(pattern)
+            .cmp(&a.0.is_alias(pattern))
+            .then(b.1.total_cmp(&a.1))
+    });
+    items.into_iter().map(|item| item.0).collect()
+}
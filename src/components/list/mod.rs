@@ -10,28 +10,50 @@
  */
 
 use std::{
+    cell::Cell,
     collections::{hash_map::DefaultHasher, HashMap},
     hash::{Hash, Hasher},
+    ops::Range,
     rc::Rc,
     sync::mpsc::channel,
     time::Duration,
 };
 
+pub mod frecency;
 pub mod nucleo;
 
 use gpui::*;
 use log::debug;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    db::db,
     loader::Loader,
     query::{TextEvent, TextInputWeak},
-    state::{Action, ActionsModel, Shortcut, StateItem, StateViewContext},
+    state::{Action, Actions, ActionsModel, Shortcut, StateItem, StateViewContext},
     theme::Theme,
 };
 
-use nucleo::fuzzy_match;
+use frecency::{fuzzy_match_frecency, Frecency};
 
-use super::shared::{Img, ImgSource};
+use super::shared::{Icon, Img, ImgMask, ImgSize, ImgSource};
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListSettings {
+    // Move the selection with j/k while the query is empty
+    pub vim_navigation: bool,
+}
+
+impl ListSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("list").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("list", self)?;
+        Ok(())
+    }
+}
 
 #[derive(Clone, IntoElement)]
 pub enum Accessory {
@@ -102,10 +124,39 @@ impl ListItem {
             accessories,
         }
     }
-}
+    fn render_title(&self, matches: Option<&[usize]>, theme: &Theme) -> AnyElement {
+        let Some(matches) = matches else {
+            return self.title.clone().into_any_element();
+        };
+        let highlight = HighlightStyle {
+            color: Some(theme.accent()),
+            font_weight: Some(FontWeight::BOLD),
+            ..HighlightStyle::default()
+        };
 
-impl ItemComponent for ListItem {
-    fn render(&self, _selected: bool, cx: &WindowContext) -> AnyElement {
+        // Match indices are character based, so they have to be mapped to byte ranges for multibyte titles
+        let mut highlights: Vec<(Range<usize>, HighlightStyle)> = vec![];
+        for (i, (start, c)) in self.title.char_indices().enumerate() {
+            if matches.binary_search(&i).is_err() {
+                continue;
+            }
+            let end = start + c.len_utf8();
+            match highlights.last_mut() {
+                Some((range, _)) if range.end == start => range.end = end,
+                _ => highlights.push((start..end, highlight)),
+            }
+        }
+        let style = TextStyle {
+            color: theme.text,
+            font_family: theme.font_sans.clone(),
+            font_weight: FontWeight::MEDIUM,
+            ..TextStyle::default()
+        };
+        StyledText::new(self.title.clone())
+            .with_highlights(&style, highlights)
+            .into_any_element()
+    }
+    fn render_inner(&self, matches: Option<&[usize]>, cx: &WindowContext) -> AnyElement {
         let theme = cx.global::<Theme>();
         let el = if let Some(img) = &self.img {
             div().child(div().mr_4().child(img.clone()))
@@ -119,7 +170,7 @@ impl ItemComponent for ListItem {
         .child(
             div()
                 .text_sm()
-                .child(self.title.clone())
+                .child(self.render_title(matches, theme))
                 .font_weight(FontWeight::MEDIUM),
         );
         let el = if let Some(subtitle) = &self.subtitle {
@@ -143,8 +194,45 @@ impl ItemComponent for ListItem {
     }
 }
 
+impl ItemComponent for ListItem {
+    fn render(&self, _selected: bool, cx: &WindowContext) -> AnyElement {
+        self.render_inner(None, cx)
+    }
+    fn render_matches(
+        &self,
+        _selected: bool,
+        keyword: &str,
+        indices: &[usize],
+        cx: &WindowContext,
+    ) -> AnyElement {
+        // Keyword-only matches don't say anything about the title, so they render plain
+        if *self.title == *keyword {
+            self.render_inner(Some(indices), cx)
+        } else {
+            self.render_inner(None, cx)
+        }
+    }
+    fn title(&self) -> Option<SharedString> {
+        Some(self.title.clone())
+    }
+}
+
 pub trait ItemComponent {
     fn render(&self, selected: bool, cx: &WindowContext) -> AnyElement;
+    // Called instead of `render` when the fuzzy matcher reported which characters of `keyword` matched
+    fn render_matches(
+        &self,
+        selected: bool,
+        _keyword: &str,
+        _indices: &[usize],
+        cx: &WindowContext,
+    ) -> AnyElement {
+        self.render(selected, cx)
+    }
+    // Matched alongside the keywords, so items are found by what they show
+    fn title(&self) -> Option<SharedString> {
+        None
+    }
 }
 
 pub struct ItemBuilder {
@@ -153,24 +241,32 @@ pub struct ItemBuilder {
     actions: Vec<Action>,
     weight: Option<u16>,
     keywords: Vec<SharedString>,
+    aliases: Vec<SharedString>,
     component: Rc<dyn ItemComponent>,
     preset: ItemPreset,
+    section: Option<SharedString>,
     meta: Option<AnyModel>,
 }
 
+// The id an item built from this id ends up with, e.g. to look up its frecency
+pub fn item_id(id: impl Hash) -> u64 {
+    let mut s = DefaultHasher::new();
+    id.hash(&mut s);
+    s.finish()
+}
+
 impl ItemBuilder {
     pub fn new(id: impl Hash, component: impl ItemComponent + 'static) -> Self {
-        let mut s = DefaultHasher::new();
-        id.hash(&mut s);
-        let id = s.finish();
         Self {
-            id,
+            id: item_id(id),
             preview: None,
             actions: vec![],
             weight: None,
             keywords: vec![],
+            aliases: vec![],
             meta: None,
             preset: ItemPreset::Default,
+            section: None,
             component: Rc::new(component),
         }
     }
@@ -190,6 +286,11 @@ impl ItemBuilder {
         self.actions = actions;
         self
     }
+    // Typing one of these exactly ranks the item first
+    pub fn aliases(mut self, aliases: Vec<impl ToString>) -> Self {
+        self.aliases = aliases.into_iter().map(|a| a.to_string().into()).collect();
+        self
+    }
     pub fn weight(mut self, weight: u16) -> Self {
         self.weight = Some(weight);
         self
@@ -198,6 +299,10 @@ impl ItemBuilder {
         self.preset = preset;
         self
     }
+    pub fn section(mut self, section: Option<impl ToString>) -> Self {
+        self.section = section.map(|s| s.to_string().into());
+        self
+    }
     pub fn build(self) -> Item {
         Item {
             id: self.id,
@@ -205,10 +310,14 @@ impl ItemBuilder {
             actions: self.actions,
             weight: self.weight,
             keywords: self.keywords,
+            aliases: self.aliases,
             selected: false,
+            checked: false,
+            matches: None,
             component: self.component,
             meta: self.meta,
             preset: self.preset,
+            section: self.section,
         }
     }
 }
@@ -228,9 +337,13 @@ pub struct Item {
     actions: Vec<Action>,
     weight: Option<u16>,
     keywords: Vec<SharedString>,
+    aliases: Vec<SharedString>,
     component: Rc<dyn ItemComponent>,
     selected: bool,
+    checked: bool,
+    matches: Option<(SharedString, Vec<usize>)>,
     preset: ItemPreset,
+    section: Option<SharedString>,
     pub meta: Option<AnyModel>,
 }
 
@@ -245,42 +358,91 @@ impl Item {
     pub fn get_keywords(&self) -> Vec<SharedString> {
         self.keywords.clone()
     }
+    pub fn is_alias(&self, query: &str) -> bool {
+        let query = query.trim();
+        !query.is_empty()
+            && self
+                .aliases
+                .iter()
+                .any(|alias| alias.to_lowercase() == query.to_lowercase())
+    }
+    fn render_component(&self, cx: &WindowContext) -> AnyElement {
+        match &self.matches {
+            Some((keyword, indices)) => {
+                self.component
+                    .render_matches(self.selected, keyword, indices, cx)
+            }
+            None => self.component.render(self.selected, cx),
+        }
+    }
 }
 
 pub trait Preview: Fn(&mut WindowContext) -> StateItem + 'static {}
 impl<F> Preview for F where F: Fn(&mut WindowContext) -> StateItem + 'static {}
 
+fn section_header(section: SharedString, theme: &Theme) -> Div {
+    div()
+        .px_2()
+        .pt_2()
+        .pb_1()
+        .text_xs()
+        .font_weight(FontWeight::SEMIBOLD)
+        .text_color(theme.subtext0)
+        .child(section)
+}
+
 impl RenderOnce for Item {
     fn render(self, cx: &mut WindowContext) -> impl IntoElement {
         match self.preset {
-            ItemPreset::Plain => self.component.render(self.selected, cx),
+            ItemPreset::Plain => self.render_component(cx),
             ItemPreset::Default => {
                 let theme = cx.global::<Theme>();
                 let mut bg_hover = theme.mantle;
                 bg_hover.fade_out(0.5);
                 if self.selected {
-                    div().border_color(theme.crust).bg(theme.mantle)
+                    div()
+                        .border_color(theme.accent.unwrap_or(theme.crust))
+                        .bg(theme.selection())
                 } else {
                     div().hover(|s| s.bg(bg_hover))
                 }
                 .p_2()
                 .border_1()
                 .rounded_xl()
-                .child(self.component.render(self.selected, cx))
+                .flex()
+                .items_center()
+                .child(div().flex_1().child(self.render_component(cx)))
+                .children(self.checked.then(|| {
+                    div().ml_2().child(
+                        Img::default()
+                            .icon(Icon::CheckCircle2)
+                            .icon_color(theme.green)
+                            .mask(ImgMask::None)
+                            .size(ImgSize::SM),
+                    )
+                }))
                 .into_any_element()
             }
         }
     }
 }
 
+// Used for page up/down until the list has been laid out
+static DEFAULT_PAGE_SIZE: usize = 8;
+
 type ScrollHandler = Option<Box<dyn FnMut(&ListScrollEvent, &mut WindowContext)>>;
+type SelectHandler = Option<Box<dyn Fn(Option<&Item>, &mut WindowContext)>>;
 
 pub struct ListBuilder {
     reverse: bool,
     update_actions: bool,
     interval: Option<Duration>,
+    filter_debounce: Duration,
+    frecency: f32,
+    multi_select: bool,
     filter: Box<dyn FilterList>,
     scroll_handler: ScrollHandler,
+    select_handler: SelectHandler,
 }
 
 impl ListBuilder {
@@ -289,10 +451,14 @@ impl ListBuilder {
             reverse: false,
             update_actions: true,
             interval: None,
+            filter_debounce: Duration::from_millis(60),
+            frecency: 0.0,
+            multi_select: false,
             scroll_handler: None,
+            select_handler: None,
             filter: Box::new(|this, cx| {
                 let text = this.query.get_text(cx);
-                fuzzy_match(&text, this.items_all.clone(), false)
+                fuzzy_match_frecency(&text, this.items_all.clone(), this.frecency, cx)
             }),
         }
     }
@@ -304,10 +470,25 @@ impl ListBuilder {
         self.reverse = true;
         self
     }
+    // Allow checking several items with cmd+shift+click or cmd+shift+space to run bulk actions on them
+    pub fn multi_select(mut self) -> Self {
+        self.multi_select = true;
+        self
+    }
     pub fn interval(mut self, interval: Duration) -> Self {
         self.interval = Some(interval);
         self
     }
+    // Coalesce query input within this window into one filter pass, zero disables it
+    pub fn filter_debounce(mut self, debounce: Duration) -> Self {
+        self.filter_debounce = debounce;
+        self
+    }
+    // How much launch history boosts the fuzzy score, the default 0.0 disables tracking entirely
+    pub fn frecency(mut self, weight: f32) -> Self {
+        self.frecency = weight;
+        self
+    }
     pub fn filter(mut self, filter: impl FilterList + 'static) -> Self {
         self.filter = Box::new(filter);
         self
@@ -319,6 +500,14 @@ impl ListBuilder {
         self.scroll_handler = Some(Box::new(handler));
         self
     }
+    // Called whenever the selected item changes
+    pub fn select_handler(
+        mut self,
+        handler: impl Fn(Option<&Item>, &mut WindowContext) + 'static,
+    ) -> Self {
+        self.select_handler = Some(Box::new(handler));
+        self
+    }
     pub fn build(
         self,
         update: impl UpdateList + 'static,
@@ -329,9 +518,13 @@ impl ListBuilder {
             Box::new(update),
             self.filter,
             self.interval,
+            self.filter_debounce,
+            self.frecency,
+            self.multi_select,
             self.update_actions,
             self.reverse,
             self.scroll_handler,
+            self.select_handler,
             context,
             cx,
         )
@@ -353,12 +546,18 @@ impl<F> FilterList for F where F: Fn(&mut List, &mut ViewContext<List>) -> Vec<I
 pub struct List {
     state: ListState,
     selected: Model<u64>,
+    checked: Model<Vec<Item>>,
+    multi_select: bool,
+    viewport: Rc<Cell<Pixels>>,
     pub actions: ActionsModel,
     pub items_all: Vec<Item>,
     pub items: Model<Vec<Item>>,
     pub query: TextInputWeak,
     pub update: Box<dyn UpdateList>,
     pub filter: Box<dyn FilterList>,
+    pub frecency: f32,
+    filter_debounce: Duration,
+    filter_pending: u64,
     preview: Option<(u64, f32, StateItem)>,
     reverse: bool,
 }
@@ -377,9 +576,20 @@ impl Render for List {
             })
             .unwrap_or((relative(1.0), div()));
 
-        if self.items.read(cx).is_empty() {
+        let items = self.items.read(cx);
+        if items.is_empty() {
             div()
         } else {
+            // Pin the header of the topmost section once its own header row scrolls out of view
+            let top = self.state.logical_scroll_top();
+            let sticky = items.get(top.item_ix).and_then(|item| {
+                let first = top.item_ix == 0 || items[top.item_ix - 1].section != item.section;
+                if first && top.offset_in_item <= Pixels(0.0) {
+                    return None;
+                }
+                item.section.clone()
+            });
+            let theme = cx.global::<Theme>();
             div()
                 .size_full()
                 .flex()
@@ -388,7 +598,27 @@ impl Render for List {
                         .w(width)
                         .h_full()
                         .relative()
-                        .child(list(self.state.clone()).size_full().pr_1()),
+                        .child(list(self.state.clone()).size_full().pr_1())
+                        // Measures the viewport for page up/down
+                        .child(
+                            canvas(
+                                {
+                                    let viewport = self.viewport.clone();
+                                    move |bounds, _| viewport.set(bounds.size.height)
+                                },
+                                |_, _, _| {},
+                            )
+                            .absolute()
+                            .size_full(),
+                        )
+                        .children(sticky.map(|section| {
+                            section_header(section, theme)
+                                .absolute()
+                                .top_0()
+                                .left_0()
+                                .right_1()
+                                .bg(theme.base)
+                        })),
                 )
                 .child(preview)
         }
@@ -396,6 +626,17 @@ impl Render for List {
 }
 
 impl List {
+    // How many items fit in the viewport, going by the height of the topmost one
+    fn page_size(&self) -> usize {
+        let viewport = self.viewport.get();
+        let top = self.state.logical_scroll_top().item_ix;
+        match self.state.bounds_for_item(top) {
+            Some(bounds) if bounds.size.height.0 > 0.0 && viewport.0 > 0.0 => {
+                ((viewport.0 / bounds.size.height.0) as usize).max(1)
+            }
+            _ => DEFAULT_PAGE_SIZE,
+        }
+    }
     pub fn up(&mut self, cx: &mut ViewContext<Self>) {
         if !self.query.has_focus(cx) {
             return;
@@ -409,18 +650,7 @@ impl List {
         } else {
             0
         };
-
-        self.selected.update(cx, |this, cx| {
-            *this = self
-                .items
-                .read(cx)
-                .get(index)
-                .map(|item| item.id)
-                .unwrap_or(0);
-
-            cx.notify();
-        });
-        self.state.scroll_to_reveal_item(index);
+        self.select_index(index, cx);
     }
     pub fn down(&mut self, cx: &mut ViewContext<Self>) {
         if !self.query.has_focus(cx) {
@@ -436,6 +666,56 @@ impl List {
         } else {
             0
         };
+        self.select_index(index, cx);
+    }
+    pub fn page_up(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.query.has_focus(cx) {
+            return;
+        }
+        let index = self
+            .selected(cx)
+            .map(|(index, _)| index.saturating_sub(self.page_size()))
+            .unwrap_or(0);
+        self.select_index(index, cx);
+    }
+    pub fn page_down(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.query.has_focus(cx) {
+            return;
+        }
+        let last = self.items.read(cx).len().saturating_sub(1);
+        let index = self
+            .selected(cx)
+            .map(|(index, _)| (index + self.page_size()).min(last))
+            .unwrap_or(0);
+        self.select_index(index, cx);
+    }
+    // Home and end jump to where the list starts and ends, which is flipped for reversed lists
+    pub fn home(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.query.has_focus(cx) {
+            return;
+        }
+        let index = match self.reverse {
+            false => 0,
+            true => self.items.read(cx).len().saturating_sub(1),
+        };
+        self.select_index(index, cx);
+    }
+    pub fn end(&mut self, cx: &mut ViewContext<Self>) {
+        if !self.query.has_focus(cx) {
+            return;
+        }
+        let index = match self.reverse {
+            false => self.items.read(cx).len().saturating_sub(1),
+            true => 0,
+        };
+        self.select_index(index, cx);
+    }
+    // Selects and reveals the bottom item, which for reversed lists is the newest one
+    pub fn scroll_to_bottom(&mut self, cx: &mut ViewContext<Self>) {
+        let index = self.items.read(cx).len().saturating_sub(1);
+        self.select_index(index, cx);
+    }
+    fn select_index(&mut self, index: usize, cx: &mut ViewContext<Self>) {
         self.selected.update(cx, |this, cx| {
             *this = self
                 .items
@@ -447,6 +727,17 @@ impl List {
         });
         self.state.scroll_to_reveal_item(index);
     }
+    // Selects the item with the given id, returns false if it isn't listed
+    pub fn select_id(&mut self, id: impl Hash, cx: &mut ViewContext<Self>) -> bool {
+        let mut s = DefaultHasher::new();
+        id.hash(&mut s);
+        let id = s.finish();
+        let Some(index) = self.items.read(cx).iter().position(|item| item.id.eq(&id)) else {
+            return false;
+        };
+        self.select_index(index, cx);
+        true
+    }
     pub fn selected(&self, cx: &AppContext) -> Option<(usize, Item)> {
         let id = self.selected.read(cx);
 
@@ -478,21 +769,54 @@ impl List {
             }
         }
     }
-    pub fn filter(&mut self, _no_scroll: bool, cx: &mut ViewContext<Self>) {
+    // Keep items of the same section together, in the order the sections were first listed
+    fn group_sections(&self, items: Vec<Item>) -> Vec<Item> {
+        if items.iter().all(|item| item.section.is_none()) {
+            return items;
+        }
+        let mut sections: Vec<Option<SharedString>> = vec![];
+        for item in self.items_all.iter().chain(items.iter()) {
+            if !sections.contains(&item.section) {
+                sections.push(item.section.clone());
+            }
+        }
+        let mut groups: Vec<Vec<Item>> = vec![vec![]; sections.len()];
+        for item in items {
+            let i = sections
+                .iter()
+                .position(|section| section.eq(&item.section))
+                .unwrap_or_default();
+            groups[i].push(item);
+        }
+        groups.into_iter().flatten().collect()
+    }
+    // Returns whether the selected item was kept, otherwise the selection has been reset already
+    pub fn filter(&mut self, _no_scroll: bool, cx: &mut ViewContext<Self>) -> bool {
         let filter_fn = std::mem::replace(&mut self.filter, Box::new(|_, _| vec![]));
-        let items = filter_fn(self, cx);
+        let items = self.group_sections(filter_fn(self, cx));
         self.filter = filter_fn;
 
         let mut scroll = self.state.logical_scroll_top();
+        let selected = *self.selected.read(cx);
+        let visible = scroll.item_ix..scroll.item_ix + self.page_size();
 
         self.state.reset(items.len());
         self.items.update(cx, |this, cx| {
-            // Determine the ideal scroll position if new elements are added
-            if let Some(new_index) = this
-                .get(scroll.item_ix)
-                .and_then(|scroll_item| items.iter().position(|item| item.id.eq(&scroll_item.id)))
-            {
-                scroll.item_ix = new_index;
+            let old_index = this.iter().position(|item| item.id.eq(&selected));
+            let new_index = items.iter().position(|item| item.id.eq(&selected));
+            match (old_index, new_index) {
+                // Keep the selected item at the same spot on screen, even if it moved in the list
+                (Some(old_index), Some(new_index)) if visible.contains(&old_index) => {
+                    scroll.item_ix = new_index.saturating_sub(old_index - scroll.item_ix);
+                }
+                // Otherwise determine the ideal scroll position if new elements are added
+                _ => {
+                    if let Some(new_index) = this.get(scroll.item_ix).and_then(|scroll_item| {
+                        items.iter().position(|item| item.id.eq(&scroll_item.id))
+                    }) {
+                        scroll.item_ix = new_index;
+                    }
+                }
             }
 
             *this = items;
@@ -503,10 +827,39 @@ impl List {
 
         self.state.scroll_to(scroll);
 
+        // The selection follows its item by id, it only resets once the item is gone
         if self.selected(cx).is_none() {
             self.reset_selection(cx);
+            return false;
+        }
+        true
+    }
+    // A new query selects the best match, unless filtering reset the selection anyway
+    fn filter_query(&mut self, cx: &mut ViewContext<Self>) {
+        if self.filter(true, cx) {
+            self.reset_selection(cx);
         }
     }
+    fn filter_debounced(&mut self, cx: &mut ViewContext<Self>) {
+        if self.filter_debounce.is_zero() {
+            self.filter_query(cx);
+            return;
+        }
+        // Only the most recent input gets to filter
+        self.filter_pending += 1;
+        let pending = self.filter_pending;
+        let debounce = self.filter_debounce;
+        cx.spawn(|view, mut cx| async move {
+            cx.background_executor().timer(debounce).await;
+            let _ = view.update(&mut cx, |this: &mut Self, cx| {
+                if this.filter_pending != pending {
+                    return;
+                }
+                this.filter_query(cx);
+            });
+        })
+        .detach();
+    }
     pub fn reset_selection(&mut self, cx: &mut ViewContext<Self>) {
         self.items.update(cx, |items, cx| {
             if items.is_empty() {
@@ -531,14 +884,19 @@ impl List {
         update: Box<dyn UpdateList>,
         filter: Box<dyn FilterList>,
         interval: Option<Duration>,
+        filter_debounce: Duration,
+        frecency: f32,
+        multi_select: bool,
         update_actions: bool,
         reverse: bool,
         scroll_handler: ScrollHandler,
+        select_handler: SelectHandler,
         context: &mut StateViewContext,
         cx: &mut WindowContext,
     ) -> View<Self> {
         let (selection_sender, r) = channel::<u64>();
         let selected = cx.new_model(|_| 0);
+        let checked: Model<Vec<Item>> = cx.new_model(|_| vec![]);
         let items: Model<Vec<Item>> = cx.new_model(|_| vec![]);
         let mut list = Self {
             state: ListState::new(
@@ -551,13 +909,20 @@ impl List {
                 Pixels(20.0),
                 {
                     let selected = selected.clone();
+                    let checked = checked.clone();
                     let items = items.clone();
                     let sender = selection_sender.clone();
                     let actions = context.actions.clone();
                     move |i, cx| {
-                        let mut item = items.read(cx)[i].clone();
+                        let all = items.read(cx);
+                        let mut item = all[i].clone();
+                        // Sections are rendered as a header above the first item of each group
+                        let header = item.section.clone().filter(|section| {
+                            i == 0 || all[i - 1].section.as_ref() != Some(section)
+                        });
                         let selected = item.id.eq(selected.read(cx));
                         item.selected = selected;
+                        item.checked = checked.read(cx).iter().any(|c| c.id.eq(&item.id));
                         let action = item.actions.first().cloned();
                         let actions = actions.inner.upgrade();
                         if actions.is_none() {
@@ -566,43 +931,74 @@ impl List {
                         let actions = actions.unwrap().read(cx).clone();
                         let sender = sender.clone();
                         let id = item.id;
-                        div()
-                            .child(item)
-                            .on_mouse_down(MouseButton::Left, {
-                                move |ev, cx| match ev.click_count {
-                                    1 => {
-                                        let _ = sender.send(id);
-                                    }
-                                    2 => {
-                                        let mut actions = actions.clone();
-                                        if let Some(action) = &action {
-                                            (action.action)(&mut actions, cx);
+                        let checked = checked.clone();
+                        let theme = cx.global::<Theme>();
+                        let row = div().child(item.clone()).on_mouse_down(MouseButton::Left, {
+                            move |ev, cx| match ev.click_count {
+                                _ if multi_select && is_toggle(&ev.modifiers) => {
+                                    toggle_checked(&checked, item.clone(), cx);
+                                }
+                                1 => {
+                                    let _ = sender.send(id);
+                                }
+                                2 => {
+                                    let mut actions = actions.clone();
+                                    if let Some(action) = &action {
+                                        if frecency > 0.0 {
+                                            Frecency::record(id, cx);
                                         }
+                                        (action.action)(&mut actions, cx);
                                     }
-                                    _ => {}
                                 }
-                            })
-                            .into_any_element()
+                                _ => {}
+                            }
+                        });
+                        match header {
+                            Some(section) => div()
+                                .child(section_header(section, theme))
+                                .child(row)
+                                .into_any_element(),
+                            None => row.into_any_element(),
+                        }
                     }
                 },
             ),
             selected,
+            checked,
+            multi_select,
+            viewport: Rc::new(Cell::new(Pixels(0.0))),
             items_all: vec![],
             items,
             actions: context.actions.clone(),
             query: context.query.clone(),
             update,
             filter,
+            frecency,
+            filter_debounce,
+            filter_pending: 0,
             preview: None,
             reverse,
         };
-        if let Some(scroll_handler) = scroll_handler {
-            list.state.set_scroll_handler(scroll_handler);
-        };
+        list.state.set_scroll_handler({
+            let selected = list.selected.clone();
+            let items = list.items.clone();
+            let mut scroll_handler = scroll_handler;
+            move |ev, cx| {
+                if !ev.visible_range.is_empty() {
+                    follow_scroll(&selected, &items, &ev.visible_range, cx);
+                }
+                if let Some(scroll_handler) = scroll_handler.as_mut() {
+                    scroll_handler(ev, cx);
+                }
+            }
+        });
 
         let update_receiver = context.update_receiver.clone();
         let view = cx.new_view(move |cx| {
             cx.observe(&list.selected, move |this: &mut List, _, cx| {
+                if let Some(select_handler) = &select_handler {
+                    select_handler(this.selected(cx).map(|(_, item)| item).as_ref(), cx);
+                }
                 if let Some((_, selected)) = this.selected(cx) {
                     let preview = if let Some(preview) = selected.preview.as_ref() {
                         if !selected
@@ -617,8 +1013,43 @@ impl List {
                         None
                     };
                     if update_actions {
+                        let mut actions = selected.actions.clone();
+                        if this.multi_select {
+                            for action in actions.iter_mut() {
+                                let inner = action.action.clone();
+                                let bulk = action.bulk.clone();
+                                let checked = this.checked.clone();
+                                // Bulk actions consume the checked items, any other action just clears them
+                                action.action = Rc::new(
+                                    move |actions: &mut Actions, cx: &mut WindowContext| {
+                                        let items = checked.update(cx, |this, cx| {
+                                            cx.notify();
+                                            std::mem::take(this)
+                                        });
+                                        match &bulk {
+                                            Some(bulk) if !items.is_empty() => {
+                                                bulk(items, actions, cx)
+                                            }
+                                            _ => inner(actions, cx),
+                                        }
+                                    },
+                                );
+                            }
+                        }
+                        if this.frecency > 0.0 {
+                            if let Some(action) = actions.first_mut() {
+                                let inner = action.action.clone();
+                                let id = selected.id;
+                                action.action = Rc::new(
+                                    move |actions: &mut Actions, cx: &mut WindowContext| {
+                                        Frecency::record(id, cx);
+                                        inner(actions, cx);
+                                    },
+                                );
+                            }
+                        }
                         this.actions.update_local(
-                            selected.actions.clone(),
+                            actions,
                             preview.clone().map(|p| p.2),
                             selected.meta.clone(),
                             cx,
@@ -634,6 +1065,7 @@ impl List {
                 cx.notify();
             })
             .detach();
+            cx.observe(&list.checked, |_, _, cx| cx.notify()).detach();
 
             cx.spawn(|view, mut cx| async move {
                 let mut last = std::time::Instant::now();
@@ -675,13 +1107,14 @@ impl List {
         let clone = view.clone();
 
         if let Some(query) = &context.query.view.upgrade() {
-            cx.subscribe(query, move |_subscriber, emitter: &TextEvent, cx| {
+            let vim = ListSettings::get().vim_navigation;
+            query.update(cx, |query, _| query.navigation_keys = vim);
+            cx.subscribe(query, move |subscriber, emitter: &TextEvent, cx| {
                 //let clone = clone.clone();
                 match emitter {
                     TextEvent::Input { text: _ } => {
                         clone.update(cx, |this, cx| {
-                            this.filter(true, cx);
-                            this.reset_selection(cx);
+                            this.filter_debounced(cx);
                         });
                     }
                     TextEvent::KeyDown(ev) => match ev.keystroke.key.as_str() {
@@ -695,6 +1128,69 @@ impl List {
                                 this.down(cx);
                             });
                         }
+                        "n" | "p" if only_control(&ev.keystroke.modifiers) => {
+                            clone.update(cx, |this, cx| {
+                                if ev.keystroke.key == "n" {
+                                    this.down(cx);
+                                } else {
+                                    this.up(cx);
+                                }
+                            });
+                        }
+                        "j" | "k"
+                            if vim
+                                && !ev.keystroke.modifiers.modified()
+                                && subscriber.read(cx).text.is_empty() =>
+                        {
+                            clone.update(cx, |this, cx| {
+                                if ev.keystroke.key == "j" {
+                                    this.down(cx);
+                                } else {
+                                    this.up(cx);
+                                }
+                            });
+                        }
+                        "pageup" => {
+                            clone.update(cx, |this, cx| {
+                                this.page_up(cx);
+                            });
+                        }
+                        "pagedown" => {
+                            clone.update(cx, |this, cx| {
+                                this.page_down(cx);
+                            });
+                        }
+                        // Home/End move the query cursor while there is text to move through
+                        "home" | "end"
+                            if is_jump(&ev.keystroke.modifiers)
+                                || subscriber.read(cx).text.is_empty() =>
+                        {
+                            clone.update(cx, |this, cx| {
+                                if ev.keystroke.key == "home" {
+                                    this.home(cx);
+                                } else {
+                                    this.end(cx);
+                                }
+                            });
+                        }
+                        "escape" => {
+                            clone.update(cx, |this, cx| {
+                                this.checked.update(cx, |this, cx| {
+                                    this.clear();
+                                    cx.notify();
+                                });
+                            });
+                        }
+                        "space" if is_toggle(&ev.keystroke.modifiers) => {
+                            clone.update(cx, |this, cx| {
+                                if !this.multi_select {
+                                    return;
+                                }
+                                if let Some((_, item)) = this.selected(cx) {
+                                    toggle_checked(&this.checked, item, cx);
+                                }
+                            });
+                        }
                         _ => {}
                     },
                     _ => {}
@@ -706,6 +1202,68 @@ impl List {
     }
 }
 
+fn is_jump(modifiers: &Modifiers) -> bool {
+    #[cfg(target_os = "macos")]
+    let m = modifiers.platform;
+    #[cfg(not(target_os = "macos"))]
+    let m = modifiers.control;
+    m
+}
+
+fn is_toggle(modifiers: &Modifiers) -> bool {
+    #[cfg(target_os = "macos")]
+    let m = modifiers.platform;
+    #[cfg(not(target_os = "macos"))]
+    let m = modifiers.control;
+    m && modifiers.shift
+}
+
+// Keeps the selection in view while scrolling with the mouse wheel or trackpad
+fn follow_scroll(
+    selected: &Model<u64>,
+    items: &Model<Vec<Item>>,
+    visible: &Range<usize>,
+    cx: &mut WindowContext,
+) {
+    let items = items.read(cx);
+    let Some(index) = items.iter().position(|item| item.id.eq(selected.read(cx))) else {
+        return;
+    };
+    // Items cut off at the edges don't count as visible
+    let visible = if visible.len() > 2 {
+        visible.start + 1..visible.end - 1
+    } else {
+        visible.clone()
+    };
+    let target = index.clamp(visible.start, visible.end - 1);
+    if target == index {
+        return;
+    }
+    let Some(id) = items.get(target).map(|item| item.id) else {
+        return;
+    };
+    selected.update(cx, |this, cx| {
+        *this = id;
+        cx.notify();
+    });
+}
+
+// Emacs-style ctrl-n/ctrl-p, which the query editor leaves unbound
+fn only_control(modifiers: &Modifiers) -> bool {
+    modifiers.control && !modifiers.shift && !modifiers.alt && !modifiers.platform
+}
+
+fn toggle_checked(checked: &Model<Vec<Item>>, item: Item, cx: &mut WindowContext) {
+    checked.update(cx, |this, cx| {
+        if let Some(i) = this.iter().position(|c| c.id.eq(&item.id)) {
+            this.remove(i);
+        } else {
+            this.push(item);
+        }
+        cx.notify();
+    });
+}
+
 pub struct AsyncListItems {
     pub items: HashMap<String, Vec<Item>>,
     pub initialized: bool,
@@ -9,36 +9,68 @@
  *
  */
 
-use std::cmp::Reverse;
+use std::{cmp::Reverse, ops::Range};
 
 use nucleo::pattern::{Atom, AtomKind, CaseMatching, Normalization};
 use nucleo::{Config, Matcher, Utf32Str};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::components::list::Item;
 use crate::state::LazyMutex;
 
 pub static MATCHER: LazyMutex<nucleo::Matcher> = LazyMutex::new(nucleo::Matcher::default);
 
+// Lowercases and strips diacritics (NFD without combining marks, recomposed to NFC), so "resume"
+// finds "Résumé". Nucleo matches graphemes, so this also returns the chars in `text` that every
+// normalized grapheme came from, to highlight the original.
+pub fn normalize(text: &str) -> (String, Vec<Range<usize>>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut positions = Vec::with_capacity(text.len());
+    let mut start = 0;
+    for grapheme in text.graphemes(true) {
+        let end = start + grapheme.chars().count();
+        let folded: String = grapheme
+            .nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .flat_map(char::to_lowercase)
+            .nfc()
+            .collect();
+        for _ in folded.graphemes(true) {
+            positions.push(start..end);
+        }
+        normalized.push_str(&folded);
+        start = end;
+    }
+    (normalized, positions)
+}
+
 pub fn fuzzy_match<T: Score>(pattern: &str, items: Vec<T>, path: bool) -> Vec<T> {
+    let mut items = fuzzy_scores(pattern, items, path);
+    items.sort_by_key(|(_, score)| Reverse(*score));
+    items.into_iter().map(|item| item.0).collect()
+}
+
+// Unsorted variant of `fuzzy_match` for callers that want to adjust the scores themselves
+pub fn fuzzy_scores<T: Score>(pattern: &str, items: Vec<T>, path: bool) -> Vec<(T, u16)> {
     let mut matcher = MATCHER.lock();
     matcher.config = Config::DEFAULT;
     if path {
         matcher.config.set_match_paths();
     }
+    // Both sides are normalized up front, so nucleo doesn't need to
     let pattern = Atom::new(
-        pattern,
-        CaseMatching::Smart,
-        Normalization::Smart,
+        &normalize(pattern).0,
+        CaseMatching::Ignore,
+        Normalization::Never,
         AtomKind::Fuzzy,
         false,
     );
     let mut buf = Vec::new();
-    let mut items: Vec<(T, u16)> = items
+    items
         .into_iter()
         .filter_map(|item| item.score(&pattern, &mut buf, &mut matcher))
-        .collect();
-    items.sort_by_key(|(_, score)| Reverse(*score));
-    items.into_iter().map(|item| item.0).collect()
+        .collect()
 }
 
 pub trait Score {
@@ -62,15 +94,160 @@ impl Score for Item {
     where
         Self: Sized,
     {
-        let keywords = self.keywords.clone();
-        let scores = keywords
+        // The best score across the rendered title and all keywords counts
+        let mut needles = self.keywords.clone();
+        if let Some(title) = self.component.title() {
+            if !needles.contains(&title) {
+                needles.insert(0, title);
+            }
+        }
+        let mut best: Option<(usize, u16)> = None;
+        for (i, needle) in needles.iter().enumerate() {
+            let (needle, _) = normalize(needle);
+            if let Some(score) = pattern.score(Utf32Str::new(&needle, buf), matcher) {
+                if best.map(|(_, highest)| score > highest).unwrap_or(true) {
+                    best = Some((i, score));
+                }
+            }
+        }
+        let (i, score) = best?;
+
+        // Only the winning keyword gets its match indices computed, since that is the one we highlight
+        let keyword = needles[i].clone();
+        let (normalized, positions) = normalize(&keyword);
+        let mut indices: Vec<u32> = Vec::new();
+        pattern.indices(Utf32Str::new(&normalized, buf), matcher, &mut indices);
+        // Back to positions in the original keyword, which is what gets displayed
+        let mut indices: Vec<usize> = indices
             .into_iter()
-            .map(|needle| pattern.score(Utf32Str::new(&needle, buf), matcher));
+            .filter_map(|i| positions.get(i as usize).cloned())
+            .flatten()
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
 
-        // Consider using `filter_map` to avoid the inner `unwrap_or(None)`
-        let highest = scores.flatten().max();
+        let mut item = self.clone();
+        item.matches = if indices.is_empty() {
+            None
+        } else {
+            Some((keyword, indices))
+        };
 
         let weight = self.weight.unwrap_or(1);
-        highest.map(|score| ((*self).clone(), score * weight)) // Cloning self to avoid borrowing issues
+        Some((item, score * weight))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::list::{item_id, ItemBuilder, ListItem};
+
+    fn item(title: &str, keywords: Vec<&str>) -> Item {
+        ItemBuilder::new(title, ListItem::new(None, title, None, vec![]))
+            .keywords(keywords)
+            .build()
+    }
+
+    #[test]
+    fn finds_items_by_title_alone() {
+        let items = vec![
+            item("Calculator", vec![]),
+            item("Clipboard History", vec![]),
+        ];
+        let results = fuzzy_match("calc", items, false);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, item_id("Calculator"));
+        let (needle, indices) = results[0].matches.clone().unwrap();
+        assert_eq!(needle.as_ref(), "Calculator");
+        assert_eq!(indices, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn keyword_matches_still_rank() {
+        let items = vec![
+            item("Notes", vec![]),
+            item("GitHub", vec!["octocat"]),
+            item("Octopus", vec![]),
+        ];
+        let results = fuzzy_match("octo", items, false);
+
+        let ids: Vec<u64> = results.iter().map(|item| item.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert!(ids.contains(&item_id("GitHub")));
+        assert!(ids.contains(&item_id("Octopus")));
+        let github = results
+            .iter()
+            .find(|item| item.id == item_id("GitHub"))
+            .unwrap();
+        assert_eq!(github.matches.clone().unwrap().0.as_ref(), "octocat");
+    }
+
+    #[test]
+    fn keywords_do_not_shadow_a_better_title_match() {
+        let items = vec![item("GitHub", vec!["octocat"])];
+        let results = fuzzy_match("github", items, false);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].matches.clone().unwrap().0.as_ref(), "GitHub");
+    }
+
+    #[test]
+    fn normalize_strips_diacritics() {
+        assert_eq!(normalize("Café").0, "cafe");
+        assert_eq!(normalize("cafe").0, normalize("Café").0);
+
+        let results = fuzzy_match("cafe", vec![item("Café", vec![])], false);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].matches.clone().unwrap().1, vec![0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn normalize_folds_case() {
+        assert_eq!(normalize("ÉCOLE").0, "ecole");
+        assert_eq!(normalize("İstanbul").0, "istanbul");
+    }
+
+    #[test]
+    fn normalize_maps_back_to_original_chars() {
+        let (normalized, positions) = normalize("Résumé.pdf");
+        assert_eq!(normalized, "resume.pdf");
+        assert_eq!(positions, (0..10).map(|i| i..i + 1).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn normalize_drops_combining_marks() {
+        // "e" followed by a combining acute accent, as typed on some keyboards
+        let (normalized, positions) = normalize("e\u{301}te");
+        assert_eq!(normalized, "ete");
+        assert_eq!(positions, vec![0..2, 2..3, 3..4]);
+    }
+
+    #[test]
+    fn normalize_keeps_hangul_composed() {
+        assert_eq!(normalize("한국어").0, "한국어");
+    }
+
+    // Highlights have to land on the matched characters, past multi-codepoint ones in front of them
+    fn highlighted(title: &str, query: &str) -> Vec<usize> {
+        let results = fuzzy_match(query, vec![item(title, vec![])], false);
+        results[0].matches.clone().unwrap().1
+    }
+
+    #[test]
+    fn highlights_after_hangul() {
+        assert_eq!(highlighted("한국어 notes", "notes"), vec![4, 5, 6, 7, 8]);
+        assert_eq!(highlighted("한국어 notes", "국"), vec![1]);
+    }
+
+    #[test]
+    fn highlights_after_flags_and_zwj_sequences() {
+        // The flag is two chars, the family five, each of them a single grapheme
+        assert_eq!(highlighted("🇩🇪 trip", "trip"), vec![3, 4, 5, 6]);
+        assert_eq!(
+            highlighted("👨\u{200d}👩\u{200d}👧 photos", "photos"),
+            vec![6, 7, 8, 9, 10, 11]
+        );
     }
 }
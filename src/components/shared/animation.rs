@@ -0,0 +1,357 @@
+/*
+ *
+ *  This source file is part of the Loungy open source project
+ *
+ *  Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ *  Licensed under MIT License
+ *
+ *  See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ *
+ */
+
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    fs,
+    hash::{Hash, Hasher},
+    io::{Cursor, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, OnceLock,
+    },
+    time::{Duration, SystemTime},
+};
+
+use async_std::task::{spawn, spawn_blocking, JoinHandle};
+use futures::{future::Shared, FutureExt};
+use gpui::*;
+use image::{
+    codecs::{gif::GifDecoder, png::PngDecoder},
+    AnimationDecoder,
+};
+use log::debug;
+use parking_lot::Mutex;
+use url::Url;
+
+use crate::{
+    paths::paths,
+    theme::{ReduceMotion, ThemeSettings},
+};
+
+// Frames of an animated image, extracted to the cache so GPUI loads them like any other file
+struct AnimationFrames {
+    frames: Vec<(Arc<PathBuf>, u64)>,
+    total: u64,
+}
+
+type DecodeFramesTask =
+    Shared<JoinHandle<Result<Option<Arc<AnimationFrames>>, Arc<anyhow::Error>>>>;
+
+static ANIMATIONS: OnceLock<Mutex<HashMap<String, DecodeFramesTask>>> = OnceLock::new();
+
+static FROZEN: OnceLock<AtomicBool> = OnceLock::new();
+
+// Browsers treat tiny frame delays as unset, so do the same instead of spinning
+static MIN_FRAME_DELAY: u64 = 20;
+static DEFAULT_FRAME_DELAY: u64 = 100;
+// Total size of the extracted frames on disk, least recently used animations are evicted first
+static MAX_FRAME_CACHE_SIZE: u64 = 100 * 1024 * 1024;
+// The GIF signature or the APNG control chunk are expected well within this
+static SNIFF_LEN: usize = 64 * 1024;
+static PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+// Frame delays in milliseconds, empty for images that turned out to be static
+static MANIFEST: &str = "frames.json";
+
+pub fn animations_frozen() -> bool {
+    FROZEN
+        .get_or_init(|| AtomicBool::new(ThemeSettings::get().freeze_animations))
+        .load(Ordering::Relaxed)
+}
+
+pub fn freeze_animations(frozen: bool, cx: &mut WindowContext) -> anyhow::Result<()> {
+    let mut settings = ThemeSettings::get();
+    settings.freeze_animations = frozen;
+    settings.set()?;
+    FROZEN
+        .get_or_init(|| AtomicBool::new(frozen))
+        .store(frozen, Ordering::Relaxed);
+    cx.refresh();
+    Ok(())
+}
+
+// Sources worth decoding, unless the caller already knows the image might be animated
+fn animation_key(src: &ImageSource, force: bool) -> Option<String> {
+    let (key, extension) = match src {
+        ImageSource::File(path) => (
+            path.to_string_lossy().to_string(),
+            path.extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase()),
+        ),
+        ImageSource::Uri(uri) => {
+            let url = Url::parse(uri).ok()?;
+            let extension = Path::new(url.path())
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase());
+            (uri.to_string(), extension)
+        }
+        _ => return None,
+    };
+    if force || matches!(extension.as_deref(), Some("gif" | "png" | "apng")) {
+        Some(key)
+    } else {
+        None
+    }
+}
+
+// Some(true) for GIFs and APNGs, None while the bytes are too short to tell
+fn sniff(bytes: &[u8]) -> Option<bool> {
+    if bytes.starts_with(b"GIF8") {
+        return Some(true);
+    }
+    if bytes.len() < PNG_SIGNATURE.len() {
+        return None;
+    }
+    if !bytes.starts_with(PNG_SIGNATURE) {
+        return Some(false);
+    }
+    // APNGs are regular PNGs with an animation control chunk ahead of the image data
+    let mut at = PNG_SIGNATURE.len();
+    while let Some(header) = bytes.get(at..at + 8) {
+        match &header[4..] {
+            b"acTL" => return Some(true),
+            b"IDAT" => return Some(false),
+            _ => at += 12 + u32::from_be_bytes(header[..4].try_into().unwrap()) as usize,
+        }
+    }
+    None
+}
+
+fn read_file(path: &Path) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut bytes = Vec::new();
+    fs::File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut bytes)?;
+    if sniff(&bytes) != Some(true) {
+        return Ok(None);
+    }
+    Ok(Some(fs::read(path)?))
+}
+
+// Stops downloading as soon as the header shows the image is static
+async fn read_uri(uri: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let mut response = reqwest::get(uri).await?.error_for_status()?;
+    let mut bytes = Vec::new();
+    let mut animated = None;
+    while let Some(chunk) = response.chunk().await? {
+        bytes.extend_from_slice(&chunk);
+        if animated.is_none() {
+            animated = sniff(&bytes).or((bytes.len() >= SNIFF_LEN).then_some(false));
+        }
+        if animated == Some(false) {
+            return Ok(None);
+        }
+    }
+    Ok((animated == Some(true)).then_some(bytes))
+}
+
+fn cache_dir() -> PathBuf {
+    paths().cache.join("frames")
+}
+
+// Loads an earlier extraction, bumping its mtime so eviction is least recently used
+fn cached(dir: &Path) -> Option<Option<AnimationFrames>> {
+    let manifest = dir.join(MANIFEST);
+    let delays: Vec<u64> = serde_json::from_slice(&fs::read(&manifest).ok()?).ok()?;
+    if let Ok(file) = fs::File::options().write(true).open(&manifest) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+    if delays.is_empty() {
+        return Some(None);
+    }
+    Some(Some(AnimationFrames {
+        total: delays.iter().sum(),
+        frames: delays
+            .into_iter()
+            .enumerate()
+            .map(|(i, delay)| (Arc::new(dir.join(format!("{}.png", i))), delay))
+            .collect(),
+    }))
+}
+
+// The manifest is written last, so an interrupted extraction is never picked up. Static images
+// get an empty one, so they aren't fetched again either
+fn extract_frames(bytes: Option<Vec<u8>>, dir: &Path) -> anyhow::Result<Option<AnimationFrames>> {
+    fs::create_dir_all(dir)?;
+    let frames = match bytes {
+        Some(bytes) if bytes.starts_with(b"GIF8") => {
+            GifDecoder::new(Cursor::new(bytes))?.into_frames()
+        }
+        Some(bytes) => PngDecoder::new(Cursor::new(bytes))?.apng().into_frames(),
+        None => {
+            fs::write(dir.join(MANIFEST), "[]")?;
+            return Ok(None);
+        }
+    };
+    let frames = frames.collect_frames()?;
+    let mut delays = Vec::with_capacity(frames.len());
+    if frames.len() > 1 {
+        for (i, frame) in frames.into_iter().enumerate() {
+            let (numer, denom) = frame.delay().numer_denom_ms();
+            let delay = match (numer / denom.max(1)) as u64 {
+                delay if delay < MIN_FRAME_DELAY => DEFAULT_FRAME_DELAY,
+                delay => delay,
+            };
+            frame.into_buffer().save(dir.join(format!("{}.png", i)))?;
+            delays.push(delay);
+        }
+    }
+    fs::write(dir.join(MANIFEST), serde_json::to_vec(&delays)?)?;
+    prune(&cache_dir());
+    Ok(cached(dir).flatten())
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .map(|meta| meta.len())
+                .sum()
+        })
+        .unwrap_or_default()
+}
+
+fn prune(root: &Path) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let mut dirs: Vec<(SystemTime, u64, PathBuf)> = entries
+        .flatten()
+        .map(|entry| {
+            let dir = entry.path();
+            let modified = fs::metadata(dir.join(MANIFEST))
+                .and_then(|meta| meta.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (modified, dir_size(&dir), dir)
+        })
+        .collect();
+    let mut size: u64 = dirs.iter().map(|(_, len, _)| len).sum();
+    if size <= MAX_FRAME_CACHE_SIZE {
+        return;
+    }
+    dirs.sort_by_key(|(modified, _, _)| *modified);
+    for (_, len, dir) in dirs {
+        if size <= MAX_FRAME_CACHE_SIZE {
+            break;
+        }
+        if fs::remove_dir_all(dir).is_ok() {
+            size -= len;
+        }
+    }
+}
+
+async fn decode_frames(src: ImageSource, key: String) -> anyhow::Result<Option<AnimationFrames>> {
+    // Files are keyed by their mtime too, so edited images are extracted again
+    let key = match &src {
+        ImageSource::File(path) => {
+            let meta = fs::metadata(path.as_ref())?;
+            let modified = meta.modified()?.duration_since(SystemTime::UNIX_EPOCH)?;
+            format!("{}:{}:{}", key, modified.as_millis(), meta.len())
+        }
+        _ => key,
+    };
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    let dir = cache_dir().join(format!("{:x}", hasher.finish()));
+    if let Some(frames) = cached(&dir) {
+        return Ok(frames);
+    }
+    let bytes = match &src {
+        ImageSource::File(path) => {
+            let path = path.clone();
+            spawn_blocking(move || read_file(&path)).await?
+        }
+        ImageSource::Uri(uri) => read_uri(uri).await?,
+        _ => return Ok(None),
+    };
+    spawn_blocking(move || extract_frames(bytes, &dir)).await
+}
+
+impl AnimationFrames {
+    fn frame(&self, delta: f32) -> Arc<PathBuf> {
+        let mut at = (delta * self.total as f32) as u64;
+        for (path, delay) in &self.frames {
+            if at < *delay {
+                return path.clone();
+            }
+            at -= delay;
+        }
+        self.frames.last().map(|(path, _)| path.clone()).unwrap()
+    }
+}
+
+// Loops animated GIFs and APNGs, None means the caller should render the source as a static image
+pub fn animated_img(
+    src: &ImageSource,
+    force: bool,
+    fit: gpui::ObjectFit,
+    cx: &mut WindowContext,
+) -> Option<AnyElement> {
+    if animations_frozen() || ReduceMotion::enabled(cx) {
+        return None;
+    }
+    let key = animation_key(src, force)?;
+    let task = ANIMATIONS
+        .get_or_init(Default::default)
+        .lock()
+        .entry(key.clone())
+        .or_insert_with(|| {
+            let decode = decode_frames(src.clone(), key.clone());
+            let task = spawn(async move {
+                decode
+                    .await
+                    .map(|frames| frames.map(Arc::new))
+                    .map_err(Arc::new)
+            })
+            .shared();
+            // The image element is stateless, so redraw once the frames are ready
+            let frames = task.clone();
+            let key = key.clone();
+            cx.spawn(|mut cx| async move {
+                match frames.await {
+                    Ok(Some(_)) => {
+                        let _ = cx.update(|cx| cx.refresh());
+                    }
+                    Ok(None) => {}
+                    // Forget the failure, so the next render tries again
+                    Err(err) => {
+                        debug!("Failed to decode animation {}: {:?}", key, err);
+                        if let Some(animations) = ANIMATIONS.get() {
+                            animations.lock().remove(&key);
+                        }
+                    }
+                }
+            })
+            .detach();
+            task
+        })
+        .clone();
+    let frames = task.now_or_never()?.ok().flatten()?;
+    Some(
+        div()
+            .size_full()
+            .with_animation(
+                ElementId::Name(key.into()),
+                Animation::new(Duration::from_millis(frames.total)).repeat(),
+                move |el, delta| {
+                    el.child(
+                        img(ImageSource::File(frames.frame(delta)))
+                            .object_fit(fit)
+                            .size_full(),
+                    )
+                },
+            )
+            .into_any_element(),
+    )
+}
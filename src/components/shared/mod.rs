@@ -12,13 +12,14 @@
 use std::{
     cell::OnceCell,
     collections::HashMap,
-    path::PathBuf,
+    fs,
+    path::{Path, PathBuf},
     sync::{Arc, OnceLock},
-    time::Duration,
+    time::{Duration, SystemTime},
 };
 
 use anyhow::anyhow;
-use async_std::task::{spawn, spawn_blocking, JoinHandle};
+use async_std::task::{sleep, spawn, spawn_blocking, JoinHandle};
 use futures::future::Shared;
 use futures::FutureExt;
 use gpui::*;
@@ -28,10 +29,15 @@ use reqwest::StatusCode;
 use scraper::{Html, Selector};
 use url::Url;
 
+pub use animation::{animated_img, animations_frozen, freeze_animations};
 pub use icon::Icon;
 
-use crate::theme::Theme;
+use crate::{
+    paths::paths,
+    theme::{ReduceMotion, Theme},
+};
 
+mod animation;
 mod icon;
 
 #[derive(Clone)]
@@ -154,6 +160,10 @@ impl RenderOnce for Img {
         if let ImgSource::Favicon(favicon) = &self.src {
             return favicon.clone().into_any_element();
         }
+        let animated = match &self.src {
+            ImgSource::Base(src) => animated_img(src, false, self.fit.clone().into(), cx),
+            _ => None,
+        };
         let theme = cx.global::<Theme>();
         let el = div()
             .flex()
@@ -183,7 +193,7 @@ impl RenderOnce for Img {
                     .path(icon.path())
                     .text_color(color.unwrap_or(theme.text))
                     .size_full();
-                if icon == Icon::Loader2 {
+                if icon == Icon::Loader2 && !ReduceMotion::enabled(cx) {
                     svg.with_animation(
                         "rotate-loader",
                         Animation::new(Duration::from_secs(1)).repeat(),
@@ -197,6 +207,21 @@ impl RenderOnce for Img {
                 }
             }
             ImgSource::Base(src) => {
+                if let Some(animated) = animated {
+                    let frame = div().size_full().child(animated);
+                    let frame = match self.mask {
+                        ImgMask::Circle => {
+                            el = el.p_0p5();
+                            frame.rounded_full().overflow_hidden().bg(theme.surface0)
+                        }
+                        ImgMask::Rounded => {
+                            el = el.p_0p5();
+                            frame.rounded_md().overflow_hidden().bg(theme.surface0)
+                        }
+                        ImgMask::None => frame,
+                    };
+                    return el.child(frame).into_any_element();
+                }
                 let img = img(src).object_fit(self.fit.into()).size_full();
                 let img = match self.mask {
                     ImgMask::Circle => {
@@ -228,20 +253,119 @@ impl Render for NoView {
     }
 }
 
-type FetchFaviconTask = Shared<JoinHandle<Result<SharedUri, Arc<anyhow::Error>>>>;
+type FetchFaviconTask = Shared<JoinHandle<Result<PathBuf, Arc<anyhow::Error>>>>;
 
 static FAVICONS: OnceLock<Arc<Mutex<HashMap<String, FetchFaviconTask>>>> = OnceLock::new();
 
+// Finished lookups are on disk, so the in memory map only needs to hold a bounded number of tasks
+static MAX_FAVICON_TASKS: usize = 256;
+// Total size of the on disk favicon cache, least recently used icons are evicted first
+static MAX_FAVICON_CACHE_SIZE: u64 = 20 * 1024 * 1024;
+// How long a failed lookup is remembered before the host is tried again
+static FAVICON_MISS_TTL: Duration = Duration::from_secs(60 * 60);
+static FAVICON_EXTENSIONS: [&str; 3] = ["svg", "ico", "png"];
+// Per request timeout, so slow hosts don't keep the fallback icon around forever
+static FAVICON_TIMEOUT: Duration = Duration::from_secs(5);
+// Unreachable hosts are retried with an exponential backoff starting at this delay
+static FAVICON_BACKOFF: Duration = Duration::from_secs(2);
+static FAVICON_RETRIES: u32 = 3;
+
 #[derive(Clone)]
 pub struct Favicon {
     img: Img,
     fallback: Icon,
     url: String,
     task: OnceCell<FetchFaviconTask>,
+    waiting: bool,
 }
 
 impl Favicon {
-    async fn find_favicon(url: String) -> Result<SharedUri, anyhow::Error> {
+    fn cache_dir() -> PathBuf {
+        paths().cache.join("favicons")
+    }
+    fn cache_key(url: &Url) -> String {
+        url.host_str().unwrap_or_default().replace(
+            |c: char| !c.is_ascii_alphanumeric() && c != '.' && c != '-',
+            "_",
+        )
+    }
+    // Serves a previously fetched icon, bumping its mtime so eviction is least recently used
+    fn cached(key: &str) -> Option<Result<PathBuf, anyhow::Error>> {
+        let dir = Self::cache_dir();
+        for ext in FAVICON_EXTENSIONS {
+            let path = dir.join(format!("{}.{}", key, ext));
+            if let Ok(file) = fs::File::options().write(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+                return Some(Ok(path));
+            }
+        }
+        let miss = dir.join(format!("{}.miss", key));
+        let age = fs::metadata(miss)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())?;
+        (age < FAVICON_MISS_TTL).then(|| Err(anyhow!("Cached favicon miss for {}", key)))
+    }
+    fn prune(dir: &Path) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                Some((meta.modified().ok()?, meta.len(), entry.path()))
+            })
+            .collect();
+        let mut size: u64 = files.iter().map(|(_, len, _)| len).sum();
+        if size <= MAX_FAVICON_CACHE_SIZE {
+            return;
+        }
+        files.sort_by_key(|(modified, _, _)| *modified);
+        for (_, len, path) in files {
+            if size <= MAX_FAVICON_CACHE_SIZE {
+                break;
+            }
+            if fs::remove_file(path).is_ok() {
+                size -= len;
+            }
+        }
+    }
+    async fn load_favicon(url: String) -> Result<PathBuf, anyhow::Error> {
+        let base_url = Url::parse(&url)?;
+        let key = Self::cache_key(&base_url);
+        if let Some(cached) = Self::cached(&key) {
+            return cached;
+        }
+        let dir = Self::cache_dir();
+        fs::create_dir_all(&dir)?;
+        let mut attempt = 0;
+        loop {
+            match Self::find_favicon(url.clone()).await {
+                Ok(Some((ext, bytes))) => {
+                    let path = dir.join(format!("{}.{}", key, ext));
+                    fs::write(&path, bytes)?;
+                    let _ = fs::remove_file(dir.join(format!("{}.miss", key)));
+                    Self::prune(&dir);
+                    return Ok(path);
+                }
+                Ok(None) => {
+                    let _ = fs::write(dir.join(format!("{}.miss", key)), []);
+                    return Err(anyhow!("No favicon found for {}", url));
+                }
+                // Network errors are likely transient, so they aren't cached as a miss
+                Err(err) => {
+                    attempt += 1;
+                    if attempt >= FAVICON_RETRIES {
+                        return Err(err);
+                    }
+                    sleep(FAVICON_BACKOFF * 2u32.pow(attempt - 1)).await;
+                }
+            }
+        }
+    }
+    // Ok(None) means the host responded but has no usable favicon
+    async fn find_favicon(url: String) -> Result<Option<(&'static str, Vec<u8>)>, anyhow::Error> {
         let base_url = Url::parse(&url).unwrap();
         let mut targets = vec![base_url.clone()];
         // if subdomain
@@ -257,11 +381,14 @@ impl Favicon {
         };
         let client = reqwest::ClientBuilder::new()
             .user_agent("http_client (loungy.app)")
+            .timeout(FAVICON_TIMEOUT)
             .build()?;
+        let mut reached = false;
         for target in targets {
             let Ok(response) = client.get(target.clone()).send().await else {
                 continue;
             };
+            reached = true;
             let url = response.url().clone();
             let Ok(html) = response.text().await else {
                 continue;
@@ -297,13 +424,23 @@ impl Favicon {
                 {
                     continue;
                 }
-                if matches!(t, "image/svg+xml" | "image/x-icon" | "image/png") {
-                    return Ok(absolute.to_string().into());
+                let ext = match t {
+                    "image/svg+xml" => "svg",
+                    "image/x-icon" => "ico",
+                    "image/png" => "png",
+                    _ => continue,
                 };
+                let Ok(bytes) = response.bytes().await else {
+                    continue;
+                };
+                return Ok(Some((ext, bytes.to_vec())));
             }
         }
 
-        Err(anyhow!("No favicon found for {}", url))
+        if !reached {
+            return Err(anyhow!("Could not reach {}", url));
+        }
+        Ok(None)
     }
     pub fn new(
         img: &Img,
@@ -333,16 +470,17 @@ impl Favicon {
             fallback,
             url,
             task: OnceCell::new(),
+            waiting: false,
         })
     }
 }
 
 impl Render for Favicon {
-    fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
-        if let Some(task) = self
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        let task = self
             .task
             .get_or_init(|| {
-                FAVICONS
+                let mut map = FAVICONS
                     .get_or_init(|| {
                         let mut map: HashMap<String, FetchFaviconTask> = HashMap::new();
                         map.insert(
@@ -352,12 +490,19 @@ impl Render for Favicon {
                         );
                         Arc::new(Mutex::new(map))
                     })
-                    .lock()
-                    .entry(self.url.clone())
+                    .lock();
+                if map.len() >= MAX_FAVICON_TASKS {
+                    map.retain(|url, task| url.is_empty() || task.peek().is_none());
+                }
+                map.entry(self.url.clone())
                     .or_insert_with(|| {
                         let url = self.url.clone();
                         spawn(async move {
-                            Self::find_favicon(url).await.map_err(|err| {
+                            Self::load_favicon(url.clone()).await.map_err(|err| {
+                                // Misses are cached on disk, so dropping the task lets later loads retry transient failures
+                                if let Some(favicons) = FAVICONS.get() {
+                                    favicons.lock().remove(&url);
+                                }
                                 let error = Arc::new(err);
                                 debug!("{}", error);
                                 error
@@ -367,13 +512,22 @@ impl Render for Favicon {
                     })
                     .clone()
             })
-            .clone()
-            .now_or_never()
-            .and_then(|result| result.ok())
-        {
-            self.img.clone().url(task)
-        } else {
-            self.img.clone().icon(self.fallback.clone())
+            .clone();
+        match task.clone().now_or_never() {
+            Some(Ok(path)) => self.img.clone().file(path),
+            Some(Err(_)) => self.img.clone().icon(self.fallback.clone()),
+            None => {
+                // Re-render once the fetch finishes, so the fallback gets replaced
+                if !self.waiting {
+                    self.waiting = true;
+                    cx.spawn(|view, mut cx| async move {
+                        task.await.ok()?;
+                        view.update(&mut cx, |_, cx| cx.notify()).ok()
+                    })
+                    .detach();
+                }
+                self.img.clone().icon(self.fallback.clone())
+            }
         }
     }
 }
@@ -30,3 +30,23 @@ pub fn format_date(date: Timestamp, _cx: &AppContext) -> String {
 
     strtime::format(format, zoned.datetime()).unwrap()
 }
+
+// Coarse bucket for grouping lists by recency
+pub fn format_day_section(date: Timestamp) -> &'static str {
+    let tz = TimeZone::system();
+    let day = date.to_zoned(tz.clone()).date();
+    let today = Timestamp::now().to_zoned(tz).date();
+    if day.eq(&today) {
+        "Today"
+    } else if today.yesterday().map(|y| y.eq(&day)).unwrap_or(false) {
+        "Yesterday"
+    } else {
+        "Older"
+    }
+}
+
+// Plain calendar date, used when expanding text
+pub fn format_plain_date(date: Timestamp) -> String {
+    let zoned = date.to_zoned(TimeZone::system());
+    strtime::format("%Y-%m-%d", zoned.datetime()).unwrap()
+}
@@ -13,10 +13,12 @@ use std::sync::OnceLock;
 
 use bonsaidb::{
     core::{
-        connection::StorageConnection,
+        connection::{Connection, LowLevelConnection, StorageConnection},
         document::KeyId,
         keyvalue::{KeyStatus, KeyValue},
-        schema::Collection,
+        schema::SerializedCollection,
+        transmog::Format,
+        transmog_pot::Pot,
     },
     local::{
         config::{Builder, StorageConfiguration},
@@ -24,10 +26,26 @@ use bonsaidb::{
         Database, Storage,
     },
 };
+use log::{error, warn};
 use serde::{de, Serialize};
 
 use crate::paths::paths;
 
+// Schema version of a collection. Additive changes only need serde defaults,
+// anything else bumps the version and upgrades the old documents in migrate.
+pub trait Migrate: SerializedCollection<Contents = Self> + Sized + 'static {
+    const VERSION: u64 = 1;
+    // Upgrades a document stored by an older version, decode it with `decode_legacy`
+    fn migrate(_version: u64, _data: &[u8]) -> Option<Self> {
+        None
+    }
+}
+
+// Decodes a document into the struct layout it was stored with
+pub fn decode_legacy<T: de::DeserializeOwned>(data: &[u8]) -> Option<T> {
+    Pot::default().deserialize(data).ok()
+}
+
 #[derive(Clone)]
 pub struct Db {
     storage: Storage,
@@ -47,6 +65,9 @@ impl Db {
         let config = StorageConfiguration::new(path)
             .vault_key_storage(LocalVaultKeyStorage::new(keys).expect("Failed to create vault"))
             .default_encryption_key(KeyId::Master);
+        Self::open(config)
+    }
+    fn open(config: StorageConfiguration) -> Self {
         let storage = Storage::open(config).expect("Failed to open storage");
         storage
             .register_schema::<()>()
@@ -57,15 +78,63 @@ impl Db {
 
         Self { inner, storage }
     }
-    pub fn init_collection<C: Collection + 'static>() -> Database {
-        let storage = &db().storage;
-        storage
+    pub fn init_collection<C: Migrate>() -> Database {
+        db().open_collection::<C>()
+    }
+    fn open_collection<C: Migrate>(&self) -> Database {
+        self.storage
             .register_schema::<C>()
             .expect("Failed to register schema");
 
-        storage
+        let database = self
+            .storage
             .create_database::<C>(&C::collection_name().to_string(), true)
-            .expect("Failed to open database")
+            .expect("Failed to open database");
+        if let Err(err) = self.migrate::<C>(&database) {
+            error!("Failed to migrate {}: {}", C::collection_name(), err);
+        }
+        database
+    }
+    // Collections without a stored version predate versioning and count as version 1
+    fn migrate<C: Migrate>(&self, database: &Database) -> anyhow::Result<()> {
+        let key = format!("schema.{}", C::collection_name());
+        let version = self.get::<u64>(&key).unwrap_or(1);
+        // Stored by a newer build, its version stays so a later upgrade still migrates
+        if version > C::VERSION {
+            warn!(
+                "{} was stored by a newer version ({} > {})",
+                C::collection_name(),
+                version,
+                C::VERSION
+            );
+            return Ok(());
+        }
+        if version == C::VERSION {
+            return Ok(());
+        }
+        for mut doc in database.collection::<C>().all().query()? {
+            // Documents the migration doesn't handle are kept as long as they still decode
+            if let Some(contents) =
+                C::migrate(version, &doc.contents).or_else(|| C::deserialize(&doc.contents).ok())
+            {
+                doc.contents = C::serialize(&contents)?.into();
+                database.update::<C, _>(&mut doc)?;
+                continue;
+            }
+            // Anything else is set aside with its original bytes instead of being lost
+            let quarantine = format!("quarantine.{}", C::collection_name());
+            let mut quarantined = self.get::<Vec<Vec<u8>>>(&quarantine).unwrap_or_default();
+            quarantined.push(doc.contents.to_vec());
+            self.set(&quarantine, &quarantined)?;
+            database.delete::<C, _>(&doc)?;
+            error!(
+                "Failed to migrate a document of {}, moved it to {}",
+                C::collection_name(),
+                quarantine
+            );
+        }
+        self.set(&key, &C::VERSION)?;
+        Ok(())
     }
     pub fn get<T: de::DeserializeOwned>(&self, id: &str) -> Option<T> {
         if let Ok(value) = self.inner.get_key(id).into() {
@@ -82,3 +151,139 @@ impl Db {
         Ok(self.inner.set_key(id, value).execute()?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use bonsaidb::core::schema::{Collection, SerializedCollection};
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    mod v1 {
+        use super::*;
+
+        #[derive(Serialize, Deserialize, Collection, Clone, Debug)]
+        #[collection(name = "test.clipboard")]
+        pub struct ClipboardItem {
+            #[natural_id]
+            pub id: u64,
+            pub text: String,
+        }
+
+        impl Migrate for ClipboardItem {}
+    }
+
+    mod v2 {
+        use super::*;
+
+        #[derive(Serialize, Deserialize, Collection, Clone, Debug)]
+        #[collection(name = "test.clipboard")]
+        pub struct ClipboardItem {
+            #[natural_id]
+            pub id: u64,
+            pub title: String,
+            pub pinned: bool,
+        }
+
+        impl Migrate for ClipboardItem {
+            const VERSION: u64 = 2;
+            fn migrate(version: u64, data: &[u8]) -> Option<Self> {
+                let old = match version {
+                    1 => decode_legacy::<v1::ClipboardItem>(data)?,
+                    _ => return None,
+                };
+                // Stands in for a document the migration can't make sense of
+                if old.text.is_empty() {
+                    return None;
+                }
+                Some(Self {
+                    id: old.id,
+                    title: old.text,
+                    pinned: false,
+                })
+            }
+        }
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("loungy-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn store_v1(dir: &PathBuf, items: Vec<v1::ClipboardItem>) {
+        let db = Db::open(StorageConfiguration::new(dir));
+        let database = db.open_collection::<v1::ClipboardItem>();
+        for item in items {
+            item.push_into(&database).unwrap();
+        }
+    }
+
+    #[test]
+    fn migrates_v1_clipboard_item_to_v2() {
+        let dir = temp_dir("migrate");
+        store_v1(
+            &dir,
+            vec![v1::ClipboardItem {
+                id: 1,
+                text: "hello".to_string(),
+            }],
+        );
+
+        let db = Db::open(StorageConfiguration::new(&dir));
+        let database = db.open_collection::<v2::ClipboardItem>();
+        let item = v2::ClipboardItem::get(&1, &database)
+            .unwrap()
+            .unwrap()
+            .contents;
+        assert_eq!(item.title, "hello");
+        assert!(!item.pinned);
+        assert_eq!(db.get::<u64>("schema.test.clipboard"), Some(2));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn quarantines_documents_that_fail_to_migrate() {
+        let dir = temp_dir("quarantine");
+        store_v1(
+            &dir,
+            vec![
+                v1::ClipboardItem {
+                    id: 1,
+                    text: "kept".to_string(),
+                },
+                v1::ClipboardItem {
+                    id: 2,
+                    text: "".to_string(),
+                },
+            ],
+        );
+
+        let db = Db::open(StorageConfiguration::new(&dir));
+        let database = db.open_collection::<v2::ClipboardItem>();
+        let items = v2::ClipboardItem::all(&database).query().unwrap();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].contents.title, "kept");
+
+        let quarantined = db.get::<Vec<Vec<u8>>>("quarantine.test.clipboard").unwrap();
+        assert_eq!(quarantined.len(), 1);
+        let original = decode_legacy::<v1::ClipboardItem>(&quarantined[0]).unwrap();
+        assert_eq!(original.id, 2);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn keeps_the_version_of_a_newer_build() {
+        let dir = temp_dir("downgrade");
+        let db = Db::open(StorageConfiguration::new(&dir));
+        db.set("schema.test.clipboard", &3u64).unwrap();
+        db.open_collection::<v2::ClipboardItem>();
+        assert_eq!(db.get::<u64>("schema.test.clipboard"), Some(3));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
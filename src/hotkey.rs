@@ -9,7 +9,11 @@
  *
  */
 
-use std::{collections::HashMap, sync::OnceLock, time::Duration};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::OnceLock,
+    time::Duration,
+};
 
 use bonsaidb::{
     core::schema::{Collection, SerializedCollection},
@@ -24,7 +28,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     commands::{RootCommand, RootCommands},
-    db::Db,
+    db::{Db, Migrate},
     state::{Actions, StateModel},
     window::Window,
 };
@@ -82,8 +86,9 @@ impl HotkeyManager {
                                         },
                                         cx,
                                     );
-                                    (command.action)(&mut Actions::default(cx), cx);
+                                    // Open first, so the command is able to close the window again
                                     Window::open(cx);
+                                    (command.action)(&mut Actions::default(cx), cx);
                                 } else {
                                     Window::toggle(cx);
                                 }
@@ -106,8 +111,11 @@ impl HotkeyManager {
             let hotkeys = CommandHotkeys::all(db()).query().unwrap_or_default();
             let _ = manager.manager.unregister_all(&manager.hotkeys);
             manager.hotkeys.clear();
+            manager.map.clear();
+            let mut configured = HashSet::<String>::new();
             for hotkey in hotkeys {
                 let hotkey = hotkey.contents;
+                configured.insert(hotkey.id.clone());
                 let known = commands.commands.get(hotkey.id.as_str());
                 if let Some(known) = known {
                     let hotkey = HotKey::try_from(hotkey.hotkey).unwrap();
@@ -115,11 +123,24 @@ impl HotkeyManager {
                     manager.map.insert(hotkey.id(), known.clone());
                 }
             }
+            // The command shortcut acts as default hotkey until the user configures one
+            for (id, command) in commands.commands.iter() {
+                if configured.contains(id) {
+                    continue;
+                }
+                let Some(shortcut) = command.shortcut.as_ref() else {
+                    continue;
+                };
+                if let Ok(hotkey) = HotKey::try_from(Self::hotkey(&shortcut.get())) {
+                    manager.hotkeys.push(hotkey);
+                    manager.map.insert(hotkey.id(), command.clone());
+                }
+            }
 
             let _ = manager.manager.register_all(&manager.hotkeys);
         });
     }
-    pub fn set(id: &str, keystroke: Keystroke, cx: &mut WindowContext) -> anyhow::Result<()> {
+    fn hotkey(keystroke: &Keystroke) -> String {
         // This is annoying and will break for most hotkeys
         let mut tokens = Vec::<&str>::new();
         if keystroke.modifiers.alt {
@@ -138,7 +159,18 @@ impl HotkeyManager {
             tokens.push("shift");
         }
         tokens.push(keystroke.key.as_str());
-        let hotkey = tokens.join("+");
+        tokens.join("+")
+    }
+    // Id of the command the keystroke is registered to as global hotkey
+    pub fn lookup(keystroke: &Keystroke, cx: &AppContext) -> Option<String> {
+        let hotkey = HotKey::try_from(Self::hotkey(keystroke)).ok()?;
+        cx.try_global::<Self>()?
+            .map
+            .get(&hotkey.id())
+            .map(|command| command.id.clone())
+    }
+    pub fn set(id: &str, keystroke: Keystroke, cx: &mut WindowContext) -> anyhow::Result<()> {
+        let hotkey = Self::hotkey(&keystroke);
 
         HotKey::try_from(hotkey.clone())?;
 
@@ -183,3 +215,5 @@ pub struct CommandHotkeys {
     id: String,
     hotkey: String,
 }
+
+impl Migrate for CommandHotkeys {}
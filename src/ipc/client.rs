@@ -4,7 +4,7 @@ use clap::Command;
 use crate::commands::RootCommands;
 
 use super::{
-    server::{get_command, CommandPayload, TopLevelCommand},
+    server::{cli_name, get_command, CommandPayload, CommandStatus, TopLevelCommand},
     SOCKET_PATH,
 };
 
@@ -19,17 +19,38 @@ pub async fn client_connect() -> anyhow::Result<()> {
 
     let matches = command.get_matches();
 
+    if matches.get_flag("List") {
+        let mut commands: Vec<_> = root_commands
+            .commands
+            .values()
+            .map(|command| (cli_name(&command.id), &command.title))
+            .collect();
+        commands.sort();
+        for (name, title) in commands {
+            println!("{}\t{}", name, title);
+        }
+        return Ok(());
+    }
+
     let payload: CommandPayload = CommandPayload {
         action: matches
             .get_one::<TopLevelCommand>("Action")
             .ok_or(anyhow::anyhow!("Action not found"))?
             .clone(),
         command: matches.get_one::<String>("Command").cloned(),
+        query: matches.get_one::<String>("Query").cloned(),
     };
 
     let bytes = serde_json::to_vec(&payload)?;
 
     stream.write_all(&bytes).await?;
 
+    let n = stream.read(&mut buf).await?;
+    let status: CommandStatus = serde_json::from_slice(&buf[..n])?;
+    if !matches!(status, CommandStatus::Ok) {
+        eprintln!("{}", status);
+        std::process::exit(1);
+    }
+
     Ok(())
 }
@@ -1,11 +1,11 @@
-use std::path::Path;
+use std::{fmt::Display, path::Path};
 
 use anyhow::anyhow;
 use async_std::{
     io::{ReadExt, WriteExt},
     os::unix::net::{UnixListener, UnixStream},
 };
-use clap::{command, Arg, ValueEnum};
+use clap::{command, Arg, ArgAction, ValueEnum};
 use gpui::AsyncWindowContext;
 use serde::{Deserialize, Serialize};
 
@@ -53,64 +53,98 @@ async fn handle_client(
 
     let mut buf = vec![0; 1024];
     let n = stream.read(&mut buf).await?;
+    // The client hung up after reading the commands, e.g. for `--list`
+    if n == 0 {
+        return Ok(());
+    }
 
     let matches: CommandPayload = serde_json::from_slice(&buf[..n])?;
 
-    let _ = cx.update::<anyhow::Result<()>>(|cx| {
-        match matches.action {
-            TopLevelCommand::Toggle => {
-                Window::toggle(cx);
-            }
-            TopLevelCommand::Show => {
-                Window::open(cx);
-            }
-            TopLevelCommand::Hide => {
-                Window::close(cx);
-            }
-            TopLevelCommand::Quit => {
-                cx.quit();
-            }
-            TopLevelCommand::Command => {
-                let Some(c) = matches.command else {
-                    return Err(anyhow!("No command provided"));
-                };
-                let Some((_, command)) = commands.commands.iter().find(|(k, _)| {
-                    let split = k.split("::").collect::<Vec<_>>();
-                    c.eq(split[2])
-                }) else {
-                    return Err(anyhow!("Command not found"));
-                };
-
-                let state = cx.global::<StateModel>();
-                let state = state.inner.read(cx);
-                let mut is_active = false;
-                if let Some(active) = state.stack.last() {
-                    is_active = active.id.eq(&command.id);
-                };
-                if !is_active {
-                    StateModel::update(
-                        |this, cx| {
-                            this.reset(cx);
-                        },
-                        cx,
-                    );
-                    (command.action)(&mut Actions::default(cx), cx);
-                    Window::open(cx);
-                } else {
+    let status = cx
+        .update::<CommandStatus>(|cx| {
+            match matches.action {
+                TopLevelCommand::Toggle => {
                     Window::toggle(cx);
                 }
+                TopLevelCommand::Show => {
+                    Window::open(cx);
+                }
+                TopLevelCommand::Hide => {
+                    Window::close(cx);
+                }
+                TopLevelCommand::Quit => {
+                    cx.quit();
+                }
+                TopLevelCommand::Command => {
+                    let Some(c) = matches.command else {
+                        return CommandStatus::Error("No command provided".to_string());
+                    };
+                    let Some((_, command)) =
+                        commands.commands.iter().find(|(k, _)| c.eq(cli_name(k)))
+                    else {
+                        return CommandStatus::UnknownCommand(c);
+                    };
+
+                    let state = cx.global::<StateModel>();
+                    let state = state.inner.read(cx);
+                    let mut is_active = false;
+                    if let Some(active) = state.stack.last() {
+                        is_active = active.id.eq(&command.id);
+                    };
+                    if !is_active {
+                        StateModel::update(
+                            |this, cx| {
+                                this.reset(cx);
+                            },
+                            cx,
+                        );
+                        (command.action)(&mut Actions::default(cx), cx);
+                        Window::open(cx);
+                    } else if matches.query.is_none() {
+                        Window::toggle(cx);
+                    } else {
+                        Window::open(cx);
+                    }
+                    // The command has pushed its view by now, so the query ends up there
+                    if let Some(query) = matches.query {
+                        StateModel::update(|this, cx| this.set_query(query, cx), cx);
+                    }
+                }
+                TopLevelCommand::Pipe => {}
             }
-            TopLevelCommand::Pipe => {}
-        }
-        Ok(())
-    });
+            CommandStatus::Ok
+        })
+        .unwrap_or_else(|err| CommandStatus::Error(err.to_string()));
+
+    // Report back to the client, so scripts can tell whether it worked
+    let bytes = serde_json::to_vec(&status)?;
+    stream.write_all(&bytes).await?;
+
     Ok(())
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CommandStatus {
+    Ok,
+    UnknownCommand(String),
+    Error(String),
+}
+
+impl Display for CommandStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CommandStatus::Ok => write!(f, "Ok"),
+            CommandStatus::UnknownCommand(command) => write!(f, "Unknown command: {}", command),
+            CommandStatus::Error(err) => write!(f, "Error: {}", err),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CommandPayload {
     pub action: TopLevelCommand,
     pub command: Option<String>,
+    pub query: Option<String>,
 }
 
 #[derive(Clone, Debug, ValueEnum, Serialize, Deserialize)]
@@ -136,12 +170,17 @@ impl From<TopLevelCommand> for clap::builder::OsStr {
     }
 }
 
+// Name of a command as used on the command line
+pub fn cli_name(id: &str) -> &str {
+    id.split("::").nth(2).unwrap_or(id)
+}
+
 pub fn get_command(commands: &RootCommands) -> clap::Command {
     command!()
         .arg(
             Arg::new("Action")
                 .value_parser(clap::builder::EnumValueParser::<TopLevelCommand>::new())
-                .required(true),
+                .required_unless_present("List"),
         )
         .arg(
             Arg::new("Command")
@@ -150,13 +189,21 @@ pub fn get_command(commands: &RootCommands) -> clap::Command {
                     commands
                         .commands
                         .keys()
-                        .map(|key| {
-                            let split = key.split("::").collect::<Vec<_>>();
-                            split[2].to_string()
-                        })
+                        .map(|key| cli_name(key).to_string())
                         .collect::<Vec<_>>(),
                 ),
         )
+        .arg(
+            Arg::new("Query")
+                .help("Text to fill into the query of the command")
+                .required(false),
+        )
+        .arg(
+            Arg::new("List")
+                .long("list")
+                .help("Print the available commands and exit")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("Delimeter")
                 .long("Delimeter")
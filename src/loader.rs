@@ -91,7 +91,7 @@ impl RenderOnce for ActiveLoaders {
                                 i
                             }
                         };
-                        let mut bg = theme.lavender;
+                        let mut bg = theme.accent();
 
                         bg.fade_out(opacity);
                         div.left(relative(left)).w(relative(width)).bg(bg)
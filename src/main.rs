@@ -41,5 +41,6 @@ async fn main() {
         run_app(listener, app);
     } else if let Err(e) = client_connect().await {
         log::error!("CLI Error: {:?}", e);
+        std::process::exit(1);
     }
 }
@@ -16,6 +16,7 @@ pub struct Paths {
     pub cache: PathBuf,
     pub config: PathBuf,
     pub data: PathBuf,
+    pub home: PathBuf,
 }
 
 pub static NAME: &str = "loungy";
@@ -50,6 +51,7 @@ impl Paths {
                 .join(NAME),
             #[cfg(target_os = "linux")]
             data: user_dir.clone().join(".local/share").join(NAME),
+            home: user_dir,
         }
     }
 }
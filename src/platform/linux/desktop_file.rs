@@ -11,12 +11,20 @@
 
 use freedesktop_entry_parser::{parse_entry, AttrSelector};
 use freedesktop_icons::lookup;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fs,
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
 
 pub(crate) struct ApplicationDesktopFile {
     pub name: String,
     pub icon: Option<String>,
     pub keywords: Vec<String>,
+    pub startup_wm_class: Option<String>,
 }
 
 pub(crate) enum DesktopFileError {
@@ -26,12 +34,53 @@ pub(crate) enum DesktopFileError {
     HiddenFile,
 }
 
+// Resolved icon of a desktop file, valid as long as the file isn't modified
+#[derive(Serialize, Deserialize)]
+struct CachedIcon {
+    mtime: u64,
+    icon: Option<PathBuf>,
+}
+
+fn modified(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
 impl ApplicationDesktopFile {
     pub(crate) fn resolve_icon(&self) -> Option<PathBuf> {
         let icon_name = self.icon.as_ref()?;
 
         lookup(icon_name).with_cache().find()
     }
+    // Searching the icon themes is slow, so remember the result per desktop file
+    pub(crate) fn resolve_icon_cached(&self, path: &Path, cache_dir: &Path) -> Option<PathBuf> {
+        let Some(mtime) = modified(path) else {
+            return self.resolve_icon();
+        };
+        let mut hasher = DefaultHasher::new();
+        path.hash(&mut hasher);
+        let cache_file = cache_dir.join(format!("{:x}.json", hasher.finish()));
+
+        if let Some(cached) = fs::read(&cache_file)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<CachedIcon>(&data).ok())
+        {
+            let valid = cached.icon.as_ref().map_or(true, |icon| icon.exists());
+            if cached.mtime == mtime && valid {
+                return cached.icon;
+            }
+        }
+
+        let icon = self.resolve_icon();
+        let cached = CachedIcon {
+            mtime,
+            icon: icon.clone(),
+        };
+        if let Ok(data) = serde_json::to_vec(&cached) {
+            let _ = fs::write(cache_file, data);
+        }
+        icon
+    }
 }
 
 impl TryFrom<&PathBuf> for ApplicationDesktopFile {
@@ -53,6 +102,10 @@ impl TryFrom<&PathBuf> for ApplicationDesktopFile {
             .map(|s| s.split(';').map(|s| s.to_string()).collect())
             .unwrap_or_default();
 
+        let startup_wm_class = content_section
+            .attr("StartupWMClass")
+            .map(|s| s.to_string());
+
         let no_display = content_section
             .attr("NoDisplay")
             .map_or(Ok(false), |s| s.parse::<bool>())
@@ -68,6 +121,7 @@ impl TryFrom<&PathBuf> for ApplicationDesktopFile {
             name,
             icon,
             keywords,
+            startup_wm_class,
         })
     }
 }
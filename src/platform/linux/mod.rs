@@ -11,22 +11,32 @@
 
 mod desktop_file;
 
+use gpui::{point, px, AsyncWindowContext, Pixels, Point, WindowAppearance, WindowContext};
+use notify::RecursiveMode;
+use url::Url;
 use walkdir::WalkDir;
 
 use crate::components::shared::{Icon, Img};
 use crate::paths::paths;
+use crate::state::LazyMutex;
 
-use std::path::PathBuf;
+use async_std::channel::{unbounded, Receiver, Sender};
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
 use std::{env, fs};
 
-use super::AppData;
+use super::{AppData, SystemAction};
 
 pub fn get_application_data(path: &PathBuf) -> Option<AppData> {
     let cache_dir = paths().cache.join("apps");
     if !cache_dir.exists() {
         fs::create_dir_all(cache_dir.clone()).unwrap();
     }
-    let cache = cache_dir.to_string_lossy().to_string();
     let last = path.components().last();
     if last.is_none() {
         return None;
@@ -35,7 +45,7 @@ pub fn get_application_data(path: &PathBuf) -> Option<AppData> {
     let file_name = last.unwrap().as_os_str().to_string_lossy().to_string();
 
     let file = desktop_file::ApplicationDesktopFile::try_from(path).ok()?;
-    let icon_url: Option<PathBuf> = file.resolve_icon();
+    let icon_url: Option<PathBuf> = file.resolve_icon_cached(path, &cache_dir);
 
     let icon_img = if let Some(icon) = icon_url.clone() {
         Img::default().file(icon)
@@ -63,7 +73,7 @@ pub fn get_application_folders() -> Vec<PathBuf> {
         }
     } else {
         let home_dir = PathBuf::from("/home").join(whoami::username());
-        let share_dir = home_dir.join(PathBuf::from("/.local/share"));
+        let share_dir = home_dir.join(".local/share");
 
         if share_dir.exists() {
             dirs.push(share_dir);
@@ -116,6 +126,340 @@ pub fn get_application_files() -> Vec<PathBuf> {
     return files;
 }
 
+// Desktop entries live in the applications subfolder of each data dir, possibly nested
+pub fn get_application_watch_folders() -> Vec<(PathBuf, RecursiveMode)> {
+    get_application_folders()
+        .into_iter()
+        .map(|dir| dir.join("applications"))
+        .filter(|dir| dir.exists())
+        .map(|dir| (dir, RecursiveMode::Recursive))
+        .collect()
+}
+
+// Window class of the focused window, as long as the session exposes it
+fn active_window_class() -> Option<String> {
+    if env::var("WAYLAND_DISPLAY").is_ok() {
+        // There is no common protocol for this, so only compositors with an IPC are supported
+        let output = Command::new("hyprctl")
+            .args(["activewindow", "-j"])
+            .output()
+            .ok()?;
+        let window = serde_json::from_slice::<serde_json::Value>(&output.stdout).ok()?;
+        return window["class"].as_str().map(|s| s.to_string());
+    }
+    let output = Command::new("xprop")
+        .args(["-root", "_NET_ACTIVE_WINDOW"])
+        .output()
+        .ok()?;
+    // _NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let id = stdout.split_whitespace().last()?;
+    if !id.starts_with("0x") || id == "0x0" {
+        return None;
+    }
+    let output = Command::new("xprop")
+        .args(["-id", id, "WM_CLASS"])
+        .output()
+        .ok()?;
+    // WM_CLASS(STRING) = "instance", "class"
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.split('"').nth(3).map(|s| s.to_string())
+}
+
+// Lowercase window class to desktop file, along with when it was built
+static WM_CLASSES: LazyMutex<Option<(Instant, HashMap<String, PathBuf>)>> =
+    LazyMutex::new(Option::default);
+
+// Exact file names win over reverse DNS names, which win over a StartupWMClass entry
+fn wm_classes() -> HashMap<String, PathBuf> {
+    let files = get_application_files();
+    let mut classes = HashMap::new();
+    for path in &files {
+        if let Some(stem) = path.file_stem() {
+            classes.insert(stem.to_string_lossy().to_lowercase(), path.clone());
+        }
+    }
+    for path in &files {
+        let Some(stem) = path.file_stem() else {
+            continue;
+        };
+        let stem = stem.to_string_lossy().to_lowercase();
+        if let Some((_, name)) = stem.rsplit_once('.') {
+            classes
+                .entry(name.to_string())
+                .or_insert_with(|| path.clone());
+        }
+    }
+    for path in &files {
+        if let Some(wm_class) = desktop_file::ApplicationDesktopFile::try_from(path)
+            .ok()
+            .and_then(|file| file.startup_wm_class)
+        {
+            classes
+                .entry(wm_class.to_lowercase())
+                .or_insert_with(|| path.clone());
+        }
+    }
+    classes
+}
+
+// Spawns xprop/hyprctl, so keep it off the main thread
 pub fn get_frontmost_application_data() -> Option<AppData> {
-    None
+    let class = active_window_class()?.to_lowercase();
+    let mut cache = WM_CLASSES.lock();
+    let mut path = cache
+        .as_ref()
+        .and_then(|(_, classes)| classes.get(&class).cloned());
+    // Unknown classes might belong to an app installed since, but don't rescan on every copy
+    let stale = cache
+        .as_ref()
+        .map(|(built, _)| built.elapsed() > Duration::from_secs(60))
+        .unwrap_or(true);
+    if path.is_none() && stale {
+        let classes = wm_classes();
+        path = classes.get(&class).cloned();
+        *cache = Some((Instant::now(), classes));
+    }
+    drop(cache);
+    get_application_data(&path?)
+}
+
+pub fn cursor_position() -> Option<Point<Pixels>> {
+    if env::var("WAYLAND_DISPLAY").is_ok() {
+        let output = Command::new("hyprctl").arg("cursorpos").output().ok()?;
+        // 1280, 720
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let (x, y) = stdout.trim().split_once(", ")?;
+        return Some(point(px(x.parse().ok()?), px(y.parse().ok()?)));
+    }
+    let output = Command::new("xdotool")
+        .args(["getmouselocation", "--shell"])
+        .output()
+        .ok()?;
+    // X=1280\nY=720\nSCREEN=0\nWINDOW=123
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        stdout
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .and_then(|value| value.parse::<f32>().ok())
+    };
+    Some(point(px(value("X=")?), px(value("Y=")?)))
+}
+
+// Appearance as of the last `refresh_system_appearance`, so looking it up never blocks
+static SYSTEM_APPEARANCE: LazyMutex<Option<WindowAppearance>> = LazyMutex::new(Option::default);
+
+pub fn system_appearance() -> Option<WindowAppearance> {
+    *SYSTEM_APPEARANCE.lock()
+}
+
+// Spawns gdbus/gsettings, so keep it off the main thread. Returns whether the appearance changed
+pub fn refresh_system_appearance() -> bool {
+    let appearance = read_system_appearance();
+    let mut last = SYSTEM_APPEARANCE.lock();
+    let changed = *last != appearance;
+    *last = appearance;
+    changed
+}
+
+// GPUI doesn't track the appearance on Linux, so ask the settings portal and fall back to the GTK theme
+fn read_system_appearance() -> Option<WindowAppearance> {
+    let output = Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.portal.Desktop",
+            "--object-path",
+            "/org/freedesktop/portal/desktop",
+            "--method",
+            "org.freedesktop.portal.Settings.Read",
+            "org.freedesktop.appearance",
+            "color-scheme",
+        ])
+        .output()
+        .ok()?;
+    // (<<uint32 1>>,) where 1 prefers dark, 2 prefers light and 0 has no preference
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if output.status.success() {
+        if stdout.contains("uint32 1") {
+            return Some(WindowAppearance::Dark);
+        }
+        if stdout.contains("uint32 2") {
+            return Some(WindowAppearance::Light);
+        }
+    }
+    let output = Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "gtk-theme"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let theme = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    Some(if theme.contains("dark") {
+        WindowAppearance::Dark
+    } else {
+        WindowAppearance::Light
+    })
+}
+
+// Moves the Loungy window, since GPUI can't reposition an open window
+pub fn move_launcher(origin: Point<Pixels>) {
+    let x = f32::from(origin.x).to_string();
+    let y = f32::from(origin.y).to_string();
+    if env::var("WAYLAND_DISPLAY").is_ok() {
+        let _ = Command::new("hyprctl")
+            .args([
+                "dispatch",
+                "movewindowpixel",
+                &format!("exact {} {},title:^Loungy$", x, y),
+            ])
+            .spawn();
+        return;
+    }
+    let _ = Command::new("xdotool")
+        .args(["search", "--name", "^Loungy$", "windowmove", &x, &y])
+        .spawn();
+}
+
+// There is no app wide hiding on Linux, so this only hides the launcher window
+pub fn hide_launcher(cx: &mut WindowContext) {
+    cx.hide();
+}
+
+pub fn system_action(action: SystemAction) -> std::io::Result<()> {
+    let user = whoami::username();
+    let (program, args) = match action {
+        SystemAction::Lock => ("loginctl", vec!["lock-session"]),
+        SystemAction::Sleep => ("systemctl", vec!["suspend"]),
+        SystemAction::LogOut => ("loginctl", vec!["terminate-user", user.as_str()]),
+        SystemAction::Restart => ("systemctl", vec!["reboot"]),
+        SystemAction::ShutDown => ("systemctl", vec!["poweroff"]),
+        SystemAction::EmptyTrash => ("gio", vec!["trash", "--empty"]),
+    };
+    Command::new(program).args(args).spawn().map(|_| ())
+}
+
+pub static REVEAL_LABEL: &str = "Show in File Manager";
+
+pub fn open_path(path: &Path) {
+    let _ = Command::new("xdg-open").arg(path).spawn();
+}
+
+// Asks the file manager to select the file over D-Bus, not every file manager implements that
+// so the containing folder is opened as a fallback
+pub fn reveal_path(path: &Path) {
+    let path = path.to_path_buf();
+    thread::spawn(move || {
+        let shown = Url::from_file_path(&path).ok().is_some_and(|uri| {
+            Command::new("dbus-send")
+                .args([
+                    "--session",
+                    "--print-reply",
+                    "--dest=org.freedesktop.FileManager1",
+                    "--type=method_call",
+                    "/org/freedesktop/FileManager1",
+                    "org.freedesktop.FileManager1.ShowItems",
+                    &format!("array:string:{}", uri),
+                    "string:",
+                ])
+                .output()
+                .is_ok_and(|output| output.status.success())
+        });
+        if !shown {
+            if let Some(parent) = path.parent() {
+                let _ = Command::new("xdg-open").arg(parent).spawn();
+            }
+        }
+    });
+}
+
+fn clicked_notifications() -> &'static (Sender<String>, Receiver<String>) {
+    static CLICKED: OnceLock<(Sender<String>, Receiver<String>)> = OnceLock::new();
+    CLICKED.get_or_init(unbounded)
+}
+
+// notify-send waits for the notification to be clicked or dismissed, which is why it gets a thread
+pub fn send_notification(id: &str, title: &str, body: &str) {
+    let id = id.to_string();
+    let mut command = Command::new("notify-send");
+    command.args([
+        "--app-name",
+        "Loungy",
+        "--action",
+        "default=Open",
+        "--wait",
+        title,
+        body,
+    ]);
+    thread::spawn(move || {
+        let Ok(output) = command.output() else {
+            return;
+        };
+        // The name of the invoked action is printed, nothing if it was dismissed
+        if String::from_utf8_lossy(&output.stdout).trim() == "default" {
+            let _ = clicked_notifications().0.try_send(id);
+        }
+    });
+}
+
+// Resolves once notify-send reports a click
+pub async fn next_clicked_notification() -> Option<String> {
+    clicked_notifications().1.recv().await.ok()
+}
+
+// Files copied in a file manager, as of the last poll
+static CLIPBOARD_FILES: LazyMutex<Option<Vec<PathBuf>>> = LazyMutex::new(Option::default);
+
+pub fn clipboard_files() -> Option<Vec<PathBuf>> {
+    CLIPBOARD_FILES.lock().clone()
+}
+
+// Arboard can't read the uri-list, so this spawns wl-paste/xclip and blocks until they exit
+fn read_clipboard_files() -> Option<Vec<PathBuf>> {
+    let output = if env::var("WAYLAND_DISPLAY").is_ok() {
+        Command::new("wl-paste")
+            .args(["--no-newline", "--type", "text/uri-list"])
+            .output()
+    } else {
+        Command::new("xclip")
+            .args([
+                "-selection",
+                "clipboard",
+                "-target",
+                "text/uri-list",
+                "-out",
+            ])
+            .output()
+    }
+    .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let paths: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| Url::parse(line.trim()).ok()?.to_file_path().ok())
+        .collect();
+    (!paths.is_empty()).then_some(paths)
+}
+
+// There is no change counter for the X11/Wayland clipboards, so poll and let the caller dedupe
+pub async fn clipboard(
+    mut on_change: impl FnMut(&mut AsyncWindowContext),
+    mut interval: impl FnMut(&mut AsyncWindowContext) -> Duration,
+    mut cx: AsyncWindowContext,
+) {
+    loop {
+        let files = cx
+            .background_executor()
+            .spawn(async { read_clipboard_files() })
+            .await;
+        *CLIPBOARD_FILES.lock() = files;
+        on_change(&mut cx);
+        let interval = interval(&mut cx);
+        cx.background_executor().timer(interval).await;
+    }
 }
@@ -11,17 +11,20 @@
 
 use crate::components::shared::Img;
 use crate::paths::paths;
+use crate::state::Toast;
 use crate::window::Window;
 use cocoa::appkit::NSPasteboard;
-use gpui::{AsyncWindowContext, WindowContext};
+use gpui::{point, px, AsyncWindowContext, Pixels, Point, WindowAppearance, WindowContext};
+use notify::RecursiveMode;
 use std::time::Duration;
 use std::{
     fs,
     path::{Path, PathBuf},
+    process::Command,
 };
-use swift_rs::{swift, Bool, SRObject, SRString};
+use swift_rs::{swift, Bool, Double, Int, SRObject, SRString};
 
-use super::{AppData, ClipboardWatcher};
+use super::{AppData, ClipboardWatcher, SystemAction};
 
 #[repr(C)]
 struct AppDataMac {
@@ -104,6 +107,15 @@ pub fn get_application_folders() -> Vec<PathBuf> {
     user_dirs
 }
 
+// Bundles sit directly in the folders, so changes inside an app don't trigger a re-index
+pub fn get_application_watch_folders() -> Vec<(PathBuf, RecursiveMode)> {
+    get_application_folders()
+        .into_iter()
+        .filter(|dir| dir.exists())
+        .map(|dir| (dir, RecursiveMode::NonRecursive))
+        .collect()
+}
+
 pub fn get_application_files() -> Vec<PathBuf> {
     let mut files = Vec::new();
 
@@ -149,6 +161,8 @@ pub fn get_frontmost_application_data() -> Option<AppData> {
 
 swift!( fn paste(value: SRString, formatting: Bool));
 
+swift!( fn paste_html(html: SRString, text: SRString));
+
 swift!( fn copy_file(path: SRString));
 
 swift!( fn paste_file(path: SRString));
@@ -166,6 +180,20 @@ pub fn close_and_paste(value: &str, formatting: bool, cx: &mut WindowContext) {
     .detach();
 }
 
+pub fn close_and_paste_html(html: &str, text: &str, cx: &mut WindowContext) {
+    Window::close(cx);
+    let html = html.to_string();
+    let text = text.to_string();
+    cx.spawn(move |mut cx| async move {
+        Window::wait_for_close(&mut cx).await;
+        ClipboardWatcher::disabled(&mut cx);
+        unsafe {
+            paste_html(SRString::from(html.as_str()), SRString::from(text.as_str()));
+        }
+    })
+    .detach();
+}
+
 pub fn close_and_paste_file(path: &Path, cx: &mut WindowContext) {
     Window::close(cx);
     let path = path.to_string_lossy().to_string();
@@ -179,6 +207,36 @@ pub fn close_and_paste_file(path: &Path, cx: &mut WindowContext) {
     .detach();
 }
 
+const ACCESSIBILITY_SETTINGS: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility";
+
+pub fn accessibility_trusted() -> bool {
+    swift!( fn accessibility_trusted() -> Bool);
+    unsafe { accessibility_trusted() }
+}
+
+pub fn open_accessibility_settings() {
+    let _ = Command::new("open").arg(ACCESSIBILITY_SETTINGS).spawn();
+}
+
+// Pasting, autofill and the menu bar go through AX, which silently does nothing without access,
+// so this explains why and links to the settings pane instead
+pub fn require_accessibility(reason: &str, toast: &mut Toast, cx: &mut WindowContext) -> bool {
+    if accessibility_trusted() {
+        return true;
+    }
+    toast.error_with_action(
+        format!("Loungy needs Accessibility access to {}", reason),
+        "Open Settings",
+        |cx| {
+            Window::close(cx);
+            open_accessibility_settings();
+        },
+        cx,
+    );
+    false
+}
+
 // Function to wait for an input element to be focused and then using AX to fill it
 pub fn autofill(value: &str, password: bool, prev: &str) -> Option<String> {
     unsafe {
@@ -192,13 +250,141 @@ pub fn autofill(value: &str, password: bool, prev: &str) -> Option<String> {
     .map(|s| s.to_string())
 }
 
+pub fn keychain_set(service: &str, account: &str, secret: &str) -> bool {
+    swift!( fn keychain_set(service: SRString, account: SRString, secret: SRString) -> Bool);
+    unsafe {
+        keychain_set(
+            SRString::from(service),
+            SRString::from(account),
+            SRString::from(secret),
+        )
+    }
+}
+
+// Blocks until the user passed (or failed) the biometric check, so call it off the main thread
+pub fn keychain_get(service: &str, account: &str, reason: &str) -> Option<String> {
+    swift!( fn keychain_get(service: SRString, account: SRString, reason: SRString) -> Option<SRString>);
+    unsafe {
+        keychain_get(
+            SRString::from(service),
+            SRString::from(account),
+            SRString::from(reason),
+        )
+    }
+    .map(|s| s.to_string())
+}
+
+pub fn keychain_delete(service: &str, account: &str) -> bool {
+    swift!( fn keychain_delete(service: SRString, account: SRString) -> Bool);
+    unsafe { keychain_delete(SRString::from(service), SRString::from(account)) }
+}
+
+// The id is handed back by next_clicked_notification once the user clicks the notification
+pub fn send_notification(id: &str, title: &str, body: &str) {
+    swift!( fn send_notification(id: SRString, title: SRString, body: SRString));
+    unsafe {
+        send_notification(
+            SRString::from(id),
+            SRString::from(title),
+            SRString::from(body),
+        )
+    }
+}
+
+// Swift can't call back into Rust, so its queue of clicks is polled
+pub async fn next_clicked_notification() -> Option<String> {
+    swift!( fn clicked_notification() -> Option<SRString>);
+    loop {
+        if let Some(id) = unsafe { clicked_notification() } {
+            return Some(id.to_string());
+        }
+        async_std::task::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+pub fn define_word(word: &str) -> Option<String> {
+    swift!( fn define_word(word: SRString) -> Option<SRString>);
+    unsafe { define_word(SRString::from(word)) }.map(|definition| definition.to_string())
+}
+
 pub fn ocr(path: &Path) {
     swift!( fn ocr(path: SRString));
     unsafe { ocr(SRString::from(path.to_string_lossy().to_string().as_str())) }
 }
 
+#[derive(Debug)]
+pub enum WindowError {
+    NotTrusted,
+    NoWindow,
+}
+
+// Moves and resizes the focused window of the frontmost application using AX
+pub fn move_window(position: &str) -> Result<(), WindowError> {
+    swift!( fn window_move(position: SRString) -> Int);
+    match unsafe { window_move(SRString::from(position)) } {
+        0 => Ok(()),
+        1 => Err(WindowError::NotTrusted),
+        _ => Err(WindowError::NoWindow),
+    }
+}
+
+pub fn cursor_position() -> Option<Point<Pixels>> {
+    swift!( fn cursor_x() -> Double);
+    swift!( fn cursor_y() -> Double);
+    unsafe { Some(point(px(cursor_x() as f32), px(cursor_y() as f32))) }
+}
+
+// GPUI already follows NSApp's effectiveAppearance on macOS
+pub fn system_appearance() -> Option<WindowAppearance> {
+    None
+}
+
+// Moves the Loungy window, since GPUI can't reposition an open window
+pub fn move_launcher(origin: Point<Pixels>) {
+    swift!( fn launcher_move(x: Double, y: Double));
+    unsafe { launcher_move(f32::from(origin.x) as Double, f32::from(origin.y) as Double) }
+}
+
+// Orders out just the Loungy window, unlike cx.hide() which hides every window of the app
+pub fn hide_launcher(_: &mut WindowContext) {
+    swift!( fn launcher_hide());
+    unsafe { launcher_hide() }
+}
+
+pub fn system_action(action: SystemAction) -> std::io::Result<()> {
+    let script = match action {
+        SystemAction::Sleep => {
+            return Command::new("pmset").arg("sleepnow").spawn().map(|_| ());
+        }
+        SystemAction::Lock => {
+            r#"tell application "System Events" to keystroke "q" using {command down, control down}"#
+        }
+        SystemAction::LogOut => r#"tell application "System Events" to log out"#,
+        SystemAction::Restart => r#"tell application "System Events" to restart"#,
+        SystemAction::ShutDown => r#"tell application "System Events" to shut down"#,
+        SystemAction::EmptyTrash => r#"tell application "Finder" to empty trash"#,
+    };
+    Command::new("osascript")
+        .arg("-e")
+        .arg(script)
+        .spawn()
+        .map(|_| ())
+}
+
+pub static REVEAL_LABEL: &str = "Reveal in Finder";
+
+pub fn open_path(path: &Path) {
+    let _ = Command::new("open").arg(path).spawn();
+}
+
+pub fn reveal_path(path: &Path) {
+    let _ = Command::new("open").arg("-R").arg(path).spawn();
+}
+
+// Reading the change count is cheap, so the clipboard contents are only read after it changed
 pub async fn clipboard(
     mut on_change: impl FnMut(&mut AsyncWindowContext),
+    mut interval: impl FnMut(&mut AsyncWindowContext) -> Duration,
     mut cx: AsyncWindowContext,
 ) {
     unsafe {
@@ -210,9 +396,8 @@ pub async fn clipboard(
                 change_count = pasteboard.changeCount();
                 on_change(&mut cx);
             }
-            cx.background_executor()
-                .timer(Duration::from_millis(50))
-                .await;
+            let interval = interval(&mut cx);
+            cx.background_executor().timer(interval).await;
         }
     }
 }
@@ -10,13 +10,16 @@
  */
 
 use crate::components::shared::Img;
-use gpui::{AppContext, AsyncAppContext, Global};
+use arboard::{Clipboard, ImageData};
+use gpui::{AppContext, AsyncAppContext, Global, WindowContext};
 
 #[cfg(target_os = "linux")]
 mod linux;
 #[cfg(target_os = "linux")]
 pub use linux::*;
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
 #[cfg(target_os = "macos")]
 mod mac;
 #[cfg(target_os = "macos")]
@@ -55,3 +58,88 @@ impl ClipboardWatcher {
     }
 }
 impl Global for ClipboardWatcher {}
+
+type NotificationHandler = Rc<dyn Fn(&str, &mut WindowContext)>;
+
+// Clicked notifications go to the handler registered for the prefix of their id, so commands
+// never see each other's clicks
+#[derive(Default)]
+pub struct NotificationRouter {
+    handlers: HashMap<&'static str, NotificationHandler>,
+}
+impl NotificationRouter {
+    pub fn register(
+        prefix: &'static str,
+        handler: impl Fn(&str, &mut WindowContext) + 'static,
+        cx: &mut WindowContext,
+    ) {
+        if !cx.has_global::<Self>() {
+            cx.set_global(Self::default());
+            cx.spawn(|mut cx| async move {
+                while let Some(id) = next_clicked_notification().await {
+                    let _ = cx.update(|cx| {
+                        let handler = cx
+                            .global::<Self>()
+                            .handlers
+                            .iter()
+                            .find(|(prefix, _)| id.starts_with(*prefix))
+                            .map(|(_, handler)| handler.clone());
+                        if let Some(handler) = handler {
+                            handler(&id, cx);
+                        }
+                    });
+                }
+            })
+            .detach();
+        }
+        cx.global_mut::<Self>()
+            .handlers
+            .insert(prefix, Rc::new(handler));
+    }
+}
+impl Global for NotificationRouter {}
+
+// Copying skips the paste keystroke, so unlike pasting it works without accessibility permissions
+fn copy_with(
+    write: impl FnOnce(&mut Clipboard) -> Result<(), arboard::Error>,
+    cx: &mut AppContext,
+) -> anyhow::Result<()> {
+    write(&mut Clipboard::new()?)?;
+    // Same as pasting, the copied value shouldn't show up as a new clipboard history entry
+    if cx.has_global::<ClipboardWatcher>() {
+        cx.global_mut::<ClipboardWatcher>().enabled = false;
+    }
+    Ok(())
+}
+
+pub fn copy_text(text: &str, cx: &mut AppContext) -> anyhow::Result<()> {
+    copy_with(|cp| cp.set_text(text), cx)
+}
+
+pub fn copy_html(html: &str, text: &str, cx: &mut AppContext) -> anyhow::Result<()> {
+    copy_with(|cp| cp.set_html(html, Some(text)), cx)
+}
+
+pub fn copy_image(path: &Path, cx: &mut AppContext) -> anyhow::Result<()> {
+    let image = image::open(path)?.to_rgba8();
+    copy_with(
+        |cp| {
+            cp.set_image(ImageData {
+                width: image.width() as usize,
+                height: image.height() as usize,
+                bytes: image.as_raw().into(),
+            })
+        },
+        cx,
+    )
+}
+
+#[derive(Clone, Copy)]
+pub enum SystemAction {
+    Lock,
+    Sleep,
+    LogOut,
+    Restart,
+    ShutDown,
+    EmptyTrash,
+}
@@ -16,6 +16,9 @@ use log::debug;
 
 use crate::theme::Theme;
 
+// Maximum number of undo steps kept per input
+static HISTORY_LIMIT: usize = 100;
+
 #[derive(IntoElement, Clone)]
 pub struct TextInput {
     pub focus_handle: FocusHandle,
@@ -85,6 +88,14 @@ pub struct TextView {
     pub word_click: (usize, u16),
     pub placeholder: String,
     pub masked: bool,
+    // Leaves j/k on an empty query to the list for vim-style navigation
+    pub navigation_keys: bool,
+    // Keystrokes it returns true for aren't typed, e.g. the keys of a multi-key shortcut
+    pub swallow: Option<Box<dyn Fn(&Keystroke, &mut WindowContext) -> bool>>,
+    undo: Vec<(String, Range<usize>)>,
+    redo: Vec<(String, Range<usize>)>,
+    // Whether the last edit was typing, so the next keystroke joins its undo step
+    typing: bool,
 }
 
 impl TextView {
@@ -95,6 +106,11 @@ impl TextView {
             word_click: (0, 0),
             placeholder: "Type here...".to_string(),
             masked: false,
+            navigation_keys: false,
+            swallow: None,
+            undo: vec![],
+            redo: vec![],
+            typing: false,
         };
         let view = cx.new_view(|cx| {
             #[cfg(debug_assertions)]
@@ -136,6 +152,8 @@ impl TextView {
     pub fn reset(&mut self, cx: &mut ViewContext<Self>) {
         self.text = "".to_string();
         self.selection = 0..0;
+        self.undo.clear();
+        self.redo.clear();
         cx.notify();
         cx.emit(TextEvent::Input {
             text: self.text.clone(),
@@ -154,10 +172,104 @@ impl TextView {
             .len();
         start..end
     }
+    pub fn text_range_to_char_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.text[..range.start].chars().count();
+        start..start + self.text[range].chars().count()
+    }
+    // Byte range of the line around the given byte index
+    pub fn line_range(&self, i: usize) -> Range<usize> {
+        let start = self.text[..i].rfind('\n').map(|n| n + 1).unwrap_or(0);
+        let end = self.text[i..]
+            .find('\n')
+            .map(|n| i + n)
+            .unwrap_or(self.text.len());
+        start..end
+    }
     pub fn select_all(&mut self, cx: &mut ViewContext<Self>) {
         self.selection = 0..self.text.chars().count();
         cx.notify();
     }
+    // Records the state before an edit, consecutive typing is coalesced into one step
+    fn push_history(&mut self, text: String, selection: Range<usize>, typing: bool) {
+        if !(typing && self.typing) {
+            self.undo.push((text, selection));
+            if self.undo.len() > HISTORY_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.redo.clear();
+        self.typing = typing;
+    }
+    pub fn undo(&mut self) {
+        if let Some((text, selection)) = self.undo.pop() {
+            let current = std::mem::replace(&mut self.text, text);
+            self.redo
+                .push((current, std::mem::replace(&mut self.selection, selection)));
+        }
+        self.typing = false;
+    }
+    pub fn redo(&mut self) {
+        if let Some((text, selection)) = self.redo.pop() {
+            let current = std::mem::replace(&mut self.text, text);
+            self.undo
+                .push((current, std::mem::replace(&mut self.selection, selection)));
+        }
+        self.typing = false;
+    }
+    // Character index of the closest word boundary before or after the given character index
+    pub fn word_boundary(&self, i: usize, forward: bool) -> usize {
+        let byte = self
+            .text
+            .char_indices()
+            .nth(i)
+            .map(|(byte, _)| byte)
+            .unwrap_or(self.text.len());
+        let words = self.word_ranges();
+        let boundary = if forward {
+            words
+                .iter()
+                .map(|word| word.end)
+                .find(|end| *end > byte)
+                .unwrap_or(self.text.len())
+        } else {
+            words
+                .iter()
+                .rev()
+                .map(|word| word.start)
+                .find(|start| *start < byte)
+                .unwrap_or(0)
+        };
+        self.text[..boundary].chars().count()
+    }
+    // Moves the cursor, or only the start/end of the selection when extending it
+    pub fn move_cursor(&mut self, i: usize, forward: bool, extend: bool) {
+        if !extend {
+            self.selection = i..i;
+        } else if forward {
+            self.selection.end = i.max(self.selection.start);
+        } else {
+            self.selection.start = i.min(self.selection.end);
+        }
+    }
+    // Deletes back to the start of the word under or before the cursor
+    pub fn delete_word(&mut self) {
+        let range = self.char_range_to_text_range(&self.text);
+        if range.is_empty() {
+            let start = self
+                .word_ranges()
+                .iter()
+                .rev()
+                .map(|word| word.start)
+                .find(|start| *start < range.start)
+                .unwrap_or(0);
+            self.text.replace_range(start..range.start, "");
+            let i = self.text[..start].chars().count();
+            self.selection = i..i;
+        } else {
+            self.text.replace_range(range, "");
+            self.selection.end = self.selection.start;
+        }
+    }
     pub fn word_ranges(&self) -> Vec<Range<usize>> {
         let mut words = Vec::new();
         let mut last_was_boundary = true;
@@ -206,6 +318,9 @@ impl RenderOnce for TextInput {
             .on_key_down(move |ev, cx| {
                 self.view.update(cx, |editor, cx| {
                     let prev = editor.text.clone();
+                    let prev_selection = editor.selection.clone();
+                    let mut typing = false;
+                    let mut history = false;
                     cx.emit(TextEvent::KeyDown(ev.clone()));
                     let keystroke = &ev.keystroke.key;
                     let chars = editor.text.chars().collect::<Vec<char>>();
@@ -215,6 +330,22 @@ impl RenderOnce for TextInput {
                     let m = ev.keystroke.modifiers.control;
 
                     let ime_key = &ev.keystroke.ime_key;
+                    let shift = ev.keystroke.modifiers.shift;
+
+                    if editor.navigation_keys
+                        && editor.text.is_empty()
+                        && !ev.keystroke.modifiers.modified()
+                        && matches!(keystroke.as_str(), "j" | "k")
+                    {
+                        return;
+                    }
+                    if editor
+                        .swallow
+                        .as_ref()
+                        .is_some_and(|swallow| swallow(&ev.keystroke, cx))
+                    {
+                        return;
+                    }
 
                     if m {
                         match keystroke.as_str() {
@@ -252,6 +383,30 @@ impl RenderOnce for TextInput {
                                 );
                                 editor.selection.end = editor.selection.start;
                             }
+                            "left" | "right" => {
+                                let forward = keystroke.as_str() == "right";
+                                #[cfg(target_os = "macos")]
+                                let i = if forward { chars.len() } else { 0 };
+                                #[cfg(not(target_os = "macos"))]
+                                let i = if forward {
+                                    editor.word_boundary(editor.selection.end, true)
+                                } else {
+                                    editor.word_boundary(editor.selection.start, false)
+                                };
+                                editor.move_cursor(i, forward, shift);
+                            }
+                            "z" => {
+                                if shift {
+                                    editor.redo();
+                                } else {
+                                    editor.undo();
+                                }
+                                history = true;
+                            }
+                            #[cfg(not(target_os = "macos"))]
+                            "w" | "backspace" => {
+                                editor.delete_word();
+                            }
                             _ => {}
                         }
                     } else if let Some(ime_key) = ime_key {
@@ -260,31 +415,42 @@ impl RenderOnce for TextInput {
                             .replace_range(editor.char_range_to_text_range(&editor.text), ime_key);
                         let i = editor.selection.start + ime_key.chars().count();
                         editor.selection = i..i;
+                        typing = true;
                     } else {
                         match keystroke.as_str() {
                             "left" => {
-                                if editor.selection.start > 0 {
-                                    let i = if editor.selection.start == editor.selection.end {
-                                        editor.selection.start - 1
-                                    } else {
-                                        editor.selection.start
-                                    };
-                                    editor.selection = i..i;
-                                }
+                                let start = editor.selection.start;
+                                let i = if ev.keystroke.modifiers.alt {
+                                    editor.word_boundary(start, false)
+                                } else if shift || editor.selection.is_empty() {
+                                    start.saturating_sub(1)
+                                } else {
+                                    start
+                                };
+                                editor.move_cursor(i, false, shift);
                             }
                             "right" => {
-                                if editor.selection.end < editor.text.len() {
-                                    let i = if editor.selection.start == editor.selection.end {
-                                        editor.selection.end + 1
-                                    } else {
-                                        editor.selection.end
-                                    };
-                                    editor.selection = i..i;
-                                }
+                                let end = editor.selection.end;
+                                let i = if ev.keystroke.modifiers.alt {
+                                    editor.word_boundary(end, true)
+                                } else if shift || editor.selection.is_empty() {
+                                    (end + 1).min(chars.len())
+                                } else {
+                                    end
+                                };
+                                editor.move_cursor(i, true, shift);
+                            }
+                            "home" => {
+                                editor.move_cursor(0, false, shift);
+                            }
+                            "end" => {
+                                editor.move_cursor(chars.len(), true, shift);
                             }
                             "backspace" => {
                                 if editor.text.is_empty() && !ev.is_held {
                                     cx.emit(TextEvent::Back);
+                                } else if ev.keystroke.modifiers.alt {
+                                    editor.delete_word();
                                 } else if editor.selection.start == editor.selection.end
                                     && editor.selection.start > 0
                                 {
@@ -315,6 +481,13 @@ impl RenderOnce for TextInput {
                             _ => {}
                         };
                     }
+                    if !history {
+                        if prev != editor.text {
+                            editor.push_history(prev.clone(), prev_selection, typing);
+                        } else {
+                            editor.typing = false;
+                        }
+                    }
                     if prev != editor.text {
                         cx.emit(TextEvent::Input {
                             text: editor.text.clone(),
@@ -338,7 +511,7 @@ impl Render for TextView {
 
         let mut text = self.text.clone();
         let mut selection_style = HighlightStyle::default();
-        let mut color = theme.lavender;
+        let mut color = theme.accent();
         color.fade_out(0.8);
         selection_style.background_color = Some(color);
 
@@ -371,14 +544,17 @@ impl Render for TextView {
                 match count {
                     2 => {
                         let word_ranges = editor.word_ranges();
-                        editor.selection = word_ranges.get(ev).unwrap().clone();
+                        editor.selection =
+                            editor.text_range_to_char_range(word_ranges.get(ev).unwrap().clone());
                     }
                     3 => {
-                        // Should select the line
+                        let word_ranges = editor.word_ranges();
+                        let line = editor.line_range(word_ranges.get(ev).unwrap().start);
+                        editor.selection = editor.text_range_to_char_range(line);
                     }
                     4 => {
                         count = 0;
-                        editor.selection = 0..editor.text.len();
+                        editor.selection = 0..editor.text.chars().count();
                     }
                     _ => {}
                 }
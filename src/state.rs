@@ -12,21 +12,24 @@
 use gpui::*;
 use log::debug;
 use parking_lot::{Mutex, MutexGuard};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::{
+    cell::Cell,
+    collections::VecDeque,
     ops::DerefMut,
     rc::Rc,
     time::{Duration, Instant},
 };
 
 use crate::{
-    commands::root::list::RootListBuilder,
+    commands::{root::list::RootListBuilder, RootCommands},
     components::{
-        list::{Accessory, ItemBuilder, List, ListBuilder, ListItem},
+        list::{Accessory, Item, ItemBuilder, ItemComponent, List, ListBuilder, ListItem},
         shared::{Icon, Img, ImgMask, ImgSize},
     },
+    db::db,
     query::{TextEvent, TextInput, TextInputWeak},
-    theme::{self, Theme},
+    theme::{self, ReduceMotion, Theme},
     window::{Window, WindowStyle},
 };
 
@@ -48,34 +51,108 @@ impl<T> LazyMutex<T> {
     }
 }
 
-#[derive(Clone, PartialEq)]
+pub trait ToastActionFn: Fn(&mut WindowContext) + 'static {}
+impl<F> ToastActionFn for F where F: Fn(&mut WindowContext) + 'static {}
+
+#[derive(Clone)]
 pub enum ToastState {
     Success {
         message: SharedString,
         fade_in: Instant,
         fade_out: Option<Instant>,
+        // Label and callback of a button shown next to the message, e.g. to undo
+        action: Option<(SharedString, Rc<dyn ToastActionFn>)>,
     },
     Error {
         message: SharedString,
         fade_in: Instant,
         fade_out: Option<Instant>,
+        // Label and callback of a button, e.g. to fix what went wrong
+        action: Option<(SharedString, Rc<dyn ToastActionFn>)>,
     },
     Loading {
         message: SharedString,
         fade_in: Instant,
         fade_out: Option<Instant>,
     },
+    // Like loading, but with a known fraction (0.0 - 1.0) of the work done
+    Progress {
+        message: SharedString,
+        fraction: f32,
+        fade_in: Instant,
+        fade_out: Option<Instant>,
+    },
     Idle,
 }
 
 impl ToastState {
-    fn dot(color: Hsla) -> AnyElement {
+    fn fade_out(&self) -> Option<Instant> {
+        match self {
+            ToastState::Success { fade_out, .. }
+            | ToastState::Error { fade_out, .. }
+            | ToastState::Loading { fade_out, .. }
+            | ToastState::Progress { fade_out, .. } => *fade_out,
+            ToastState::Idle => None,
+        }
+    }
+    // Restarts the timings, for toasts that were waiting in the queue
+    fn restart(self) -> Self {
+        let now = Instant::now();
+        let shift =
+            |fade_in: Instant, fade_out: Option<Instant>| fade_out.map(|at| now + (at - fade_in));
+        match self {
+            ToastState::Success {
+                message,
+                fade_in,
+                fade_out,
+                action,
+            } => ToastState::Success {
+                message,
+                fade_in: now,
+                fade_out: shift(fade_in, fade_out),
+                action,
+            },
+            ToastState::Error {
+                message,
+                fade_in,
+                fade_out,
+                action,
+            } => ToastState::Error {
+                message,
+                fade_in: now,
+                fade_out: shift(fade_in, fade_out),
+                action,
+            },
+            ToastState::Loading {
+                message,
+                fade_in,
+                fade_out,
+            } => ToastState::Loading {
+                message,
+                fade_in: now,
+                fade_out: shift(fade_in, fade_out),
+            },
+            ToastState::Progress {
+                message,
+                fraction,
+                fade_in,
+                fade_out,
+            } => ToastState::Progress {
+                message,
+                fraction,
+                fade_in: now,
+                fade_out: shift(fade_in, fade_out),
+            },
+            ToastState::Idle => ToastState::Idle,
+        }
+    }
+    fn dot(color: Hsla, reduced: bool) -> AnyElement {
         let size = Pixels(6.0);
         div()
             .size_6()
             .flex()
             .relative()
-            .child(
+            .children((!reduced).then(|| {
                 div()
                     .size(size)
                     .absolute()
@@ -97,8 +174,8 @@ impl ToastState {
                                 div.bg(color).size(size)
                             }
                         },
-                    ),
-            )
+                    )
+            }))
             .child(
                 div()
                     .size(size)
@@ -126,13 +203,20 @@ impl ToastState {
 impl Render for ToastState {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let theme = cx.global::<theme::Theme>();
+        let reduced = ReduceMotion::enabled(cx);
+        let action = match self {
+            ToastState::Success { action, .. } | ToastState::Error { action, .. } => action.clone(),
+            _ => None,
+        };
+        let view = cx.view().clone();
         if let Some((el, bg, message, fade_in, fade_out)) = match self {
             ToastState::Success {
                 message,
                 fade_in,
                 fade_out,
+                ..
             } => Some((
-                ToastState::dot(theme.green),
+                ToastState::dot(theme.green, reduced),
                 theme.green,
                 message,
                 fade_in,
@@ -142,8 +226,9 @@ impl Render for ToastState {
                 message,
                 fade_in,
                 fade_out,
+                ..
             } => Some((
-                ToastState::dot(theme.red),
+                ToastState::dot(theme.red, reduced),
                 theme.red,
                 message,
                 fade_in,
@@ -164,9 +249,33 @@ impl Render for ToastState {
                 fade_in,
                 fade_out,
             )),
+            ToastState::Progress {
+                message,
+                fraction,
+                fade_in,
+                fade_out,
+            } => Some((
+                div()
+                    .w_12()
+                    .h_1p5()
+                    .rounded_full()
+                    .bg(theme.surface0)
+                    .child(
+                        div()
+                            .h_full()
+                            .rounded_full()
+                            .bg(theme.blue)
+                            .w(relative(fraction.clamp(0.0, 1.0))),
+                    )
+                    .into_any_element(),
+                theme.blue,
+                message,
+                fade_in,
+                fade_out,
+            )),
             ToastState::Idle => None,
         } {
-            div()
+            let toast = div()
                 .absolute()
                 .bottom_0()
                 .h_full()
@@ -178,6 +287,29 @@ impl Render for ToastState {
                 .text_color(theme.text)
                 .font_weight(FontWeight::MEDIUM)
                 .child(message.to_string())
+                .children(action.map(|(label, action)| {
+                    div()
+                        .ml_auto()
+                        .px_2()
+                        .rounded_md()
+                        .bg(theme.surface0)
+                        .cursor_pointer()
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .child(label)
+                        .on_mouse_down(MouseButton::Left, move |_, cx| {
+                            action(cx);
+                            view.update(cx, |this, cx| {
+                                *this = ToastState::Idle;
+                                cx.notify();
+                            });
+                        })
+                }));
+            if reduced {
+                let mut bg = bg;
+                bg.a = 0.1;
+                return toast.bg(bg).into_any_element();
+            }
+            toast
                 .with_animation(
                     "toast-pulse",
                     Animation::new(Duration::from_secs(3))
@@ -222,48 +354,192 @@ impl Render for ToastState {
 #[derive(Clone)]
 pub struct Toast {
     pub state: View<ToastState>,
+    // Toasts waiting for the visible one to fade out
+    queue: Model<VecDeque<ToastState>>,
 }
 
 impl Toast {
     pub fn init(cx: &mut WindowContext) -> Self {
         let state = cx.new_view(|_| ToastState::Idle);
-        Self { state }
+        let queue = cx.new_model(|_| VecDeque::new());
+        Self { state, queue }
     }
-    pub fn loading<C: VisualContext>(&mut self, message: impl ToString, cx: &mut C) {
+    fn show<C: VisualContext>(&mut self, toast: ToastState, cx: &mut C) {
+        let queue = self.queue.clone();
         self.state.update(cx, |this, cx| {
-            *this = ToastState::Loading {
+            // Loading toasts are replaced right away, finished ones get their time to be read
+            let busy = !matches!(
+                this,
+                ToastState::Loading { .. } | ToastState::Progress { .. }
+            ) && this.fade_out().is_some_and(|at| at > Instant::now());
+            if busy {
+                queue.update(cx, |queue, _| {
+                    if matches!(
+                        queue.back(),
+                        Some(ToastState::Loading { .. } | ToastState::Progress { .. })
+                    ) {
+                        queue.pop_back();
+                    }
+                    queue.push_back(toast);
+                });
+                return;
+            }
+            *this = toast;
+            cx.notify();
+            Self::advance(this, queue, cx);
+        });
+    }
+    // Shows the next queued toast once the current one faded out
+    fn advance(
+        state: &ToastState,
+        queue: Model<VecDeque<ToastState>>,
+        cx: &mut ViewContext<ToastState>,
+    ) {
+        let Some(fade_out) = state.fade_out() else {
+            return;
+        };
+        cx.spawn(move |view, mut cx| async move {
+            cx.background_executor()
+                .timer(
+                    fade_out.saturating_duration_since(Instant::now()) + Duration::from_millis(300),
+                )
+                .await;
+            let _ = view.update(&mut cx, |this, cx| {
+                // Another toast took over in the meantime
+                if this.fade_out() != Some(fade_out) {
+                    return;
+                }
+                if let Some(next) = queue.update(cx, |queue, _| queue.pop_front()) {
+                    *this = next.restart();
+                    cx.notify();
+                    Self::advance(this, queue.clone(), cx);
+                }
+            });
+        })
+        .detach();
+    }
+    pub fn loading<C: VisualContext>(&mut self, message: impl ToString, cx: &mut C) {
+        self.show(
+            ToastState::Loading {
                 message: message.to_string().into(),
                 fade_in: Instant::now(),
                 fade_out: None,
-            };
-            cx.notify();
+            },
+            cx,
+        );
+    }
+    // Updates the fraction in place while the same progress toast is showing
+    pub fn progress<C: VisualContext>(
+        &mut self,
+        message: impl ToString,
+        fraction: f32,
+        cx: &mut C,
+    ) {
+        let message: SharedString = message.to_string().into();
+        let mut updated = false;
+        self.state.update(cx, |this, cx| {
+            if let ToastState::Progress {
+                message: current,
+                fraction: value,
+                ..
+            } = this
+            {
+                if current.eq(&message) {
+                    *value = fraction;
+                    updated = true;
+                    cx.notify();
+                }
+            }
         });
+        if !updated {
+            self.show(
+                ToastState::Progress {
+                    message,
+                    fraction,
+                    fade_in: Instant::now(),
+                    fade_out: None,
+                },
+                cx,
+            );
+        }
     }
     pub fn success<C: VisualContext>(&mut self, message: impl ToString, cx: &mut C) {
-        self.state.update(cx, |this, cx| {
-            *this = ToastState::Success {
+        self.show(
+            ToastState::Success {
                 message: message.to_string().into(),
                 fade_in: Instant::now(),
                 fade_out: Some(Instant::now() + Duration::from_secs(3)),
-            };
-            cx.notify();
-        });
+                action: None,
+            },
+            cx,
+        );
+    }
+    // Success toast with a button, which stays a bit longer so there is time to click it
+    pub fn success_with_action<C: VisualContext>(
+        &mut self,
+        message: impl ToString,
+        label: impl ToString,
+        action: impl ToastActionFn,
+        cx: &mut C,
+    ) {
+        self.show(
+            ToastState::Success {
+                message: message.to_string().into(),
+                fade_in: Instant::now(),
+                fade_out: Some(Instant::now() + Duration::from_secs(6)),
+                action: Some((label.to_string().into(), Rc::new(action))),
+            },
+            cx,
+        );
     }
     pub fn error<C: VisualContext>(&mut self, message: impl ToString, cx: &mut C) {
+        self.show(
+            ToastState::Error {
+                message: message.to_string().into(),
+                fade_in: Instant::now(),
+                fade_out: Some(Instant::now() + Duration::from_secs(4)),
+                action: None,
+            },
+            cx,
+        );
+    }
+    // Replaces whatever is showing instead of queueing, for prompts that only make sense right now
+    pub fn prompt<C: VisualContext>(
+        &mut self,
+        message: impl ToString,
+        duration: Duration,
+        cx: &mut C,
+    ) {
+        let queue = self.queue.clone();
         self.state.update(cx, |this, cx| {
             *this = ToastState::Error {
                 message: message.to_string().into(),
                 fade_in: Instant::now(),
-                fade_out: Some(Instant::now() + Duration::from_secs(4)),
+                fade_out: Some(Instant::now() + duration),
+                action: None,
             };
             cx.notify();
+            Self::advance(this, queue, cx);
         });
     }
-    /*
-       TODO: This works in theory, but cx.hide() will hide the entire app so the toast won't show.
-       I experimented with instead removing the main window with cx.remove_window() and restoring it on hotkey press, but then we lose all state.
-       So right now I don't have a good solution. I am leaving this here for future reference investigation.
-    */
+    pub fn error_with_action<C: VisualContext>(
+        &mut self,
+        message: impl ToString,
+        label: impl ToString,
+        action: impl ToastActionFn,
+        cx: &mut C,
+    ) {
+        self.show(
+            ToastState::Error {
+                message: message.to_string().into(),
+                fade_in: Instant::now(),
+                fade_out: Some(Instant::now() + Duration::from_secs(6)),
+                action: Some((label.to_string().into(), Rc::new(action))),
+            },
+            cx,
+        );
+    }
+    // Closes the launcher and shows the message in a separate window, which dismisses itself
     pub fn floating(&mut self, message: impl ToString, icon: Option<Icon>, cx: &mut WindowContext) {
         let bounds = cx.display().map(|d| d.bounds()).unwrap_or(Bounds {
             origin: Point::new(Pixels::from(0.0), Pixels::from(0.0)),
@@ -272,7 +548,7 @@ impl Toast {
                 height: Pixels::from(1080.0),
             },
         });
-        Window::close(cx);
+        Window::dismiss(cx);
         let _ = cx.open_window(
             WindowStyle::Toast {
                 width: message.to_string().len() as u32 * 12,
@@ -350,6 +626,7 @@ impl StateItem {
         let (s, r) = crossbeam_channel::unbounded::<bool>();
         let (actions_weak, actions) = ActionsModel::init(s, cx);
         let query = TextInput::new(cx);
+        actions_weak.swallow_chords(&query, cx);
 
         let actions_clone = actions_weak.clone();
         cx.subscribe(&query.view, move |_, event, cx| match event {
@@ -360,22 +637,29 @@ impl StateItem {
             }
             TextEvent::KeyDown(ev) => {
                 let _ = actions_clone.inner.update(cx, |this, cx| {
-                    if let Some(action) = this.check(&ev.keystroke, cx) {
-                        if ev.is_held {
+                    match this.check(&ev.keystroke, cx) {
+                        Some(Check::Action(action)) => {
+                            if !ev.is_held {
+                                (action.action)(this, cx);
+                            }
                             return;
                         }
-                        (action.action)(this, cx);
-                        return;
-                    };
-                    if !ev.is_held
-                        && (Keystroke {
-                            modifiers: Modifiers::default(),
-                            key: "tab".to_string(),
-                            ime_key: None,
-                        })
-                        .eq(&ev.keystroke)
-                    {
-                        this.dropdown_cycle(cx);
+                        // Waiting for the rest of a multi-key shortcut
+                        Some(Check::Pending) => return,
+                        None => {}
+                    }
+                    if !ev.is_held && ev.keystroke.key.as_str() == "tab" {
+                        let modifiers = ev.keystroke.modifiers;
+                        if modifiers == Modifiers::default() {
+                            this.dropdown_cycle(cx);
+                        } else if modifiers
+                            == (Modifiers {
+                                shift: true,
+                                ..Modifiers::default()
+                            })
+                        {
+                            this.filter_cycle(cx);
+                        }
                     }
                 });
 
@@ -429,6 +713,32 @@ pub struct State {
     pub stack: Vec<StateItem>,
 }
 
+// The command that was open when the window was last closed, to reopen it on launch
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestoreSettings {
+    pub enabled: bool,
+    // Command id and query
+    last: Option<(String, String)>,
+}
+
+impl RestoreSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("restore").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("restore", self)?;
+        Ok(())
+    }
+    pub fn enable(enabled: bool) -> anyhow::Result<()> {
+        Self {
+            enabled,
+            last: None,
+        }
+        .set()
+    }
+}
+
 #[derive(Clone)]
 pub struct StateModel {
     pub inner: Model<State>,
@@ -442,9 +752,44 @@ impl StateModel {
         this.push(RootListBuilder {}, cx);
 
         cx.set_global(this.clone());
+        this.restore(cx);
 
         this
     }
+    // Only the command right above the root list is remembered, not the views it opened
+    pub fn save(&self, cx: &mut WindowContext) {
+        let mut settings = RestoreSettings::get();
+        if !settings.enabled {
+            return;
+        }
+        settings.last = self
+            .inner
+            .read(cx)
+            .stack
+            .get(1)
+            .map(|item| (item.id.to_string(), item.query.view.read(cx).text.clone()));
+        if let Err(err) = settings.set() {
+            log::error!("Failed to save the open command: {:?}", err);
+        }
+    }
+    fn restore(&self, cx: &mut WindowContext) {
+        let settings = RestoreSettings::get();
+        let Some((id, query)) = settings.last.filter(|_| settings.enabled) else {
+            return;
+        };
+        let Some(command) = cx
+            .try_global::<RootCommands>()
+            .and_then(|commands| commands.commands.get(&id).cloned())
+            .filter(|command| command.restore)
+        else {
+            return;
+        };
+        let actions = self.inner.read(cx).stack[0].actions.clone();
+        actions.update(cx, |actions, cx| (command.action)(actions, cx));
+        if self.inner.read(cx).stack.len() > 1 {
+            self.set_query(query, cx);
+        }
+    }
     pub fn update(f: impl FnOnce(&mut Self, &mut WindowContext), cx: &mut WindowContext) {
         if !cx.has_global::<Self>() {
             log::error!("StateModel not found");
@@ -478,6 +823,29 @@ impl StateModel {
         });
     }
 
+    // Pushes the view and fills its query, once it has been built
+    pub fn push_with_query(
+        &self,
+        view: impl StateViewBuilder,
+        query: impl ToString,
+        cx: &mut WindowContext,
+    ) {
+        self.push(view, cx);
+        self.set_query(query, cx);
+    }
+    // Sets the query of the view on top of the stack
+    pub fn set_query(&self, query: impl ToString, cx: &mut WindowContext) {
+        let query_input = self
+            .inner
+            .read(cx)
+            .stack
+            .last()
+            .map(|item| item.query.downgrade());
+        if let Some(query_input) = query_input {
+            query_input.set_text(query, cx);
+        }
+    }
+
     pub fn push_item(&self, item: StateItem, cx: &mut WindowContext) {
         self.inner.update(cx, |model, cx| {
             model.stack.push(item);
@@ -502,15 +870,28 @@ impl Global for StateModel {}
 
 // Actions
 
+// Max delay between the keys of a multi-key shortcut
+static CHORD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+enum Check {
+    Action(Action),
+    // The keystroke started or continued a multi-key shortcut
+    Pending,
+}
+
 #[derive(Clone, IntoElement, Deserialize)]
 pub struct Shortcut {
     inner: Keystroke,
+    // Keystrokes that have to follow the first one, e.g. "g" then "c"
+    #[serde(default)]
+    then: Vec<Keystroke>,
 }
 
 impl From<&Keystroke> for Shortcut {
     fn from(keystroke: &Keystroke) -> Self {
         Self {
             inner: keystroke.clone(),
+            then: vec![],
         }
     }
 }
@@ -523,8 +904,14 @@ impl Shortcut {
                 key: key.to_string(),
                 ime_key: None,
             },
+            then: vec![],
         }
     }
+    pub fn then(mut self, next: Shortcut) -> Self {
+        self.then.push(next.inner);
+        self.then.extend(next.then);
+        self
+    }
     pub fn cmd(mut self) -> Self {
         #[cfg(target_os = "macos")]
         {
@@ -558,6 +945,11 @@ impl Shortcut {
     pub fn get(&self) -> Keystroke {
         self.inner.clone()
     }
+    pub fn keys(&self) -> Vec<Keystroke> {
+        let mut keys = vec![self.inner.clone()];
+        keys.extend(self.then.iter().cloned());
+        keys
+    }
 }
 
 fn key_icon(el: Div, icon: Icon) -> Div {
@@ -590,79 +982,95 @@ fn key_string(el: Div, theme: &Theme, string: impl ToString) -> Div {
     )
 }
 
-impl RenderOnce for Shortcut {
-    fn render(self, cx: &mut WindowContext) -> impl IntoElement {
-        let theme = cx.global::<theme::Theme>();
-        let mut el = div().flex().items_center();
-        let shortcut = self.inner;
-        if shortcut.modifiers.control {
-            el = key_icon(el, Icon::ChevronUp);
+fn key_stroke(mut el: Div, theme: &Theme, shortcut: Keystroke) -> Div {
+    if shortcut.modifiers.control {
+        el = key_icon(el, Icon::ChevronUp);
+    }
+    if shortcut.modifiers.alt {
+        el = key_icon(el, Icon::Option);
+    }
+    if shortcut.modifiers.shift {
+        el = key_icon(el, Icon::ArrowBigUp);
+    }
+    if shortcut.modifiers.platform {
+        el = key_icon(el, Icon::Command);
+    }
+    match shortcut.key.as_str() {
+        "enter" => {
+            el = key_icon(el, Icon::CornerDownLeft);
         }
-        if shortcut.modifiers.alt {
-            el = key_icon(el, Icon::Option);
+        "backspace" => {
+            el = key_icon(el, Icon::Delete);
         }
-        if shortcut.modifiers.shift {
-            el = key_icon(el, Icon::ArrowBigUp);
+        "delete" => {
+            el = key_icon(el, Icon::Delete);
         }
-        if shortcut.modifiers.platform {
-            el = key_icon(el, Icon::Command);
+        "escape" => {
+            el = key_icon(el, Icon::ArrowUpRightFromSquare);
         }
-        match shortcut.key.as_str() {
-            "enter" => {
-                el = key_icon(el, Icon::CornerDownLeft);
-            }
-            "backspace" => {
-                el = key_icon(el, Icon::Delete);
-            }
-            "delete" => {
-                el = key_icon(el, Icon::Delete);
-            }
-            "escape" => {
-                el = key_icon(el, Icon::ArrowUpRightFromSquare);
-            }
-            "tab" => {
-                el = key_icon(el, Icon::ArrowRightToLine);
-            }
-            "space" => {
-                el = key_icon(el, Icon::Space);
-            }
-            "up" => {
-                el = key_icon(el, Icon::ArrowUp);
-            }
-            "down" => {
-                el = key_icon(el, Icon::ArrowDown);
-            }
-            "left" => {
-                el = key_icon(el, Icon::ArrowLeft);
-            }
-            "right" => {
-                el = key_icon(el, Icon::ArrowRight);
-            }
-            "comma" => {
-                el = key_string(el, theme, ",");
-            }
-            "dot" => {
-                el = key_string(el, theme, ".");
-            }
-            "questionmark" => {
-                el = key_string(el, theme, "?");
-            }
-            "exclamationmark" => {
-                el = key_string(el, theme, "!");
-            }
-            "slash" => {
-                el = key_string(el, theme, "/");
-            }
-            "backslash" => {
-                el = key_string(el, theme, "\\");
-            }
-            _ => {
-                el = key_string(
-                    el,
-                    theme,
-                    shortcut.ime_key.unwrap_or(shortcut.key).to_uppercase(),
+        "tab" => {
+            el = key_icon(el, Icon::ArrowRightToLine);
+        }
+        "space" => {
+            el = key_icon(el, Icon::Space);
+        }
+        "up" => {
+            el = key_icon(el, Icon::ArrowUp);
+        }
+        "down" => {
+            el = key_icon(el, Icon::ArrowDown);
+        }
+        "left" => {
+            el = key_icon(el, Icon::ArrowLeft);
+        }
+        "right" => {
+            el = key_icon(el, Icon::ArrowRight);
+        }
+        "comma" => {
+            el = key_string(el, theme, ",");
+        }
+        "dot" => {
+            el = key_string(el, theme, ".");
+        }
+        "questionmark" => {
+            el = key_string(el, theme, "?");
+        }
+        "exclamationmark" => {
+            el = key_string(el, theme, "!");
+        }
+        "slash" => {
+            el = key_string(el, theme, "/");
+        }
+        "backslash" => {
+            el = key_string(el, theme, "\\");
+        }
+        _ => {
+            el = key_string(
+                el,
+                theme,
+                shortcut.ime_key.unwrap_or(shortcut.key).to_uppercase(),
+            );
+        }
+    }
+    el
+}
+
+impl RenderOnce for Shortcut {
+    fn render(self, cx: &mut WindowContext) -> impl IntoElement {
+        let theme = cx.global::<theme::Theme>();
+        let mut el = div().flex().items_center();
+        for (i, keystroke) in self.keys().into_iter().enumerate() {
+            if i > 0 {
+                el = el.child(
+                    div()
+                        .ml_1()
+                        .mr_0p5()
+                        .text_xs()
+                        .text_color(theme.subtext0)
+                        .child("then"),
                 );
             }
+            el = key_stroke(el, theme, keystroke);
         }
         el
     }
@@ -671,25 +1079,36 @@ impl RenderOnce for Shortcut {
 pub trait ActionFn: Fn(&mut Actions, &mut WindowContext) + 'static {}
 impl<F> ActionFn for F where F: Fn(&mut Actions, &mut WindowContext) + 'static {}
 
+pub trait BulkActionFn: Fn(Vec<Item>, &mut Actions, &mut WindowContext) + 'static {}
+impl<F> BulkActionFn for F where F: Fn(Vec<Item>, &mut Actions, &mut WindowContext) + 'static {}
+
+pub trait BadgeFn: Fn(&WindowContext) -> Option<String> + 'static {}
+impl<F> BadgeFn for F where F: Fn(&WindowContext) -> Option<String> + 'static {}
+
 #[derive(Clone, IntoElement)]
 pub struct Action {
     pub label: SharedString,
     pub shortcut: Option<Shortcut>,
     pub image: Img,
     pub action: Rc<dyn ActionFn>,
+    pub bulk: Option<Rc<dyn BulkActionFn>>,
+    pub badge: Option<Rc<dyn BadgeFn>>,
     pub hide: bool,
 }
 
 impl RenderOnce for Action {
-    fn render(self, _cx: &mut WindowContext) -> impl IntoElement {
+    fn render(self, cx: &mut WindowContext) -> impl IntoElement {
+        let theme = cx.global::<Theme>();
         let shortcut = if let Some(shortcut) = self.shortcut {
             div().child(shortcut)
         } else {
             div()
         };
+        let badge = self.badge.and_then(|badge| badge(cx));
         div()
             .ml_auto()
             .child(div().child(self.label).mr_2())
+            .children(badge.map(|badge| div().child(badge).text_color(theme.subtext0).mr_2()))
             .flex()
             .items_center()
             .justify_between()
@@ -709,10 +1128,42 @@ impl Action {
             label: label.to_string().into(),
             shortcut,
             action: Rc::new(action),
+            bulk: None,
+            badge: None,
             image,
             hide,
         }
     }
+    // Only runs when activated a second time shortly after, for destructive actions
+    pub fn confirm(mut self) -> Self {
+        let action = self.action.clone();
+        let label = self.label.clone();
+        let armed = Rc::new(Cell::new(None::<Instant>));
+        let window = Duration::from_secs(3);
+        self.action = Rc::new(move |actions, cx| {
+            if armed.get().is_some_and(|at| at.elapsed() < window) {
+                armed.set(None);
+                action(actions, cx);
+            } else {
+                armed.set(Some(Instant::now()));
+                // A queued prompt could still be waiting when the confirmation window is over
+                actions
+                    .toast
+                    .prompt(format!("Press again to confirm: {}", label), window, cx);
+            }
+        });
+        self
+    }
+    // Run on all checked items when the list is in multi-select mode
+    pub fn bulk(mut self, action: impl BulkActionFn + 'static) -> Self {
+        self.bulk = Some(Rc::new(action));
+        self
+    }
+    // Extra text next to the label, evaluated on every render
+    pub fn badge(mut self, badge: impl BadgeFn + 'static) -> Self {
+        self.badge = Some(Rc::new(badge));
+        self
+    }
     pub fn new_rc(
         image: Img,
         label: impl ToString,
@@ -724,18 +1175,90 @@ impl Action {
             label: label.to_string().into(),
             shortcut,
             action,
+            bulk: None,
+            badge: None,
             image,
             hide,
         }
     }
 }
 
+// Popup row for an action, built on render so badges stay current
+struct ActionItem(Action);
+
+impl ActionItem {
+    fn list_item(&self, cx: &WindowContext) -> ListItem {
+        let mut accessories = vec![];
+        if let Some(badge) = self.0.badge.as_ref().and_then(|badge| badge(cx)) {
+            accessories.push(Accessory::new(badge, None));
+        }
+        if let Some(shortcut) = self.0.shortcut.clone() {
+            accessories.push(Accessory::shortcut(shortcut));
+        }
+        ListItem::new(
+            Some(self.0.image.clone()),
+            self.0.label.clone(),
+            None,
+            accessories,
+        )
+    }
+}
+
+impl ItemComponent for ActionItem {
+    fn render(&self, selected: bool, cx: &WindowContext) -> AnyElement {
+        self.list_item(cx).render(selected, cx)
+    }
+    fn render_matches(
+        &self,
+        selected: bool,
+        keyword: &str,
+        indices: &[usize],
+        cx: &WindowContext,
+    ) -> AnyElement {
+        self.list_item(cx)
+            .render_matches(selected, keyword, indices, cx)
+    }
+}
+
+// Pill used for the query dropdown and select inputs
+pub fn dropdown_style(theme: &Theme) -> Div {
+    div()
+        .px_2()
+        .py_0p5()
+        .rounded_lg()
+        .bg(theme.mantle)
+        .flex()
+        .items_center()
+        .justify_between()
+        .text_xs()
+        .font_weight(FontWeight::SEMIBOLD)
+        .text_color(theme.subtext0)
+        .border_1()
+        .border_color(theme.crust)
+}
+
 #[derive(Clone)]
 pub struct Dropdown {
     value: String,
     items: Vec<(String, String)>,
 }
 
+impl Dropdown {
+    fn cycle(&mut self, cx: &mut ViewContext<Self>) {
+        if self.items.is_empty() {
+            return;
+        }
+        let index = self
+            .items
+            .iter()
+            .position(|item| item.0.eq(&self.value))
+            .unwrap_or(0);
+        let next = (index + 1) % self.items.len();
+        self.value = self.items[next].0.clone();
+        cx.notify();
+    }
+}
+
 impl Render for Dropdown {
     fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
         let theme = cx.global::<theme::Theme>();
@@ -750,21 +1273,7 @@ impl Render for Dropdown {
             .unwrap()
             .1
             .clone();
-        div()
-            .px_2()
-            .py_0p5()
-            .rounded_lg()
-            .bg(theme.mantle)
-            .ml_auto()
-            .flex()
-            .items_center()
-            .justify_between()
-            .text_xs()
-            .font_weight(FontWeight::SEMIBOLD)
-            .text_color(theme.subtext0)
-            .border_1()
-            .border_color(theme.crust)
-            .child(div().child(label))
+        dropdown_style(theme).ml_auto().child(div().child(label))
     }
 }
 
@@ -780,6 +1289,11 @@ pub struct Actions {
     update_sender: crossbeam_channel::Sender<bool>,
     pub toast: Toast,
     pub dropdown: View<Dropdown>,
+    // Secondary dropdown for filtering, cycled with shift-tab
+    pub filter: View<Dropdown>,
+    // Keystrokes of a partially entered multi-key shortcut
+    chord: Vec<Keystroke>,
+    chord_at: Option<Instant>,
 }
 
 impl Actions {
@@ -797,7 +1311,13 @@ impl Actions {
                 value: "".to_string(),
                 items: vec![],
             }),
+            filter: cx.new_view(|_| Dropdown {
+                value: "".to_string(),
+                items: vec![],
+            }),
             update_sender,
+            chord: vec![],
+            chord_at: None,
         }
     }
     pub fn default(cx: &mut WindowContext) -> Self {
@@ -821,6 +1341,14 @@ impl Actions {
                 true,
             ))
         }
+        let pinned = Window::is_pinned(cx);
+        combined.push(Action::new(
+            Img::default().icon(if pinned { Icon::PinOff } else { Icon::Pin }),
+            if pinned { "Unpin Window" } else { "Pin Window" },
+            Some(Shortcut::new("p").cmd().shift()),
+            |_, cx| Window::toggle_pin(cx),
+            true,
+        ));
         combined
     }
     fn update_list(&self, cx: &mut WindowContext) {
@@ -882,16 +1410,68 @@ impl Actions {
                     .text_sm(),
             )
     }
-    fn check(&self, keystroke: &Keystroke, cx: &WindowContext) -> Option<Action> {
+    // What the keystroke triggers after the keys pressed so far, and the keys pressed afterwards
+    fn resolve(
+        &self,
+        keystroke: &Keystroke,
+        cx: &WindowContext,
+    ) -> (Option<Check>, Vec<Keystroke>) {
+        let mut pressed = if self.chord_at.is_some_and(|at| at.elapsed() > CHORD_TIMEOUT) {
+            vec![]
+        } else {
+            self.chord.clone()
+        };
+        pressed.push(keystroke.clone());
         let actions = self.combined(cx);
-        for action in actions {
-            if let Some(shortcut) = &action.shortcut {
-                if shortcut.inner.eq(keystroke) {
-                    return Some(action.clone());
+        // If the keystroke doesn't continue the pending sequence, start over with it alone
+        for pressed in [pressed, vec![keystroke.clone()]] {
+            let mut partial = false;
+            for action in &actions {
+                if let Some(shortcut) = &action.shortcut {
+                    let keys = shortcut.keys();
+                    if keys.eq(&pressed) {
+                        return (Some(Check::Action(action.clone())), vec![]);
+                    }
+                    partial = partial || keys.starts_with(&pressed);
                 }
             }
+            if partial {
+                return (Some(Check::Pending), pressed);
+            }
+        }
+        (None, vec![])
+    }
+    fn check(&mut self, keystroke: &Keystroke, cx: &WindowContext) -> Option<Check> {
+        let (check, chord) = self.resolve(keystroke, cx);
+        self.chord_at = (!chord.is_empty()).then(Instant::now);
+        self.chord = chord;
+        check
+    }
+    // Whether the keystroke is part of a multi-key shortcut, so it shouldn't be typed
+    fn is_chord(&self, keystroke: &Keystroke, cx: &WindowContext) -> bool {
+        match self.resolve(keystroke, cx).0 {
+            Some(Check::Pending) => true,
+            Some(Check::Action(action)) => action
+                .shortcut
+                .is_some_and(|shortcut| shortcut.keys().len() > 1),
+            None => false,
         }
-        None
+    }
+    // Label of the in-app action the keystroke would trigger
+    pub fn shortcut_label(
+        &self,
+        keystroke: &Keystroke,
+        cx: &WindowContext,
+    ) -> Option<SharedString> {
+        self.combined(cx)
+            .into_iter()
+            .find(|action| {
+                action
+                    .shortcut
+                    .as_ref()
+                    .is_some_and(|shortcut| shortcut.keys().eq(&[keystroke.clone()]))
+            })
+            .map(|action| action.label)
     }
     pub fn update(&self) {
         let _ = self.update_sender.send(true);
@@ -912,19 +1492,11 @@ impl Actions {
         self.update()
     }
     pub fn dropdown_cycle(&mut self, cx: &mut WindowContext) {
-        self.dropdown.update(cx, |this, cx| {
-            if this.items.is_empty() {
-                return;
-            }
-            let index = this
-                .items
-                .iter()
-                .position(|item| item.0.eq(&this.value))
-                .unwrap_or(0);
-            let next = (index + 1) % this.items.len();
-            this.value = this.items[next].0.clone();
-            cx.notify();
-        });
+        self.dropdown.update(cx, |this, cx| this.cycle(cx));
+        self.update()
+    }
+    pub fn filter_cycle(&mut self, cx: &mut WindowContext) {
+        self.filter.update(cx, |this, cx| this.cycle(cx));
         self.update()
     }
     pub fn has_focus(&self, cx: &WindowContext) -> bool {
@@ -949,7 +1521,11 @@ impl Render for Actions {
                 .flex()
                 .items_center()
                 .font_weight(FontWeight::SEMIBOLD)
-                .child(div().child(action.clone()).text_color(theme.text))
+                .child(
+                    div()
+                        .child(action.clone())
+                        .text_color(theme.accent.unwrap_or(theme.text)),
+                )
                 .child(div().h_2_3().w(Pixels(2.0)).bg(theme.surface0).mx_2())
                 .child(open)
                 .child(self.popup(cx))
@@ -965,6 +1541,17 @@ pub struct ActionsModel {
 }
 
 impl ActionsModel {
+    // Keeps the keys of multi-key shortcuts out of the query
+    fn swallow_chords(&self, query: &TextInput, cx: &mut WindowContext) {
+        let actions = self.inner.clone();
+        query.view.update(cx, |view, _| {
+            view.swallow = Some(Box::new(move |keystroke, cx| {
+                actions
+                    .upgrade()
+                    .is_some_and(|actions| actions.read(cx).is_chord(keystroke, cx))
+            }));
+        });
+    }
     pub fn init(
         update_sender: crossbeam_channel::Sender<bool>,
         cx: &mut WindowContext,
@@ -982,6 +1569,7 @@ impl ActionsModel {
         inner.update(cx, |this, cx| {
             let (_s, r) = crossbeam_channel::unbounded::<bool>();
             let query = TextInput::new(cx);
+            model.swallow_chords(&query, cx);
             let mut context = StateViewContext {
                 query: query.downgrade(),
                 actions: model.clone(),
@@ -1002,24 +1590,11 @@ impl ActionsModel {
                                     return None;
                                 }
                                 let action = item.clone();
-                                let accessories = if let Some(shortcut) = item.shortcut {
-                                    vec![Accessory::shortcut(shortcut)]
-                                } else {
-                                    vec![]
-                                };
                                 Some(
-                                    ItemBuilder::new(
-                                        item.label.clone(),
-                                        ListItem::new(
-                                            Some(action.image.clone()),
-                                            item.label.clone(),
-                                            None,
-                                            accessories,
-                                        ),
-                                    )
-                                    .keywords(vec![item.label.clone()])
-                                    .actions(vec![action])
-                                    .build(),
+                                    ItemBuilder::new(item.label.clone(), ActionItem(item.clone()))
+                                        .keywords(vec![item.label.clone()])
+                                        .actions(vec![action])
+                                        .build(),
                                 )
                             })
                             .collect(),
@@ -1048,12 +1623,15 @@ impl ActionsModel {
                             });
                             return;
                         }
-                        if let Some(action) = this.check(&ev.keystroke, cx) {
-                            if ev.is_held {
+                        match this.check(&ev.keystroke, cx) {
+                            Some(Check::Action(action)) => {
+                                if !ev.is_held {
+                                    (action.action)(this, cx);
+                                }
                                 return;
                             }
-                            (action.action)(this, cx);
-                            return;
+                            Some(Check::Pending) => return,
+                            None => {}
                         }
                         if ev.keystroke.key.as_str() == "escape" {
                             this.show = false;
@@ -1114,6 +1692,31 @@ impl ActionsModel {
             .map(|this| this.read(cx).dropdown.read(cx).value.clone())
             .unwrap_or_default()
     }
+    pub fn get_filter_value(&self, cx: &WindowContext) -> String {
+        self.inner
+            .upgrade()
+            .map(|this| this.read(cx).filter.read(cx).value.clone())
+            .unwrap_or_default()
+    }
+    pub fn set_filter(
+        &mut self,
+        value: impl ToString,
+        items: Vec<(impl ToString, impl ToString)>,
+        cx: &mut WindowContext,
+    ) {
+        let _ = self.inner.update(cx, |model, cx| {
+            model.filter.update(cx, |this, cx| {
+                this.items = items
+                    .into_iter()
+                    .map(|(value, label)| (value.to_string(), label.to_string()))
+                    .collect();
+                this.value = value.to_string();
+                cx.notify();
+            });
+            model.update();
+            cx.notify();
+        });
+    }
     pub fn set_dropdown(
         &mut self,
         value: impl ToString,
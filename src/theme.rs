@@ -13,7 +13,7 @@ use gpui::*;
 use log::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{db::db, paths::paths};
+use crate::{db::db, paths::paths, platform::system_appearance};
 
 fn color_to_hsla(color: catppuccin::Colour) -> Hsla {
     Rgba {
@@ -67,6 +67,7 @@ impl From<catppuccin::Flavour> for Theme {
             base: color_to_hsla(colors.base),
             mantle: color_to_hsla(colors.mantle),
             crust: color_to_hsla(colors.crust),
+            accent: None,
         }
     }
 }
@@ -102,6 +103,9 @@ pub struct Theme {
     pub base: Hsla,
     pub mantle: Hsla,
     pub crust: Hsla,
+    // Tints selection, focus borders and the primary action without touching the palette
+    #[serde(default)]
+    pub accent: Option<Hsla>,
 }
 
 fn load_fonts(cx: &mut AppContext) -> gpui::Result<()> {
@@ -158,6 +162,14 @@ impl WindowBackgroundAppearanceContent {
 pub struct ThemeSettings {
     pub light: String,
     pub dark: String,
+    // Manually selected theme, which disables following the system appearance
+    #[serde(default)]
+    pub fixed: Option<String>,
+    // Shows only the first frame of animated images
+    #[serde(default)]
+    pub freeze_animations: bool,
+    #[serde(default)]
+    pub reduce_motion: bool,
 }
 
 impl Default for ThemeSettings {
@@ -165,34 +177,86 @@ impl Default for ThemeSettings {
         Self {
             light: "Catppuccin Latte".into(),
             dark: "Catppuccin Mocha".into(),
+            fixed: None,
+            freeze_animations: false,
+            reduce_motion: false,
         }
     }
 }
 
+impl ThemeSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("theme").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("theme", self)?;
+        Ok(())
+    }
+}
+
 impl Theme {
     pub fn init(cx: &mut AppContext) {
         load_fonts(cx).expect("Failed to load fonts");
-        let appearance = cx.window_appearance();
+        let appearance = system_appearance().unwrap_or(cx.window_appearance());
         let theme = Theme::mode(appearance);
 
         cx.set_global(theme);
+        cx.set_global(ReduceMotion(ThemeSettings::get().reduce_motion));
     }
     pub fn mode(mode: WindowAppearance) -> Theme {
-        let settings = db().get::<ThemeSettings>("theme").unwrap_or_default();
-        let list = Theme::list();
-        let name = match mode {
+        match ThemeSettings::get().fixed {
+            Some(fixed) => Theme::named(&fixed),
+            None => Theme::auto(mode),
+        }
+    }
+    // The configured light or dark theme, ignoring a manually selected one
+    pub fn auto(mode: WindowAppearance) -> Theme {
+        let settings = ThemeSettings::get();
+        Theme::named(&match mode {
             WindowAppearance::Dark | WindowAppearance::VibrantDark => settings.dark,
             WindowAppearance::Light | WindowAppearance::VibrantLight => settings.light,
-        };
+        })
+    }
+    fn named(name: &str) -> Theme {
+        let list = Theme::list();
         list.clone()
             .into_iter()
-            .find(|t| t.name == name)
+            .find(|t| t.name.as_ref() == name)
             .unwrap_or_else(|| {
                 error!("Theme not found: {}", name);
                 list.first().unwrap().clone()
             })
             .clone()
     }
+    // Swaps in the given theme and re-renders
+    pub fn activate(theme: Theme, cx: &mut WindowContext) {
+        cx.set_background_appearance(WindowBackgroundAppearance::from(
+            theme.window_background.clone().unwrap_or_default(),
+        ));
+        cx.set_global(theme);
+        cx.refresh();
+    }
+    // Applies the theme matching the current settings and system appearance
+    pub fn sync(cx: &mut WindowContext) {
+        Self::activate(Self::mode(Self::appearance(cx)), cx);
+    }
+    // Falls back to lavender, which the UI used before accents were configurable
+    pub fn accent(&self) -> Hsla {
+        self.accent.unwrap_or(self.lavender)
+    }
+    // Background of the selected list item, a faint accent tint or the stock mantle
+    pub fn selection(&self) -> Hsla {
+        match self.accent {
+            Some(mut accent) => {
+                accent.fade_out(0.85);
+                accent
+            }
+            None => self.mantle,
+        }
+    }
+    pub fn appearance(cx: &WindowContext) -> WindowAppearance {
+        system_appearance().unwrap_or(cx.window_appearance())
+    }
 
     pub fn list() -> Vec<Theme> {
         let config = paths().config.clone().join("themes");
@@ -233,3 +297,22 @@ impl Theme {
 }
 
 impl Global for Theme {}
+
+// Replaces animations with static renders, for accessibility or to save battery
+pub struct ReduceMotion(pub bool);
+
+impl Global for ReduceMotion {}
+
+impl ReduceMotion {
+    pub fn enabled(cx: &AppContext) -> bool {
+        cx.try_global::<Self>().is_some_and(|motion| motion.0)
+    }
+    pub fn set(enabled: bool, cx: &mut WindowContext) -> anyhow::Result<()> {
+        let mut settings = ThemeSettings::get();
+        settings.reduce_motion = enabled;
+        settings.set()?;
+        cx.set_global(Self(enabled));
+        cx.refresh();
+        Ok(())
+    }
+}
@@ -9,11 +9,18 @@
  *
  */
 
-use std::time::Duration;
+use std::{rc::Rc, time::Duration};
 
 use gpui::*;
+use serde::{Deserialize, Serialize};
 
-use crate::{components::shared::NoView, state::StateModel, theme::Theme};
+use crate::{
+    components::shared::NoView,
+    db::db,
+    platform::{cursor_position, hide_launcher, move_launcher},
+    state::StateModel,
+    theme::Theme,
+};
 
 pub static WIDTH: u32 = 800;
 pub static HEIGHT: u32 = 450;
@@ -55,32 +62,128 @@ impl WindowStyle {
             Size { width, height },
         )));
         options.titlebar = None;
-        options.is_movable = false;
+        options.is_movable = matches!(self, WindowStyle::Main);
         options.kind = WindowKind::PopUp;
         options
     }
 }
 
+// Where the main window was when it was last closed
+#[derive(Clone, Serialize, Deserialize)]
+pub struct WindowPlacement {
+    display: u32,
+    x: f32,
+    y: f32,
+}
+
+impl WindowPlacement {
+    fn get() -> Option<Self> {
+        db().get::<Self>("window")
+    }
+    fn save(cx: &mut WindowContext) {
+        let Some(display) = cx.display() else {
+            return;
+        };
+        let origin = cx.bounds().origin;
+        let placement = Self {
+            display: display.id().into(),
+            x: origin.x.into(),
+            y: origin.y.into(),
+        };
+        if let Err(err) = db().set::<Self>("window", &placement) {
+            log::error!("Failed to save window placement: {}", err);
+        }
+    }
+    // Moves the window to its last position, unless the display it was on is gone
+    pub fn restore(options: &mut WindowOptions, cx: &AppContext) {
+        let Some(placement) = Self::get() else {
+            return;
+        };
+        let Some(WindowBounds::Windowed(bounds)) = options.window_bounds else {
+            return;
+        };
+        // The window is supposed to open on another display
+        if options
+            .display_id
+            .is_some_and(|id| u32::from(id).ne(&placement.display))
+        {
+            return;
+        }
+        let origin = point(px(placement.x), px(placement.y));
+        let Some(display) = cx.displays().into_iter().find(|display| {
+            u32::from(display.id()).eq(&placement.display) && display.bounds().contains(&origin)
+        }) else {
+            return;
+        };
+        options.window_bounds = Some(WindowBounds::Windowed(Bounds::new(origin, bounds.size)));
+        options.display_id = Some(display.id());
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSettings {
+    // Display the window is pinned to, otherwise it follows the cursor
+    pub display: Option<u32>,
+}
+
+impl WindowSettings {
+    pub fn get() -> Self {
+        db().get::<Self>("window.settings").unwrap_or_default()
+    }
+    pub fn set(&self) -> anyhow::Result<()> {
+        db().set::<Self>("window.settings", self)?;
+        Ok(())
+    }
+    // The pinned display or the one containing the cursor
+    pub fn display(cx: &AppContext) -> Option<Rc<dyn PlatformDisplay>> {
+        let displays = cx.displays();
+        match Self::get().display {
+            Some(id) => displays
+                .into_iter()
+                .find(|display| u32::from(display.id()).eq(&id)),
+            None => {
+                let cursor = cursor_position()?;
+                displays
+                    .into_iter()
+                    .find(|display| display.bounds().contains(&cursor))
+            }
+        }
+    }
+}
+
 pub struct Window {
     inner: View<NoView>,
     hidden: bool,
+    // Keeps the window open when it loses focus, until it's closed explicitly
+    pinned: bool,
 }
 
 impl Window {
     pub fn init(cx: &mut WindowContext) {
         let view = cx.new_view(|cx| {
             cx.observe_window_activation(|_, cx| {
-                if cx.is_window_active() {
+                if cx.is_window_active() || Window::is_pinned(cx) {
                     return;
                 };
                 Window::close(cx);
             })
             .detach();
-            cx.observe_window_appearance(|_, cx| {
-                cx.update_global::<Theme, _>(|theme: &mut Theme, cx| {
-                    *theme = Theme::mode(cx.window_appearance());
-                    cx.refresh();
-                });
+            cx.observe_window_appearance(|_, cx| Theme::sync(cx))
+                .detach();
+            // There is no appearance change notification on Linux, so poll for it
+            #[cfg(target_os = "linux")]
+            cx.spawn(|_, mut cx| async move {
+                loop {
+                    let changed = cx
+                        .background_executor()
+                        .spawn(async { crate::platform::refresh_system_appearance() })
+                        .await;
+                    if changed && cx.update(Theme::sync).is_err() {
+                        break;
+                    }
+                    cx.background_executor().timer(Duration::from_secs(5)).await;
+                }
             })
             .detach();
             NoView {}
@@ -88,14 +191,42 @@ impl Window {
         cx.set_global::<Self>(Self {
             inner: view,
             hidden: false,
+            pinned: false,
+        });
+    }
+    pub fn is_pinned(cx: &AppContext) -> bool {
+        cx.try_global::<Self>().is_some_and(|w| w.pinned)
+    }
+    pub fn toggle_pin(cx: &mut WindowContext) {
+        cx.update_global::<Self, _>(|this, _| {
+            this.pinned = !this.pinned;
         });
+        cx.refresh();
     }
     pub fn is_open(cx: &AsyncAppContext) -> bool {
         cx.read_global::<Self, _>(|w, _| !w.hidden).unwrap_or(false)
     }
+    // Moves the hidden window over to the target display, unless it's already there
+    fn position(cx: &mut WindowContext) {
+        let Some(display) = WindowSettings::display(cx) else {
+            return;
+        };
+        if cx
+            .display()
+            .is_some_and(|current| current.id().eq(&display.id()))
+        {
+            return;
+        }
+        if let Some(WindowBounds::Windowed(bounds)) =
+            WindowStyle::Main.options(display.bounds()).window_bounds
+        {
+            move_launcher(bounds.origin);
+        }
+    }
     pub fn open(cx: &mut WindowContext) {
         cx.update_global::<Self, _>(|this, cx| {
             if this.hidden {
+                Self::position(cx);
                 cx.activate_window();
                 this.hidden = false;
             }
@@ -104,6 +235,7 @@ impl Window {
     pub fn toggle(cx: &mut WindowContext) {
         cx.update_global::<Self, _>(|this, cx| {
             if this.hidden {
+                Self::position(cx);
                 cx.activate_window();
                 this.hidden = false;
             } else {
@@ -113,9 +245,21 @@ impl Window {
         });
     }
     pub fn close(cx: &mut WindowContext) {
+        Self::hide_with(cx, |cx| cx.hide());
+    }
+    // Only hides the launcher window, so other windows like floating toasts stay visible
+    pub fn dismiss(cx: &mut WindowContext) {
+        Self::hide_with(cx, hide_launcher);
+    }
+    fn hide_with(cx: &mut WindowContext, hide: impl FnOnce(&mut WindowContext)) {
+        StateModel::update(|this, cx| this.save(cx), cx);
         cx.update_global::<Self, _>(|this, cx| {
+            if !this.hidden {
+                WindowPlacement::save(cx);
+            }
             this.hidden = true;
-            cx.hide();
+            this.pinned = false;
+            hide(cx);
         });
         // After 90 seconds, reset the state
         cx.spawn(|mut cx| async move {
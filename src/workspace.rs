@@ -15,6 +15,7 @@ use crate::components::shared::{Icon, Img};
 use crate::loader::ActiveLoaders;
 use crate::state::{StateItem, StateModel};
 use crate::theme::Theme;
+use crate::window::Window;
 
 pub struct Workspace {
     state: StateModel,
@@ -46,6 +47,10 @@ impl Render for Workspace {
                 .child(Img::default().icon(Icon::ArrowLeft));
         }
         let a = item.actions.read(cx).clone();
+        let mut pin = div();
+        if Window::is_pinned(cx) {
+            pin = div().mr_2().child(Img::default().icon(Icon::Pin));
+        }
 
         div()
             .rounded_xl()
@@ -67,7 +72,16 @@ impl Render for Workspace {
                     .items_center()
                     .child(back)
                     .child(item.query.clone())
-                    .child(a.dropdown.clone())
+                    .child(
+                        div()
+                            .ml_auto()
+                            .flex()
+                            .items_center()
+                            .gap_1()
+                            .child(a.filter.clone())
+                            .child(a.dropdown.clone()),
+                    )
+                    .child(pin)
                     .p_2()
                     .w_full(),
             )
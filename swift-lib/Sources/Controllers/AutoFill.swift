@@ -48,6 +48,15 @@ final class AutoFill {
         simulatePasteEvent(formatting: formatting)
     }
 
+    func pasteHtml(html: String, text: String) {
+        let pasteboard = NSPasteboard.general
+        pasteboard.declareTypes([.html, .string], owner: nil)
+        pasteboard.setString(html, forType: .html)
+        pasteboard.setString(text, forType: .string)
+        
+        simulatePasteEvent()
+    }
+
     func copyFile(for path: String) {
         let pasteboard = NSPasteboard.general
         pasteboard.declareTypes([.fileURL], owner: nil)
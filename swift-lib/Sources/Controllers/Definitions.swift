@@ -0,0 +1,24 @@
+/*
+ This source file is part of the Loungy open source project
+ 
+ Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ Licensed under MIT License
+ 
+ See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ */
+
+import CoreServices
+import Foundation
+
+final class Definitions {
+
+    // Plain text entry of the system dictionary, as shown by the Dictionary app
+    func define(_ word: String) -> String? {
+        let range = CFRangeMake(0, (word as NSString).length)
+        guard let definition = DCSCopyTextDefinition(nil, word as CFString, range) else {
+            return nil
+        }
+
+        return definition.takeRetainedValue() as String
+    }
+}
@@ -0,0 +1,80 @@
+/*
+ This source file is part of the Loungy open source project
+ 
+ Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ Licensed under MIT License
+ 
+ See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ */
+
+import Foundation
+import LocalAuthentication
+import Security
+
+final class Keychain {
+
+    // Items live in the data protection keychain, the only one that enforces access control.
+    // Older items stored without it in the file keychain are no longer read, only deleted.
+    private func baseQuery(service: String, account: String, legacy: Bool = false) -> [String: Any] {
+        var query: [String: Any] = [
+            kSecClass as String: kSecClassGenericPassword,
+            kSecAttrService as String: service,
+            kSecAttrAccount as String: account,
+        ]
+        if !legacy {
+            query[kSecUseDataProtectionKeychain as String] = true
+        }
+        return query
+    }
+
+    func set(service: String, account: String, secret: String) -> Bool {
+        _ = delete(service: service, account: account)
+
+        // The Keychain itself asks for biometrics on every read, re-enrolling fingers invalidates the item
+        guard let access = SecAccessControlCreateWithFlags(
+            nil,
+            kSecAttrAccessibleWhenUnlockedThisDeviceOnly,
+            .biometryCurrentSet,
+            nil
+        ) else {
+            return false
+        }
+
+        var query = baseQuery(service: service, account: account)
+        query[kSecAttrAccessControl as String] = access
+        query[kSecValueData as String] = Data(secret.utf8)
+
+        return SecItemAdd(query as CFDictionary, nil) == errSecSuccess
+    }
+
+    // Blocks while the system shows the biometric prompt
+    func get(service: String, account: String, reason: String) -> String? {
+        let context = LAContext()
+        context.localizedReason = reason
+
+        var query = baseQuery(service: service, account: account)
+        query[kSecReturnData as String] = true
+        query[kSecMatchLimit as String] = kSecMatchLimitOne
+        query[kSecUseAuthenticationContext as String] = context
+        query[kSecUseOperationPrompt as String] = reason
+
+        var result: AnyObject?
+        guard SecItemCopyMatching(query as CFDictionary, &result) == errSecSuccess,
+              let data = result as? Data
+        else {
+            return nil
+        }
+
+        return String(data: data, encoding: .utf8)
+    }
+
+    func delete(service: String, account: String) -> Bool {
+        var deleted = true
+        for legacy in [false, true] {
+            let query = baseQuery(service: service, account: account, legacy: legacy)
+            let status = SecItemDelete(query as CFDictionary)
+            deleted = deleted && (status == errSecSuccess || status == errSecItemNotFound)
+        }
+        return deleted
+    }
+}
@@ -159,7 +159,7 @@ private extension MenuBar {
         )
     }
 
-    func shortcut(_: String?, _ modifiers: Int, _ virtualKey: Int) -> Shortcut? {
+    func shortcut(_ command: String?, _ modifiers: Int, _ virtualKey: Int) -> Shortcut? {
         var shortcut: String?
 
         if virtualKey > 0 {
@@ -168,6 +168,14 @@ private extension MenuBar {
             }
         }
 
+        // Most items only set the command character, e.g. "B" for Bold
+        if shortcut == nil,
+           let command,
+           command.isNotEmpty,
+           command.unicodeScalars.allSatisfy({ $0.value > 0x20 && $0.value < 0xF700 }) {
+            shortcut = command.lowercased()
+        }
+
         let mods = decode(modifiers: modifiers)
 
         guard let shortcut else {
@@ -0,0 +1,66 @@
+/*
+ This source file is part of the Loungy open source project
+ 
+ Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ Licensed under MIT License
+ 
+ See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ */
+
+import Foundation
+import UserNotifications
+
+final class Notifications: NSObject, UNUserNotificationCenterDelegate {
+
+    private var clicked: [String] = []
+    private let lock = NSLock()
+
+    override init() {
+        super.init()
+        UNUserNotificationCenter.current().delegate = self
+    }
+
+    func send(id: String, title: String, body: String) {
+        let center = UNUserNotificationCenter.current()
+        center.requestAuthorization(options: [.alert, .sound]) { granted, _ in
+            guard granted else {
+                return
+            }
+
+            let content = UNMutableNotificationContent()
+            content.title = title
+            content.body = body
+            content.sound = .default
+            content.userInfo = ["id": id]
+
+            center.add(
+                UNNotificationRequest(
+                    identifier: UUID().uuidString,
+                    content: content,
+                    trigger: nil
+                )
+            )
+        }
+    }
+
+    // Clicks are queued until Loungy polls for them, since Swift can't call back into Rust
+    func nextClicked() -> String? {
+        lock.lock()
+        defer { lock.unlock() }
+
+        return clicked.isEmpty ? nil : clicked.removeFirst()
+    }
+
+    func userNotificationCenter(
+        _ center: UNUserNotificationCenter,
+        didReceive response: UNNotificationResponse,
+        withCompletionHandler completionHandler: @escaping () -> Void
+    ) {
+        if let id = response.notification.request.content.userInfo["id"] as? String {
+            lock.lock()
+            clicked.append(id)
+            lock.unlock()
+        }
+        completionHandler()
+    }
+}
@@ -0,0 +1,125 @@
+/*
+ This source file is part of the Loungy open source project
+
+ Copyright (c) 2024 Loungy, Matthias Grandl and the Loungy project contributors
+ Licensed under MIT License
+
+ See https://github.com/MatthiasGrandl/Loungy/blob/main/LICENSE.md for license information
+ */
+
+import ApplicationServices
+import Cocoa
+import Foundation
+
+final class WindowManager {
+    // Keep in sync with `WindowError` in the platform layer
+    enum Result: Int {
+        case success = 0
+        case notTrusted = 1
+        case noWindow = 2
+    }
+
+    func move(to position: String) -> Result {
+        guard AXIsProcessTrusted() else {
+            return .notTrusted
+        }
+        guard let app = NSWorkspace.shared.frontmostApplication else {
+            return .noWindow
+        }
+
+        let axApp = AXUIElementCreateApplication(app.processIdentifier)
+        guard let value = axApp.attribute(for: kAXFocusedWindowAttribute) else {
+            return .noWindow
+        }
+        let window = value as! AXUIElement
+
+        guard let current = frame(of: window) else {
+            return .noWindow
+        }
+        let screen = NSScreen.screens.first { $0.frame.intersects(current) } ?? NSScreen.main
+        guard let screen else {
+            return .noWindow
+        }
+        let visible = screen.visibleFrame
+
+        var target = visible
+        switch position {
+        case "left_half":
+            target.size.width = visible.width / 2
+        case "right_half":
+            target.size.width = visible.width / 2
+            target.origin.x = visible.midX
+        case "center":
+            target.size = CGSize(
+                width: min(current.width, visible.width),
+                height: min(current.height, visible.height)
+            )
+            target.origin.x = visible.midX - target.width / 2
+            target.origin.y = visible.midY - target.height / 2
+        default:
+            break
+        }
+
+        setFrame(of: window, to: target)
+        return .success
+    }
+
+    // Mouse location, anchored at the top of the primary screen like GPUI
+    func cursor() -> CGPoint {
+        let location = NSEvent.mouseLocation
+        let height = NSScreen.screens.first?.frame.height ?? 0
+        return CGPoint(x: location.x, y: height - location.y)
+    }
+
+    // Moves Loungy's own window, the top left corner is in GPUI coordinates
+    func moveLauncher(to point: CGPoint) {
+        guard let window = NSApp.windows.first(where: { $0.title == "Loungy" }) else {
+            return
+        }
+        let height = NSScreen.screens.first?.frame.height ?? 0
+        window.setFrameTopLeftPoint(NSPoint(x: point.x, y: height - point.y))
+    }
+
+    // Hides Loungy's own window and hands the focus back, without hiding other windows
+    func hideLauncher() {
+        NSApp.windows.first(where: { $0.title == "Loungy" })?.orderOut(nil)
+        NSApp.deactivate()
+    }
+
+    // Accessibility uses a flipped coordinate space anchored at the top of the primary screen
+    private func flip(_ rect: CGRect) -> CGRect {
+        let height = NSScreen.screens.first?.frame.height ?? 0
+        var flipped = rect
+        flipped.origin.y = height - rect.maxY
+        return flipped
+    }
+
+    private func frame(of window: AXUIElement) -> CGRect? {
+        guard let positionValue = window.attribute(for: kAXPositionAttribute),
+              let sizeValue = window.attribute(for: kAXSizeAttribute)
+        else {
+            return nil
+        }
+        var position = CGPoint.zero
+        var size = CGSize.zero
+        AXValueGetValue(positionValue as! AXValue, .cgPoint, &position)
+        AXValueGetValue(sizeValue as! AXValue, .cgSize, &size)
+
+        return flip(CGRect(origin: position, size: size))
+    }
+
+    private func setFrame(of window: AXUIElement, to rect: CGRect) {
+        var position = flip(rect).origin
+        var size = rect.size
+        if let value = AXValueCreate(.cgPoint, &position) {
+            AXUIElementSetAttributeValue(window, kAXPositionAttribute as CFString, value)
+        }
+        if let value = AXValueCreate(.cgSize, &size) {
+            AXUIElementSetAttributeValue(window, kAXSizeAttribute as CFString, value)
+        }
+        // Some apps clamp the size depending on the position, so apply the position once more
+        if let value = AXValueCreate(.cgPoint, &position) {
+            AXUIElementSetAttributeValue(window, kAXPositionAttribute as CFString, value)
+        }
+    }
+}
@@ -28,7 +28,11 @@ func enableAccessibilityFeatures() {
 final class Library {
     let apps: Apps
     let autofill: AutoFill
+    let definitions: Definitions
+    let keychain: Keychain
+    let notifications: Notifications
     let ocr: OCR
+    let windowManager: WindowManager
 
     static let shared = Library()
 
@@ -36,7 +40,11 @@ final class Library {
         enableAccessibilityFeatures()
         apps = Apps()
         autofill = AutoFill()
+        definitions = Definitions()
+        keychain = Keychain()
+        notifications = Notifications()
         ocr = OCR()
+        windowManager = WindowManager()
     }
 }
 
@@ -45,7 +53,11 @@ final class Library {
 let library = Library.shared
 let apps = library.apps
 let autofill = library.autofill
+let definitions = library.definitions
+let keychain = library.keychain
+let notifications = library.notifications
 let ocr = library.ocr
+let windowManager = library.windowManager
 
 // MARK: - Apps Public Methods
 
@@ -88,6 +100,14 @@ func paste(value: SRString, formatting: Bool) {
     )
 }
 
+@_cdecl("paste_html")
+func pasteHtml(html: SRString, text: SRString) {
+    autofill.pasteHtml(
+        html: html.toString(),
+        text: text.toString()
+    )
+}
+
 @_cdecl("copy_file")
 func copyFile(for path: SRString) {
     autofill.copyFile(
@@ -109,6 +129,39 @@ func simulatePasteEvent(formatting: Bool = true) {
     )
 }
 
+// MARK: - Keychain Public Methods
+
+@_cdecl("keychain_set")
+public func keychainSet(service: SRString, account: SRString, secret: SRString) -> Bool {
+    keychain.set(
+        service: service.toString(),
+        account: account.toString(),
+        secret: secret.toString()
+    )
+}
+
+@_cdecl("keychain_get")
+public func keychainGet(service: SRString, account: SRString, reason: SRString) -> SRString? {
+    guard let secret = keychain.get(
+        service: service.toString(),
+        account: account.toString(),
+        reason: reason.toString()
+    )
+    else {
+        return nil
+    }
+
+    return SRString(secret)
+}
+
+@_cdecl("keychain_delete")
+public func keychainDelete(service: SRString, account: SRString) -> Bool {
+    keychain.delete(
+        service: service.toString(),
+        account: account.toString()
+    )
+}
+
 // MARK: - MenuBar Public Methods
 
 @_cdecl("menu_items")
@@ -144,6 +197,44 @@ public func selectMenuItem(data: SRData) {
     }
 }
 
+// MARK: - Definitions Public Methods
+
+@_cdecl("define_word")
+public func defineWord(word: SRString) -> SRString? {
+    guard let definition = definitions.define(word.toString()) else {
+        return nil
+    }
+
+    return SRString(definition)
+}
+
+// MARK: - Notifications Public Methods
+
+@_cdecl("send_notification")
+public func sendNotification(id: SRString, title: SRString, body: SRString) {
+    notifications.send(
+        id: id.toString(),
+        title: title.toString(),
+        body: body.toString()
+    )
+}
+
+@_cdecl("clicked_notification")
+public func clickedNotification() -> SRString? {
+    guard let id = notifications.nextClicked() else {
+        return nil
+    }
+
+    return SRString(id)
+}
+
+// MARK: - Accessibility Public Methods
+
+@_cdecl("accessibility_trusted")
+public func accessibilityTrusted() -> Bool {
+    AXIsProcessTrusted()
+}
+
 // MARK: - OCR Public Methods
 
 @_cdecl("ocr")
@@ -152,3 +243,34 @@ public func readText(path: SRString) {
         from: path.toString()
     )
 }
+
+// MARK: - WindowManager Public Methods
+
+@_cdecl("window_move")
+public func moveWindow(position: SRString) -> Int {
+    windowManager.move(
+        to: position.toString()
+    ).rawValue
+}
+
+@_cdecl("cursor_x")
+public func cursorX() -> Double {
+    windowManager.cursor().x
+}
+
+@_cdecl("cursor_y")
+public func cursorY() -> Double {
+    windowManager.cursor().y
+}
+
+@_cdecl("launcher_move")
+public func moveLauncher(x: Double, y: Double) {
+    windowManager.moveLauncher(
+        to: CGPoint(x: x, y: y)
+    )
+}
+
+@_cdecl("launcher_hide")
+public func hideLauncher() {
+    windowManager.hideLauncher()
+}